@@ -0,0 +1,36 @@
+use crate::db::AppData;
+use crate::filecache::FileCacheStats;
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use dorsal::DefaultReturn;
+
+use crate::pages::base;
+
+#[get("/api/v1/admin/cache")]
+/// Get the hit rate and memory usage of the in-process file cache ([`crate::filecache::FileCache`])
+pub async fn cache_stats_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    if token_user.is_none() {
+        return HttpResponse::NotAcceptable().body("An account is required to view cache stats.");
+    }
+
+    let user = token_user.unwrap().payload.unwrap();
+
+    if !user.level.permissions.contains(&"VIB:Admin".to_string()) {
+        return HttpResponse::NotAcceptable().body("You are not allowed to view cache stats.");
+    }
+
+    // return
+    return HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(
+            serde_json::to_string::<DefaultReturn<Option<FileCacheStats>>>(&DefaultReturn {
+                success: true,
+                message: String::from("Cache stats"),
+                payload: Option::Some(data.db.file_cache.stats()),
+            })
+            .unwrap(),
+        );
+}
@@ -1,10 +1,14 @@
-use crate::db::{AppData, PCreateProject, PEditFieldsByName, ProjectMetadata};
-use actix_files::file_extension_to_mime;
+use crate::db::{AppData, FileOperation, PCreateProject, PEditFieldsByName, ProjectMetadata};
+use actix_web::http::StatusCode;
 use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use base64::Engine;
 use dorsal::DefaultReturn;
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 
 use crate::pages::base;
+use crate::serve;
+use crate::uploads;
 
 #[post("/api/v1/projects")]
 /// Create a new project ([`crate::db::Database::create_project`])
@@ -219,6 +223,7 @@ pub async fn read_file_request(req: HttpRequest, data: web::Data<AppData>) -> im
             } else {
                 Option::None
             },
+            serve::share::token_from_request(&req),
             false,
             false,
         )
@@ -231,24 +236,32 @@ pub async fn read_file_request(req: HttpRequest, data: web::Data<AppData>) -> im
             .body(res.message);
     }
 
-    // incr project requests
-    data.db
-        .incr_project_requests(project_name.to_string())
-        .await;
-
-    // get file extension from path
-    let ext = res
-        .message
-        .split(".")
-        .collect::<Vec<&str>>()
-        .pop()
-        .unwrap_or("txt");
+    // get project (for response headers)
+    let project = data.db.get_project_by_id(project_name.to_string()).await;
+    let project = project.payload.unwrap();
+
+    let mut response = serve::serve_file(
+        &req,
+        &data.db,
+        &project,
+        res.message,
+        res.payload.unwrap(),
+        set_cookie,
+    )
+    .await;
+
+    // files on the app origin can't run scripts
+    serve::security::sandbox(response.headers_mut());
+
+    // incr project requests (revalidated files aren't billed)
+    if response.status() != StatusCode::NOT_MODIFIED {
+        data.db
+            .incr_project_requests(project_name.to_string())
+            .await;
+    }
 
     // return
-    return HttpResponse::Ok()
-        .append_header(("Content-Type", file_extension_to_mime(ext)))
-        .append_header(("Set-Cookie", set_cookie))
-        .body(res.payload.unwrap());
+    return response;
 }
 
 #[get("/{name:.*}")]
@@ -260,21 +273,38 @@ pub async fn read_project_global_request(
     let project_name = req.match_info().get("name").unwrap();
     let path = "/index.html";
 
+    // projects are served from the user-content domain when there is one
+    let location = serve::security::content_url(project_name, "/", req.query_string());
+
+    if serve::security::has_content_host()
+        && location.is_some()
+        && data
+            .db
+            .get_project_by_id(project_name.to_string())
+            .await
+            .success
+    {
+        return serve::redirect(StatusCode::FOUND, location.unwrap());
+    }
+
     // verify auth status
     let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
 
+    let as_user = if token_user.is_some() {
+        let user = token_user.unwrap().payload.unwrap();
+        Option::Some(user.user.username)
+    } else {
+        Option::None
+    };
+
     // ...
     let res = data
         .db
         .get_file_in_project(
             project_name.to_string(),
             path.to_string(),
-            if token_user.is_some() {
-                let user = token_user.unwrap().payload.unwrap();
-                Option::Some(user.user.username)
-            } else {
-                Option::None
-            },
+            as_user.clone(),
+            serve::share::token_from_request(&req),
             false,
             false,
         )
@@ -284,24 +314,54 @@ pub async fn read_project_global_request(
         return crate::pages::errors::error404(req, data).await;
     }
 
-    // incr project requests
-    data.db
-        .incr_project_requests(project_name.to_string())
-        .await;
+    // get project (for response headers)
+    let project = data.db.get_project_by_id(project_name.to_string()).await;
+    let project = project.payload.unwrap();
+
+    // maintenance mode
+    let maintenance = &project.metadata.maintenance;
+
+    if maintenance.enabled {
+        let can_bypass = as_user
+            .as_ref()
+            .is_some_and(|u| maintenance.allowed_users.contains(u))
+            || data.db.can_manage_project(&project, as_user.clone()).await;
+
+        if serve::maintenance::is_down(maintenance, path, can_bypass) {
+            let mut response = serve::maintenance::response(
+                &data.db,
+                &project,
+                &serve::security::site_host().unwrap_or_default(),
+            )
+            .await;
+
+            serve::security::sandbox(response.headers_mut());
+            return response;
+        }
+    }
 
-    // get file extension from path
-    let ext = res
-        .message
-        .split(".")
-        .collect::<Vec<&str>>()
-        .pop()
-        .unwrap_or("txt");
+    let mut response = serve::serve_file(
+        &req,
+        &data.db,
+        &project,
+        res.message,
+        res.payload.unwrap(),
+        set_cookie,
+    )
+    .await;
+
+    // files on the app origin can't run scripts
+    serve::security::sandbox(response.headers_mut());
+
+    // incr project requests (revalidated files aren't billed)
+    if response.status() != StatusCode::NOT_MODIFIED {
+        data.db
+            .incr_project_requests(project_name.to_string())
+            .await;
+    }
 
     // return
-    return HttpResponse::Ok()
-        .append_header(("Content-Type", file_extension_to_mime(ext)))
-        .append_header(("Set-Cookie", set_cookie))
-        .body(res.payload.unwrap());
+    return response;
 }
 
 #[get("/{name:.*}/{path:.*}")]
@@ -313,21 +373,38 @@ pub async fn read_file_global_request(
     let project_name = req.match_info().get("name").unwrap();
     let path = req.match_info().get("path").unwrap();
 
+    // projects are served from the user-content domain when there is one
+    let location = serve::security::content_url(project_name, path, req.query_string());
+
+    if serve::security::has_content_host()
+        && location.is_some()
+        && data
+            .db
+            .get_project_by_id(project_name.to_string())
+            .await
+            .success
+    {
+        return serve::redirect(StatusCode::FOUND, location.unwrap());
+    }
+
     // verify auth status
     let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
 
+    let as_user = if token_user.is_some() {
+        let user = token_user.unwrap().payload.unwrap();
+        Option::Some(user.user.username)
+    } else {
+        Option::None
+    };
+
     // ...
     let res = data
         .db
         .get_file_in_project(
             project_name.to_string(),
             path.to_string(),
-            if token_user.is_some() {
-                let user = token_user.unwrap().payload.unwrap();
-                Option::Some(user.user.username)
-            } else {
-                Option::None
-            },
+            as_user.clone(),
+            serve::share::token_from_request(&req),
             false,
             false,
         )
@@ -337,33 +414,245 @@ pub async fn read_file_global_request(
         return crate::pages::errors::error404(req, data).await;
     }
 
-    // incr project requests
-    data.db
-        .incr_project_requests(project_name.to_string())
-        .await;
+    // get project (for response headers)
+    let project = data.db.get_project_by_id(project_name.to_string()).await;
+    let project = project.payload.unwrap();
+
+    // maintenance mode
+    let maintenance = &project.metadata.maintenance;
+
+    if maintenance.enabled {
+        let can_bypass = as_user
+            .as_ref()
+            .is_some_and(|u| maintenance.allowed_users.contains(u))
+            || data.db.can_manage_project(&project, as_user.clone()).await;
+
+        if serve::maintenance::is_down(maintenance, &format!("/{path}"), can_bypass) {
+            let mut response = serve::maintenance::response(
+                &data.db,
+                &project,
+                &serve::security::site_host().unwrap_or_default(),
+            )
+            .await;
+
+            serve::security::sandbox(response.headers_mut());
+            return response;
+        }
+    }
 
-    // get file extension from path
-    let ext = res
-        .message
-        .split(".")
-        .collect::<Vec<&str>>()
-        .pop()
-        .unwrap_or("txt");
+    let mut response = serve::serve_file(
+        &req,
+        &data.db,
+        &project,
+        res.message,
+        res.payload.unwrap(),
+        set_cookie,
+    )
+    .await;
+
+    // files on the app origin can't run scripts
+    serve::security::sandbox(response.headers_mut());
+
+    // incr project requests (revalidated files aren't billed)
+    if response.status() != StatusCode::NOT_MODIFIED {
+        data.db
+            .incr_project_requests(project_name.to_string())
+            .await;
+    }
 
     // return
-    return HttpResponse::Ok()
-        .append_header(("Content-Type", file_extension_to_mime(ext)))
-        .append_header(("Set-Cookie", set_cookie))
-        .body(res.payload.unwrap());
+    return response;
 }
 
-#[post("/api/v1/project/{name:.*}/files/{path:.*}")]
-/// Insert a file into a project
-pub async fn insert_file_request(
+/// Largest request body the file API accepts, larger files are uploaded in chunks (see [`crate::uploads`])
+pub const MAX_FILE_BODY_SIZE: usize = 1_048_576;
+
+/// Check if a request body is a `multipart/form-data` form
+fn is_multipart(req: &HttpRequest) -> bool {
+    req.headers()
+        .get("Content-Type")
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|h| h.to_lowercase().starts_with("multipart/form-data"))
+}
+
+/// Read a request body, failing once more than `limit` bytes have been `read`
+async fn read_body<E: std::fmt::Debug>(
+    mut body: impl futures_util::Stream<Item = Result<web::Bytes, E>> + Unpin,
+    read: &mut usize,
+    limit: usize,
+) -> Result<Vec<u8>, (StatusCode, String)> {
+    let mut content: Vec<u8> = Vec::new();
+
+    while let Some(bytes) = body.next().await {
+        if bytes.is_err() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                String::from("Failed to read body."),
+            ));
+        }
+
+        let bytes = bytes.unwrap();
+        *read += bytes.len();
+
+        if *read > limit {
+            return Err((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                String::from("Payload is too large."),
+            ));
+        }
+
+        content.extend_from_slice(&bytes);
+    }
+
+    Ok(content)
+}
+
+/// Field names of form files that are stored at their file name instead (see [`read_files`])
+const GENERIC_FIELD_NAMES: &[&str] = &["file", "files", "file[]", "files[]", "upload", "uploads"];
+
+/// Read the files sent to the file API, as `(path, base64 content)`
+///
+/// * `application/json` - a [`PAddFile`], stored at `path`
+/// * `multipart/form-data` - every file in the form, stored at `path` joined with its field name,
+///   or its file name when the field name is empty or generic (see [`GENERIC_FIELD_NAMES`])
+/// * anything else (`application/octet-stream`) - the raw body, stored at `path`
+///
+/// Every path is checked with [`uploads::check_file_path`], and a form can't have two files with
+/// the same path.
+///
+/// # Arguments:
+/// * `req` - the request
+/// * `body` - the request body
+/// * `path` - file path from the URL
+async fn read_files(
+    req: &HttpRequest,
+    body: web::Payload,
+    path: &str,
+) -> Result<Vec<(String, String)>, (StatusCode, String)> {
+    // check size
+    let content_length = req
+        .headers()
+        .get("Content-Length")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.parse::<usize>().ok());
+
+    if content_length.is_some_and(|l| l > MAX_FILE_BODY_SIZE) {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            String::from("Payload is too large."),
+        ));
+    }
+
+    let mut read: usize = 0;
+
+    // form
+    if is_multipart(req) {
+        let mut form = actix_multipart::Multipart::new(req.headers(), body);
+        let mut files: Vec<(String, String)> = Vec::new();
+
+        while let Some(field) = form.next().await {
+            if field.is_err() {
+                return Err((StatusCode::BAD_REQUEST, field.err().unwrap().to_string()));
+            }
+
+            let mut field = field.unwrap();
+
+            // only files are stored (fields without a file name are ignored)
+            let Some(disposition) = field.content_disposition() else {
+                continue;
+            };
+
+            let Some(filename) = disposition.get_filename() else {
+                continue;
+            };
+
+            let name = disposition
+                .get_name()
+                .filter(|n| !n.is_empty() && !GENERIC_FIELD_NAMES.contains(n))
+                .unwrap_or(filename)
+                .to_string();
+
+            let dir = path.trim_matches('/');
+            let file_path = if dir.is_empty() {
+                format!("/{}", name.trim_start_matches("/"))
+            } else {
+                format!("/{}/{}", dir, name.trim_start_matches("/"))
+            };
+
+            if let Err(e) = uploads::check_file_path(&file_path) {
+                return Err((StatusCode::BAD_REQUEST, e));
+            }
+
+            if files.iter().any(|(p, _)| p == &file_path) {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("The form has more than one file at \"{file_path}\"."),
+                ));
+            }
+
+            let content = read_body(&mut field, &mut read, MAX_FILE_BODY_SIZE).await?;
+
+            files.push((
+                file_path,
+                base64::engine::general_purpose::STANDARD.encode(content),
+            ));
+        }
+
+        if files.is_empty() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                String::from("The form doesn't contain any files."),
+            ));
+        }
+
+        return Ok(files);
+    }
+
+    // single file
+    let file_path = format!("/{}", path.trim_start_matches("/"));
+
+    if let Err(e) = uploads::check_file_path(&file_path) {
+        return Err((StatusCode::BAD_REQUEST, e));
+    }
+
+    let is_json = req
+        .headers()
+        .get("Content-Type")
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|h| h.to_lowercase().starts_with("application/json"));
+
+    let content = read_body(body, &mut read, MAX_FILE_BODY_SIZE).await?;
+
+    let content = if is_json {
+        let file = serde_json::from_slice::<PAddFile>(&content);
+
+        if file.is_err() {
+            return Err((StatusCode::BAD_REQUEST, file.err().unwrap().to_string()));
+        }
+
+        file.unwrap().content
+    } else {
+        base64::engine::general_purpose::STANDARD.encode(content)
+    };
+
+    Ok(vec![(file_path, content)])
+}
+
+/// Store the files sent to the file API, creating (or updating) each of them
+///
+/// Forms get a result for every file, other requests get the result of their file.
+///
+/// # Arguments:
+/// * `req` - the request
+/// * `body` - the request body (see [`read_files`])
+/// * `data` - [`AppData`]
+/// * `update` - update existing files instead of creating them
+async fn store_files(
     req: HttpRequest,
-    body: web::Json<PAddFile>,
+    body: web::Payload,
     data: web::Data<AppData>,
-) -> impl Responder {
+    update: bool,
+) -> HttpResponse {
     let project_name = req.match_info().get("name").unwrap();
     let path = req.match_info().get("path").unwrap();
 
@@ -374,52 +663,90 @@ pub async fn insert_file_request(
         return HttpResponse::NotAcceptable().body("An account is required to edit projects.");
     }
 
-    // check size
-    // file must be less than or equal to 1 MB
-    let content_length = req.headers().get("Content-Length");
-
-    if content_length.is_none()
-        | (std::str::from_utf8(content_length.unwrap().as_bytes())
-            .unwrap()
-            .parse::<usize>()
-            .unwrap()
-            > 1_048_576)
-    {
-        return HttpResponse::PayloadTooLarge()
+    let username = token_user.unwrap().payload.unwrap().user.username;
+
+    // read files
+    let files = read_files(&req, body, path).await;
+
+    if files.is_err() {
+        let (status, message) = files.err().unwrap();
+
+        return HttpResponse::build(status)
             .append_header(("Content-Type", "text/plain"))
             .append_header(("Set-Cookie", set_cookie))
-            .body("Payload is too large.");
+            .body(message);
     }
 
     // ...
-    let res = data
-        .db
-        .store_file_in_project(
-            project_name.to_string(),
-            path.to_string(),
-            body.content.clone(),
-            if token_user.is_some() {
-                Option::Some(token_user.unwrap().payload.unwrap().user.username)
-            } else {
-                Option::None
-            },
-        )
-        .await;
+    let mut results: Vec<DefaultReturn<Option<String>>> = Vec::new();
+
+    for (path, content) in files.unwrap() {
+        let res = if update {
+            data.db
+                .update_file_in_project(
+                    project_name.to_string(),
+                    path,
+                    content,
+                    Option::Some(username.clone()),
+                )
+                .await
+        } else {
+            data.db
+                .store_file_in_project(
+                    project_name.to_string(),
+                    path,
+                    content,
+                    Option::Some(username.clone()),
+                )
+                .await
+        };
+
+        results.push(res);
+    }
+
+    let body = if is_multipart(&req) {
+        let stored = results.iter().filter(|r| r.success).count();
+
+        serde_json::to_string(&DefaultReturn {
+            success: stored == results.len(),
+            message: format!("Stored {} of {} files", stored, results.len()),
+            payload: results,
+        })
+        .unwrap()
+    } else {
+        serde_json::to_string(&results.remove(0)).unwrap()
+    };
 
     // return
     return HttpResponse::Ok()
         .append_header(("Content-Type", "application/json"))
         .append_header(("Set-Cookie", set_cookie))
-        .body(serde_json::to_string(&res).unwrap());
+        .body(body);
+}
+
+#[post("/api/v1/project/{name:.*}/files/{path:.*}")]
+/// Insert a file into a project (JSON, a raw body, or many files in a form, see [`read_files`])
+pub async fn insert_file_request(
+    req: HttpRequest,
+    body: web::Payload,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    store_files(req, body, data, false).await
 }
 
 #[put("/api/v1/project/{name:.*}/files/{path:.*}")]
-/// Update a file in a project
+/// Update a file in a project (JSON, a raw body, or many files in a form, see [`read_files`])
 pub async fn update_file_request(
     req: HttpRequest,
-    body: web::Json<PAddFile>,
+    body: web::Payload,
     data: web::Data<AppData>,
 ) -> impl Responder {
+    store_files(req, body, data, true).await
+}
+
+#[delete("/api/v1/project/{name:.*}/files/{path:.*}")]
+/// Delete a file from a project
+pub async fn delete_file_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
     let project_name = req.match_info().get("name").unwrap();
     let path = req.match_info().get("path").unwrap();
 
@@ -430,30 +757,12 @@ pub async fn update_file_request(
         return HttpResponse::NotAcceptable().body("An account is required to edit projects.");
     }
 
-    // check size
-    // file must be less than or equal to 1 MB
-    let content_length = req.headers().get("Content-Length");
-
-    if content_length.is_none()
-        | (std::str::from_utf8(content_length.unwrap().as_bytes())
-            .unwrap()
-            .parse::<usize>()
-            .unwrap()
-            > 1_048_576)
-    {
-        return HttpResponse::PayloadTooLarge()
-            .append_header(("Content-Type", "text/plain"))
-            .append_header(("Set-Cookie", set_cookie))
-            .body("Payload is too large.");
-    }
-
     // ...
     let res = data
         .db
-        .update_file_in_project(
+        .delete_file_in_project(
             project_name.to_string(),
             path.to_string(),
-            body.content.clone(),
             if token_user.is_some() {
                 Option::Some(token_user.unwrap().payload.unwrap().user.username)
             } else {
@@ -469,9 +778,18 @@ pub async fn update_file_request(
         .body(serde_json::to_string(&res).unwrap());
 }
 
-#[delete("/api/v1/project/{name:.*}/files/{path:.*}")]
-/// Delete a file from a project
-pub async fn delete_file_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PMoveFile {
+    pub path: String,
+}
+
+#[post("/api/v1/project/{name:.*}/files:mv/{path:.*}")]
+/// Move a file in a project
+pub async fn move_file_request(
+    req: HttpRequest,
+    body: web::Json<PMoveFile>,
+    data: web::Data<AppData>,
+) -> impl Responder {
     let project_name = req.match_info().get("name").unwrap();
     let path = req.match_info().get("path").unwrap();
 
@@ -485,9 +803,10 @@ pub async fn delete_file_request(req: HttpRequest, data: web::Data<AppData>) ->
     // ...
     let res = data
         .db
-        .delete_file_in_project(
+        .move_file_in_project(
             project_name.to_string(),
             path.to_string(),
+            body.path.clone(),
             if token_user.is_some() {
                 Option::Some(token_user.unwrap().payload.unwrap().user.username)
             } else {
@@ -503,20 +822,22 @@ pub async fn delete_file_request(req: HttpRequest, data: web::Data<AppData>) ->
         .body(serde_json::to_string(&res).unwrap());
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-pub struct PMoveFile {
-    pub path: String,
+/// Largest request body a file batch can be (10 MiB)
+pub const MAX_FILE_BATCH_BODY_SIZE: usize = 10_485_760;
+
+#[derive(Serialize, Deserialize)]
+pub struct PFileBatch {
+    pub operations: Vec<FileOperation>,
 }
 
-#[post("/api/v1/project/{name:.*}/files:mv/{path:.*}")]
-/// Move a file in a project
-pub async fn move_file_request(
+#[post("/api/v1/project/{name:.*}/files:batch")]
+/// Apply a batch of file operations to a project, all or nothing (see [`crate::db::Database::apply_file_batch`])
+pub async fn file_batch_request(
     req: HttpRequest,
-    body: web::Json<PMoveFile>,
+    body: web::Payload,
     data: web::Data<AppData>,
 ) -> impl Responder {
     let project_name = req.match_info().get("name").unwrap();
-    let path = req.match_info().get("path").unwrap();
 
     // verify auth status
     let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
@@ -525,18 +846,35 @@ pub async fn move_file_request(
         return HttpResponse::NotAcceptable().body("An account is required to edit projects.");
     }
 
+    // read body
+    let mut read: usize = 0;
+    let body = read_body(body, &mut read, MAX_FILE_BATCH_BODY_SIZE).await;
+
+    if body.is_err() {
+        let (status, message) = body.err().unwrap();
+
+        return HttpResponse::build(status)
+            .append_header(("Content-Type", "text/plain"))
+            .append_header(("Set-Cookie", set_cookie))
+            .body(message);
+    }
+
+    let batch = serde_json::from_slice::<PFileBatch>(&body.unwrap());
+
+    if batch.is_err() {
+        return HttpResponse::BadRequest()
+            .append_header(("Content-Type", "text/plain"))
+            .append_header(("Set-Cookie", set_cookie))
+            .body(batch.err().unwrap().to_string());
+    }
+
     // ...
     let res = data
         .db
-        .move_file_in_project(
+        .apply_file_batch(
             project_name.to_string(),
-            path.to_string(),
-            body.path.clone(),
-            if token_user.is_some() {
-                Option::Some(token_user.unwrap().payload.unwrap().user.username)
-            } else {
-                Option::None
-            },
+            batch.unwrap().operations,
+            Option::Some(token_user.unwrap().payload.unwrap().user.username),
         )
         .await;
 
@@ -574,3 +912,555 @@ pub async fn favorite_request(req: HttpRequest, data: web::Data<AppData>) -> imp
         .append_header(("Set-Cookie", set_cookie))
         .body(serde_json::to_string(&res).unwrap());
 }
+
+#[get("/api/v1/project/{name:.*}/shares")]
+/// Get a project's share links
+pub async fn get_share_links_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let project_name = req.match_info().get("name").unwrap();
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    if token_user.is_none() {
+        return HttpResponse::NotAcceptable().body("An account is required to manage share links.");
+    }
+
+    // ...
+    let res = data
+        .db
+        .get_share_links(
+            project_name.to_string(),
+            Option::Some(token_user.unwrap().payload.unwrap().user.username),
+        )
+        .await;
+
+    // return
+    return HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap());
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PCreateShareLink {
+    /// path scope, a directory (ending with `/`) or a single file
+    pub path: String,
+    /// how long (in ms) the link is valid for
+    pub duration: u128,
+}
+
+#[post("/api/v1/project/{name:.*}/shares")]
+/// Create a share link for a project
+pub async fn create_share_link_request(
+    req: HttpRequest,
+    body: web::Json<PCreateShareLink>,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    let project_name = req.match_info().get("name").unwrap();
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    if token_user.is_none() {
+        return HttpResponse::NotAcceptable().body("An account is required to manage share links.");
+    }
+
+    // ...
+    let res = data
+        .db
+        .create_share_link(
+            project_name.to_string(),
+            body.path.clone(),
+            body.duration,
+            Option::Some(token_user.unwrap().payload.unwrap().user.username),
+        )
+        .await;
+
+    // return
+    return HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap());
+}
+
+#[post("/api/v1/project/{name:.*}/shares:rotate")]
+/// Revoke every share link of a project by rotating its share key
+pub async fn rotate_share_key_request(
+    req: HttpRequest,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    let project_name = req.match_info().get("name").unwrap();
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    if token_user.is_none() {
+        return HttpResponse::NotAcceptable().body("An account is required to manage share links.");
+    }
+
+    // ...
+    let res = data
+        .db
+        .rotate_share_key(
+            project_name.to_string(),
+            Option::Some(token_user.unwrap().payload.unwrap().user.username),
+        )
+        .await;
+
+    // return
+    return HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap());
+}
+
+#[delete("/api/v1/project/{name:.*}/shares/{id}")]
+/// Revoke a share link
+pub async fn revoke_share_link_request(
+    req: HttpRequest,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    let project_name = req.match_info().get("name").unwrap();
+    let id = req.match_info().get("id").unwrap();
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    if token_user.is_none() {
+        return HttpResponse::NotAcceptable().body("An account is required to manage share links.");
+    }
+
+    // ...
+    let res = data
+        .db
+        .revoke_share_link(
+            project_name.to_string(),
+            id.to_string(),
+            Option::Some(token_user.unwrap().payload.unwrap().user.username),
+        )
+        .await;
+
+    // return
+    return HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap());
+}
+
+#[get("/api/v1/project/{name:.*}/previews")]
+/// Get a project's previews
+pub async fn get_previews_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let project_name = req.match_info().get("name").unwrap();
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    if token_user.is_none() {
+        return HttpResponse::NotAcceptable().body("An account is required to manage previews.");
+    }
+
+    // ...
+    let res = data
+        .db
+        .get_project_previews(
+            project_name.to_string(),
+            Option::Some(token_user.unwrap().payload.unwrap().user.username),
+        )
+        .await;
+
+    // return
+    return HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap());
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PCreatePreview {
+    /// preview name, the preview is served at `{name}--{project}`
+    pub name: String,
+    /// make the preview's files private
+    #[serde(default)]
+    pub private: bool,
+}
+
+#[post("/api/v1/project/{name:.*}/previews")]
+/// Create a preview of a project
+pub async fn create_preview_request(
+    req: HttpRequest,
+    body: web::Json<PCreatePreview>,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    let project_name = req.match_info().get("name").unwrap();
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    if token_user.is_none() {
+        return HttpResponse::NotAcceptable().body("An account is required to manage previews.");
+    }
+
+    // ...
+    let res = data
+        .db
+        .create_preview(
+            project_name.to_string(),
+            body.name.clone(),
+            body.private,
+            Option::Some(token_user.unwrap().payload.unwrap().user.username),
+        )
+        .await;
+
+    // return
+    return HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap());
+}
+
+#[post("/api/v1/project/{name:.*}/previews/{preview}:promote")]
+/// Promote a preview to production
+pub async fn promote_preview_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let project_name = req.match_info().get("name").unwrap();
+    let preview = req.match_info().get("preview").unwrap();
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    if token_user.is_none() {
+        return HttpResponse::NotAcceptable().body("An account is required to manage previews.");
+    }
+
+    // ...
+    let res = data
+        .db
+        .promote_preview(
+            project_name.to_string(),
+            preview.to_string(),
+            Option::Some(token_user.unwrap().payload.unwrap().user.username),
+        )
+        .await;
+
+    // return
+    return HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap());
+}
+
+#[delete("/api/v1/project/{name:.*}/previews/{preview}")]
+/// Delete a preview
+pub async fn delete_preview_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let project_name = req.match_info().get("name").unwrap();
+    let preview = req.match_info().get("preview").unwrap();
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    if token_user.is_none() {
+        return HttpResponse::NotAcceptable().body("An account is required to manage previews.");
+    }
+
+    // ...
+    let res = data
+        .db
+        .delete_preview(
+            project_name.to_string(),
+            preview.to_string(),
+            Option::Some(token_user.unwrap().payload.unwrap().user.username),
+        )
+        .await;
+
+    // return
+    return HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap());
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PCreateUpload {
+    /// path the file is stored at once the upload is finished
+    pub path: String,
+    /// file size (in bytes)
+    pub size: usize,
+    /// size of every chunk but the last (in bytes)
+    #[serde(default)]
+    pub chunk_size: Option<usize>,
+}
+
+#[post("/api/v1/project/{name:.*}/uploads")]
+/// Start a chunked upload ([`crate::uploads`])
+pub async fn create_upload_request(
+    req: HttpRequest,
+    body: web::Json<PCreateUpload>,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    let project_name = req.match_info().get("name").unwrap();
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    if token_user.is_none() {
+        return HttpResponse::NotAcceptable().body("An account is required to edit projects.");
+    }
+
+    // ...
+    let res = data
+        .db
+        .create_upload(
+            project_name.to_string(),
+            body.path.clone(),
+            body.size,
+            body.chunk_size,
+            Option::Some(token_user.unwrap().payload.unwrap().user.username),
+        )
+        .await;
+
+    // return
+    return HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap());
+}
+
+#[get("/api/v1/project/{name:.*}/uploads/{id}")]
+/// Get a chunked upload and the chunks it has received so far
+pub async fn get_upload_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let project_name = req.match_info().get("name").unwrap();
+    let id = req.match_info().get("id").unwrap();
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    if token_user.is_none() {
+        return HttpResponse::NotAcceptable().body("An account is required to edit projects.");
+    }
+
+    // ...
+    let res = data
+        .db
+        .get_upload(
+            project_name.to_string(),
+            id.to_string(),
+            Option::Some(token_user.unwrap().payload.unwrap().user.username),
+        )
+        .await;
+
+    // return
+    return HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap());
+}
+
+#[put("/api/v1/project/{name:.*}/uploads/{id}/chunks/{number}")]
+/// Store a chunk of a chunked upload (the request body is the raw chunk)
+pub async fn upload_chunk_request(
+    req: HttpRequest,
+    mut body: web::Payload,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    let project_name = req.match_info().get("name").unwrap();
+    let id = req.match_info().get("id").unwrap();
+    let number = req.match_info().get("number").unwrap().parse::<usize>();
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    if token_user.is_none() {
+        return HttpResponse::NotAcceptable().body("An account is required to edit projects.");
+    }
+
+    if number.is_err() {
+        return HttpResponse::BadRequest()
+            .append_header(("Content-Type", "text/plain"))
+            .append_header(("Set-Cookie", set_cookie))
+            .body("Chunk number is invalid.");
+    }
+
+    // read chunk
+    let mut content = web::BytesMut::new();
+
+    while let Some(bytes) = body.next().await {
+        if bytes.is_err() {
+            return HttpResponse::BadRequest()
+                .append_header(("Content-Type", "text/plain"))
+                .append_header(("Set-Cookie", set_cookie))
+                .body("Failed to read chunk.");
+        }
+
+        let bytes = bytes.unwrap();
+
+        if content.len() + bytes.len() > crate::uploads::MAX_CHUNK_SIZE {
+            return HttpResponse::PayloadTooLarge()
+                .append_header(("Content-Type", "text/plain"))
+                .append_header(("Set-Cookie", set_cookie))
+                .body("Payload is too large.");
+        }
+
+        content.extend_from_slice(&bytes);
+    }
+
+    // ...
+    let res = data
+        .db
+        .store_upload_chunk(
+            project_name.to_string(),
+            id.to_string(),
+            number.unwrap(),
+            &content,
+            Option::Some(token_user.unwrap().payload.unwrap().user.username),
+        )
+        .await;
+
+    // return
+    return HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap());
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PFinishUpload {
+    /// SHA-256 of the whole file (hex)
+    pub sha256: String,
+}
+
+#[post("/api/v1/project/{name:.*}/uploads/{id}:finish")]
+/// Finish a chunked upload, storing the file
+pub async fn finish_upload_request(
+    req: HttpRequest,
+    body: web::Json<PFinishUpload>,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    let project_name = req.match_info().get("name").unwrap();
+    let id = req.match_info().get("id").unwrap();
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    if token_user.is_none() {
+        return HttpResponse::NotAcceptable().body("An account is required to edit projects.");
+    }
+
+    // ...
+    let res = data
+        .db
+        .finish_upload(
+            project_name.to_string(),
+            id.to_string(),
+            body.sha256.clone(),
+            Option::Some(token_user.unwrap().payload.unwrap().user.username),
+        )
+        .await;
+
+    // return
+    return HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap());
+}
+
+#[delete("/api/v1/project/{name:.*}/uploads/{id}")]
+/// Cancel a chunked upload
+pub async fn delete_upload_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let project_name = req.match_info().get("name").unwrap();
+    let id = req.match_info().get("id").unwrap();
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    if token_user.is_none() {
+        return HttpResponse::NotAcceptable().body("An account is required to edit projects.");
+    }
+
+    // ...
+    let res = data
+        .db
+        .delete_upload(
+            project_name.to_string(),
+            id.to_string(),
+            Option::Some(token_user.unwrap().payload.unwrap().user.username),
+        )
+        .await;
+
+    // return
+    return HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap());
+}
+
+#[derive(Default, Deserialize)]
+pub struct AccessLogQuery {
+    /// `combined` (default) or `jsonl`
+    #[serde(default)]
+    pub format: String,
+}
+
+#[get("/api/v1/project/{name:.*}/logs/{day}")]
+/// Download a project's access log for a day (`YYYY-MM-DD`)
+pub async fn get_access_log_request(
+    req: HttpRequest,
+    info: web::Query<AccessLogQuery>,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    let project_name = req.match_info().get("name").unwrap();
+    let day = req.match_info().get("day").unwrap();
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    if token_user.is_none() {
+        return HttpResponse::NotAcceptable().body("An account is required to view access logs.");
+    }
+
+    // check format
+    let format = if info.format.is_empty() {
+        Option::Some(serve::access_log::AccessLogFormat::Combined)
+    } else {
+        serve::access_log::AccessLogFormat::parse(&info.format)
+    };
+
+    if format.is_none() {
+        return HttpResponse::BadRequest()
+            .append_header(("Content-Type", "text/plain"))
+            .append_header(("Set-Cookie", set_cookie))
+            .body("Log format must be \"combined\" or \"jsonl\".");
+    }
+
+    let format = format.unwrap();
+
+    // ...
+    let res = data
+        .db
+        .get_access_log(
+            project_name.to_string(),
+            day.to_string(),
+            Option::Some(token_user.unwrap().payload.unwrap().user.username),
+        )
+        .await;
+
+    if res.success == false {
+        return HttpResponse::NotFound()
+            .append_header(("Content-Type", "text/plain"))
+            .append_header(("Set-Cookie", set_cookie))
+            .body(res.message);
+    }
+
+    // return
+    return HttpResponse::Ok()
+        .append_header(("Content-Type", format.content_type()))
+        .append_header((
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"{}-{}.{}\"",
+                project_name,
+                day,
+                format.extension()
+            ),
+        ))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serve::access_log::format(&res.payload.unwrap(), format));
+}
@@ -1,5 +1,8 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
+use actix_web::http::header::EntityTag;
+use actix_web::web::Bytes;
 use base64::Engine;
 use conductor::{score, ConductorEngine};
 use dorsal::db::special::log_db::Log;
@@ -9,6 +12,24 @@ use dorsal::DefaultReturn;
 
 use serde::{Deserialize, Serialize};
 
+use crate::filecache::{self, FileCache, Lease};
+use crate::serve::access_log::{self, AccessLogPolicy, AccessLogRecord};
+use crate::serve::caching;
+use crate::serve::cors::CorsPolicy;
+use crate::serve::encoding::{self, Encoding};
+use crate::serve::headers::{self, HeaderRule};
+use crate::serve::hosts;
+use crate::serve::hotlink::HotlinkPolicy;
+use crate::serve::images::{self, Transform};
+use crate::serve::maintenance::MaintenancePolicy;
+use crate::serve::paths;
+use crate::serve::previews;
+use crate::serve::protection;
+use crate::serve::redirects::{self, RedirectRule};
+use crate::serve::share;
+use crate::serve::sitemap;
+use crate::uploads;
+
 #[derive(Clone)]
 pub struct AppData {
     pub db: Database,
@@ -17,6 +38,16 @@ pub struct AppData {
     pub port: u16,
 }
 
+/// How long (in ms) a cached [`ProjectRequestLimit`] is used before the project owner is checked again
+pub const REQUEST_LIMIT_CACHE_TIME: u128 = 300_000;
+
+/// [`ProjectRequestLimit`] stored in the cache (see [`Database::get_project_request_limit`])
+#[derive(Serialize, Deserialize)]
+struct CachedRequestLimit {
+    limit: ProjectRequestLimit,
+    fetched: u128,
+}
+
 // base structures
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ProjectRequestLimit {
@@ -40,6 +71,26 @@ impl std::fmt::Display for ProjectRequestLimit {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ProjectUploadLimit {
+    /// files uploaded in chunks can be at most 10 MiB
+    Default = 10_485_760,
+    /// files uploaded in chunks can be at most 100 MiB
+    Enterprise = 104_857_600,
+}
+
+impl Default for ProjectUploadLimit {
+    fn default() -> Self {
+        ProjectUploadLimit::Default
+    }
+}
+
+impl std::fmt::Display for ProjectUploadLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ProjectType {
     /// Files are manually uploaded and stored in the database as base64
@@ -67,6 +118,8 @@ pub enum ProjectFilePrivacy {
     Confidential,
     /// project files can be LISTED by nobody; files can only be VIEWED by project owner
     Private,
+    /// project files can be LISTED by nobody; files can be VIEWED by anybody with the project password
+    Protected,
 }
 
 impl Default for ProjectFilePrivacy {
@@ -81,6 +134,29 @@ impl std::fmt::Display for ProjectFilePrivacy {
     }
 }
 
+/// What to do with the trailing slash of request paths (only paths without a file extension)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum TrailingSlash {
+    /// redirect `/docs` to `/docs/`
+    Add,
+    /// redirect `/docs/` to `/docs`
+    Strip,
+    /// serve both
+    Ignore,
+}
+
+impl Default for TrailingSlash {
+    fn default() -> Self {
+        TrailingSlash::Ignore
+    }
+}
+
+impl std::fmt::Display for TrailingSlash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Project {
     /// basically the project ID (no spaces, must be unique)
@@ -106,15 +182,54 @@ pub struct ProjectPrivateMetadata {
     /// actually a creation timestamp
     #[serde(default = "default_creation_timestamp")]
     pub created: u128,
+    /// last time the project's files were changed (`0` if they haven't changed since this was added)
+    #[serde(default)]
+    pub updated: u128,
+    // share links
+    /// key every share link is signed with, rotating it revokes all share links
+    #[serde(default)]
+    pub share_key: String,
+    /// share links that haven't been revoked
+    #[serde(default)]
+    pub share_links: Vec<ShareLink>,
+    // previews
+    /// name of the project this is a preview of (see [`previews`])
+    #[serde(default)]
+    pub preview_of: Option<String>,
+    // password protection
+    /// hash of the password of [`ProjectFilePrivacy::Protected`] projects (see [`protection::hash_password`])
+    #[serde(default)]
+    pub password: String,
 }
 
 impl Default for ProjectPrivateMetadata {
     fn default() -> Self {
+        let now = utility::unix_epoch_timestamp();
+
         ProjectPrivateMetadata {
             r#type: ProjectType::default(),
             limit: ProjectRequestLimit::default(),
             // dates
-            created: utility::unix_epoch_timestamp(),
+            created: now,
+            updated: now,
+            // share links
+            share_key: utility::random_id(),
+            share_links: Vec::new(),
+            // previews
+            preview_of: Option::None,
+            // password protection
+            password: String::new(),
+        }
+    }
+}
+
+impl ProjectPrivateMetadata {
+    /// Get the last time the project's files were changed, falling back to the creation date
+    pub fn last_modified(&self) -> u128 {
+        if self.updated == 0 {
+            self.created
+        } else {
+            self.updated
         }
     }
 }
@@ -130,6 +245,38 @@ pub struct ProjectMetadata {
     pub file_privacy: ProjectFilePrivacy,
     #[serde(default)]
     pub clean_paths: bool,
+    /// Serve `/index.html` (with `200 OK`) for paths that don't exist, for single-page apps
+    #[serde(default)]
+    pub spa_fallback: bool,
+    /// Trailing slash policy, paths are redirected to their canonical form
+    #[serde(default)]
+    pub trailing_slash: TrailingSlash,
+    /// New password for [`ProjectFilePrivacy::Protected`] projects, only its hash is stored (see
+    /// [`ProjectPrivateMetadata::password`]), so this is always empty when it's read back and saving
+    /// it empty keeps the current password
+    #[serde(default)]
+    pub password: String,
+    /// `Cache-Control` rules for served files, the first rule matching the file path is used
+    #[serde(default)]
+    pub cache_control: Vec<CacheControlRule>,
+    /// `Content-Type` overrides for served files, the first rule matching the file path is used
+    #[serde(default)]
+    pub content_types: Vec<ContentTypeRule>,
+    /// Hotlink protection for images and media
+    #[serde(default)]
+    pub hotlink: HotlinkPolicy,
+    /// CORS policy for project files
+    #[serde(default)]
+    pub cors: CorsPolicy,
+    /// Maintenance mode, takes the project offline for everybody but its owner
+    #[serde(default)]
+    pub maintenance: MaintenancePolicy,
+    /// Generate `/sitemap.xml` (from the project's HTML files) and a default `/robots.txt` when the project doesn't have them
+    #[serde(default)]
+    pub sitemap: bool,
+    /// Access logs of served requests, downloadable by the owner
+    #[serde(default)]
+    pub access_log: AccessLogPolicy,
 }
 
 impl Default for ProjectMetadata {
@@ -137,6 +284,16 @@ impl Default for ProjectMetadata {
         ProjectMetadata {
             file_privacy: ProjectFilePrivacy::default(),
             clean_paths: false,
+            spa_fallback: false,
+            trailing_slash: TrailingSlash::default(),
+            password: String::new(),
+            cache_control: Vec::new(),
+            content_types: Vec::new(),
+            hotlink: HotlinkPolicy::default(),
+            cors: CorsPolicy::default(),
+            maintenance: MaintenancePolicy::default(),
+            sitemap: false,
+            access_log: AccessLogPolicy::default(),
         }
     }
 }
@@ -147,6 +304,154 @@ impl std::fmt::Display for ProjectMetadata {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheControlRule {
+    /// path pattern, `*` matches anything (`/assets/*`, `*.woff2`)
+    pub path: String,
+    /// value of the `Cache-Control` header (`public, max-age=31536000, immutable`)
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentTypeRule {
+    /// path pattern, `*` matches anything (`/downloads/*`, `/LICENSE`)
+    pub path: String,
+    /// value of the `Content-Type` header (`text/plain`), text types get `charset=utf-8` if they have no charset
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareLink {
+    /// random ID, part of the signed token
+    pub id: String,
+    /// path scope, a directory (ending with `/`) or a single file
+    pub path: String,
+    /// unix epoch timestamp (in ms) the link expires at
+    pub expires: u128,
+    /// unix epoch timestamp (in ms) the link was created at
+    pub created: u128,
+}
+
+/// [`ShareLink`] returned to the project owner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareLinkWithToken {
+    #[serde(flatten)]
+    pub link: ShareLink,
+    /// value of the `share` query parameter
+    pub token: String,
+}
+
+/// A preview deployment of a [`Project`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectPreview {
+    /// preview name
+    pub name: String,
+    /// name of the preview's own project (`{preview}--{project}`), also its subdomain
+    pub project: String,
+    pub file_privacy: ProjectFilePrivacy,
+    /// unix epoch timestamp (in ms) the preview was created at
+    pub created: u128,
+    /// last time the preview's files were changed
+    pub updated: u128,
+}
+
+/// Why a file couldn't be read (see [`Database::get_file_in_loaded_project`])
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileError {
+    /// the project doesn't exist
+    ProjectNotFound,
+    /// the file is not readable by the user (and not in a share link's scope)
+    NotAllowed,
+    /// the file is in a protected path (`.secrets`)
+    Protected,
+    /// the project's request limit is exceeded
+    LimitExceeded,
+    /// there's no file at the path (or at any path it resolves to)
+    NotFound,
+    /// anything else (database errors)
+    Other(String),
+}
+
+impl std::fmt::Display for FileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileError::ProjectNotFound => write!(f, "Project does not exist!"),
+            FileError::NotAllowed => write!(f, "Not allowed to view project files!"),
+            FileError::Protected => write!(f, "This path requires protected read access."),
+            FileError::LimitExceeded => write!(f, "Limit exceeded!"),
+            FileError::NotFound => write!(f, "Unable to find file at given path!"),
+            FileError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// A resumable upload session (see [`crate::uploads`])
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectUpload {
+    /// random ID
+    pub id: String,
+    /// path the file is stored at once the upload is finished
+    pub path: String,
+    /// username of the user that started the upload, the only one that can send chunks
+    pub owner: String,
+    /// file size (in bytes)
+    pub size: usize,
+    /// size of every chunk but the last (in bytes)
+    pub chunk_size: usize,
+    /// number of chunks the file is split into
+    pub chunks: usize,
+    /// unix epoch timestamp (in ms) the upload was started at
+    pub created: u128,
+    /// unix epoch timestamp (in ms) the upload expires at
+    pub expires: u128,
+    /// numbers of the chunks received so far
+    #[serde(default)]
+    pub received: Vec<usize>,
+}
+
+/// Most operations a single file batch can have (see [`Database::apply_file_batch`])
+pub const MAX_FILE_BATCH_OPERATIONS: usize = 500;
+
+/// A single operation of a file batch (see [`Database::apply_file_batch`])
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum FileOperation {
+    /// create a file that doesn't exist yet
+    Create {
+        path: String,
+        /// base64 content
+        content: String,
+    },
+    /// replace the content of an existing file
+    Update {
+        path: String,
+        /// base64 content
+        content: String,
+    },
+    /// move an existing file to a path that doesn't exist yet
+    Move { path: String, new_path: String },
+    /// delete an existing file
+    Delete { path: String },
+}
+
+/// Mark the results of a file batch that wasn't applied, keeping the errors of failed operations
+fn not_applied(results: Vec<DefaultReturn<Option<String>>>) -> Vec<DefaultReturn<Option<String>>> {
+    results
+        .into_iter()
+        .map(|r| {
+            if r.success {
+                DefaultReturn {
+                    success: false,
+                    message: String::from("Not applied"),
+                    payload: r.payload,
+                }
+            } else {
+                r
+            }
+        })
+        .collect()
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Organization {
     /// must be unique (same requirements as [`Project`] name)
@@ -197,16 +502,25 @@ pub struct Database {
     pub base: dorsal::StarterDatabase,
     pub auth: dorsal::AuthDatabase,
     pub logs: dorsal::LogDatabase,
+    /// key used to sign cookies and links (`SIGNING_KEY`)
+    pub signing_key: String,
+    /// decoded file contents of the most requested files
+    pub file_cache: Arc<FileCache>,
+    /// held while a billing counter is read and written back (see [`Database::incr_project_bandwidth`])
+    pub billing_lock: Arc<futures_util::lock::Mutex<()>>,
 }
 
 impl Database {
-    pub async fn new(opts: dorsal::DatabaseOpts) -> Database {
+    pub async fn new(opts: dorsal::DatabaseOpts, signing_key: String) -> Database {
         let db = dorsal::StarterDatabase::new(opts).await;
 
         Database {
             base: db.clone(),
             auth: dorsal::AuthDatabase { base: db.clone() },
             logs: dorsal::LogDatabase { base: db },
+            signing_key,
+            file_cache: Arc::new(FileCache::from_env()),
+            billing_lock: Arc::new(futures_util::lock::Mutex::new(())),
         }
     }
 
@@ -235,6 +549,47 @@ impl Database {
         .execute(c)
         .await;
 
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"ProjectFileVariants\" (
+                project VARCHAR(1000000),
+                path VARCHAR(1000000),
+                encoding VARCHAR(1000000),
+                content BLOB
+            )",
+        )
+        .execute(c)
+        .await;
+
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"ProjectUploads\" (
+                id VARCHAR(1000000),
+                project VARCHAR(1000000),
+                upload VARCHAR(1000000)
+            )",
+        )
+        .execute(c)
+        .await;
+
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"ProjectUploadChunks\" (
+                upload VARCHAR(1000000),
+                number VARCHAR(1000000),
+                content BLOB
+            )",
+        )
+        .execute(c)
+        .await;
+
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"ProjectAccessLogs\" (
+                project VARCHAR(1000000),
+                day VARCHAR(1000000),
+                record VARCHAR(1000000)
+            )",
+        )
+        .execute(c)
+        .await;
+
         // users and logs tables
         let _ = sqlquery(
             "CREATE TABLE IF NOT EXISTS \"Users\" (
@@ -260,6 +615,46 @@ impl Database {
         .await;
     }
 
+    /// Claim the lease on the in-process [`FileCache`] for this server instance
+    ///
+    /// The file cache is only cleared by the instance that changes a file, so only the instance
+    /// holding the lease ([`filecache::LEASE_KEY`]) may use it. The lease is renewed on every claim
+    /// and expires when it isn't renewed for [`filecache::LEASE_TIME`].
+    ///
+    /// # Arguments:
+    /// * `instance` - id of this instance (see [`filecache::instance_id`])
+    pub async fn claim_file_cache(&self, instance: &str) -> Lease {
+        let now = utility::unix_epoch_timestamp();
+        let current = self
+            .base
+            .cachedb
+            .get(filecache::LEASE_KEY.to_string())
+            .await
+            .unwrap_or_default();
+
+        // "{instance} {timestamp}"
+        let (holder, timestamp) = current.split_once(" ").unwrap_or(("", "0"));
+        let timestamp = timestamp.parse::<u128>().unwrap_or(0);
+
+        let lease = if holder == instance {
+            Lease::Held
+        } else if holder.is_empty() | (now.saturating_sub(timestamp) >= filecache::LEASE_TIME) {
+            Lease::Taken
+        } else {
+            return Lease::Busy;
+        };
+
+        self.base
+            .cachedb
+            .set(
+                filecache::LEASE_KEY.to_string(),
+                format!("{instance} {now}"),
+            )
+            .await;
+
+        lease
+    }
+
     /// Sort a hashmap by its keys from shortest to longest
     pub fn sort_hashmap_by_keys<T>(&self, hashmap: HashMap<String, T>) -> Vec<(String, T)> {
         let mut out = Vec::new();
@@ -400,6 +795,39 @@ impl Database {
         };
     }
 
+    /// Get a [`Project`] by the name its host resolved to (see [`hosts::resolve`])
+    ///
+    /// Hosts are case-folded, so projects with uppercase names are looked up without case when
+    /// there's no exact match.
+    ///
+    /// # Arguments:
+    /// * `name` - lowercase project name
+    pub async fn get_project_by_host_name(&self, name: String) -> DefaultReturn<Option<Project>> {
+        let existing = self.get_project_by_id(name.clone()).await;
+
+        if existing.success {
+            return existing;
+        }
+
+        // ...
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT \"name\" FROM \"Projects\" WHERE LOWER(\"name\") = ? LIMIT 1"
+        } else {
+            "SELECT \"name\" FROM \"Projects\" WHERE LOWER(\"name\") = $1 LIMIT 1"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query).bind::<&String>(&name).fetch_one(c).await;
+
+        if res.is_err() {
+            return existing;
+        }
+
+        let row = self.base.textify_row(res.unwrap()).data;
+        self.get_project_by_id(row.get("name").unwrap().to_string())
+            .await
+    }
+
     /// Get all [projects](PCreateProject) owned by a specific user (limited), sorted by billing period start
     ///
     /// # Arguments:
@@ -464,6 +892,11 @@ impl Database {
             )
             .unwrap();
 
+            // previews are listed on their project's page
+            if metadata.preview_of.is_some() {
+                continue;
+            }
+
             full_res.push(PCreateProject {
                 name: row.get("name").unwrap().to_string(),
                 r#type: metadata.r#type,
@@ -532,8 +965,11 @@ impl Database {
             };
         }
 
-        // project cannot have names we may need
-        if ["dashboard", "api", "social"].contains(&props.name.as_str()) {
+        // project cannot have names we may need (or the name of a preview), and must be reachable from its host
+        if ["dashboard", "api", "social"].contains(&props.name.as_str())
+            | props.name.contains(previews::SEPARATOR)
+            | !hosts::is_reachable(&props.name)
+        {
             return DefaultReturn {
                 success: false,
                 message: String::from("Name is invalid"),
@@ -595,54 +1031,16 @@ impl Database {
         let user = user.payload.unwrap();
 
         // get user projects for count
-        if !user
-            .level
-            .permissions
-            .contains(&"VIB:MaxProjects:Disabled".to_string())
-        {
-            let mut max_of_10 = user
-                .level
-                .permissions
-                .contains(&"VIB:MaxProjects:10".to_string());
-            let max_of_25 = user
-                .level
-                .permissions
-                .contains(&"VIB:MaxProjects:25".to_string());
-
-            // if both are false, max_of_10 should be true
-            if (max_of_10 == false) && (max_of_25 == false) {
-                max_of_10 = true;
-            }
-
-            // ...
-            let user_projects = self
-                .get_projects_by_owner_limited(user.user.username.clone(), Option::Some(0))
-                .await;
-
-            if !user_projects.success {
-                return DefaultReturn {
-                    success: false,
-                    message: String::from("User is invalid!"),
-                    payload: Option::None,
-                };
-            }
-
-            let user_projects = user_projects.payload.unwrap();
+        let quota = self
+            .check_project_quota(&user.user.username, &user.level.permissions)
+            .await;
 
-            // ...
-            if max_of_10 && user_projects.len() >= 10 {
-                return DefaultReturn {
-                    success: false,
-                    message: String::from("You have reached the maximum number of projects allowed for your account level."),
-                    payload: Option::None,
-                };
-            } else if max_of_25 && user_projects.len() >= 25 {
-                return DefaultReturn {
-                    success: false,
-                    message: String::from("You have reached the maximum number of projects allowed for your account level."),
-                    payload: Option::None,
-                };
-            }
+        if !quota.success {
+            return DefaultReturn {
+                success: false,
+                message: quota.message,
+                payload: Option::None,
+            };
         }
 
         // create project
@@ -692,6 +1090,71 @@ impl Database {
         };
     }
 
+    /// Make sure a user can own another project (`VIB:MaxProjects` permissions), previews count
+    /// towards the limit like any other project
+    ///
+    /// # Arguments:
+    /// * `owner` - username of the user that would own the project
+    /// * `permissions` - permissions of the user's level
+    async fn check_project_quota(
+        &self,
+        owner: &String,
+        permissions: &[String],
+    ) -> DefaultReturn<Option<String>> {
+        if permissions.contains(&"VIB:MaxProjects:Disabled".to_string()) {
+            return DefaultReturn {
+                success: true,
+                message: String::from("No project limit"),
+                payload: Option::None,
+            };
+        }
+
+        let mut max_of_10 = permissions.contains(&"VIB:MaxProjects:10".to_string());
+        let max_of_25 = permissions.contains(&"VIB:MaxProjects:25".to_string());
+
+        // if both are false, max_of_10 should be true
+        if (max_of_10 == false) && (max_of_25 == false) {
+            max_of_10 = true;
+        }
+
+        // count every project (and preview) the user owns
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT \"name\" FROM \"Projects\" WHERE \"owner\" = ?"
+        } else {
+            "SELECT \"name\" FROM \"Projects\" WHERE \"owner\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query).bind::<&String>(owner).fetch_all(c).await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from("User is invalid!"),
+                payload: Option::None,
+            };
+        }
+
+        let count = res.unwrap().len();
+
+        // ...
+        if (max_of_10 && count >= 10) | (max_of_25 && count >= 25) {
+            return DefaultReturn {
+                success: false,
+                message: String::from(
+                    "You have reached the maximum number of projects allowed for your account level.",
+                ),
+                payload: Option::None,
+            };
+        }
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Project can be created"),
+            payload: Option::None,
+        }
+    }
+
     /// Update a [`Project`]'s [`fields`](PEditFieldsByName) by its `name`
     pub async fn edit_fields_by_name(
         &self,
@@ -773,18 +1236,47 @@ impl Database {
                     payload: Option::None,
                 };
             }
+
+            if fields.name.contains(previews::SEPARATOR) | !hosts::is_reachable(&fields.name) {
+                return DefaultReturn {
+                    success: false,
+                    message: String::from("Name is invalid"),
+                    payload: Option::None,
+                };
+            }
         }
 
-        // update project
-        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
-            "UPDATE \"Projects\" SET \"owner\" = ?, \"name\" = ? WHERE \"name\" = ?"
-        } else {
-            "UPDATE \"Projects\" SET (\"owner\", \"name\") = ($1, $2) WHERE \"name\" = $2"
-        };
+        // previews keep the name and owner of their project
+        if (name != fields.name) | (original_project != fields.owner) {
+            if project.private_metadata.preview_of.is_some() {
+                return DefaultReturn {
+                    success: false,
+                    message: String::from("Previews can't be renamed or transferred."),
+                    payload: Option::None,
+                };
+            }
 
-        let c = &self.base.db.client;
-        let res = sqlquery(query)
-            .bind::<&String>(&fields.owner)
+            if !self.list_project_previews(&name).await.is_empty() {
+                return DefaultReturn {
+                    success: false,
+                    message: String::from(
+                        "Delete or promote this project's previews before renaming or transferring it.",
+                    ),
+                    payload: Option::None,
+                };
+            }
+        }
+
+        // update project
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "UPDATE \"Projects\" SET \"owner\" = ?, \"name\" = ? WHERE \"name\" = ?"
+        } else {
+            "UPDATE \"Projects\" SET (\"owner\", \"name\") = ($1, $2) WHERE \"name\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&fields.owner)
             .bind::<&String>(&fields.name)
             .bind::<&String>(&name)
             .execute(c)
@@ -821,6 +1313,63 @@ impl Database {
                     payload: Option::None,
                 };
             }
+
+            // update file variants
+            let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql")
+            {
+                "UPDATE \"ProjectFileVariants\" SET \"project\" = ? WHERE \"project\" = ?"
+            } else {
+                "UPDATE \"ProjectFileVariants\" SET (\"project\") = ($1) WHERE \"project\" = $2"
+            };
+
+            let c = &self.base.db.client;
+            let res = sqlquery(query)
+                .bind::<&String>(&fields.name)
+                .bind::<&String>(&name)
+                .execute(c)
+                .await;
+
+            if res.is_err() {
+                return DefaultReturn {
+                    success: false,
+                    message: String::from(res.err().unwrap().to_string()),
+                    payload: Option::None,
+                };
+            }
+
+            // update unfinished uploads
+            let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql")
+            {
+                "UPDATE \"ProjectUploads\" SET \"project\" = ? WHERE \"project\" = ?"
+            } else {
+                "UPDATE \"ProjectUploads\" SET (\"project\") = ($1) WHERE \"project\" = $2"
+            };
+
+            let c = &self.base.db.client;
+            let _ = sqlquery(query)
+                .bind::<&String>(&fields.name)
+                .bind::<&String>(&name)
+                .execute(c)
+                .await;
+
+            // update access logs
+            let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql")
+            {
+                "UPDATE \"ProjectAccessLogs\" SET \"project\" = ? WHERE \"project\" = ?"
+            } else {
+                "UPDATE \"ProjectAccessLogs\" SET (\"project\") = ($1) WHERE \"project\" = $2"
+            };
+
+            let c = &self.base.db.client;
+            let _ = sqlquery(query)
+                .bind::<&String>(&fields.name)
+                .bind::<&String>(&name)
+                .execute(c)
+                .await;
+
+            // files no longer belong to the old name
+            self.file_cache
+                .remove_starting_with(&format!("project:{}:", name));
         }
 
         // update cache
@@ -923,6 +1472,14 @@ impl Database {
             if res.is_err() {
                 return false;
             }
+
+            // bandwidth is billed per month
+            let _lock = self.billing_lock.lock().await;
+
+            self.base
+                .cachedb
+                .remove(format!("billing:bandwidth:{}", name))
+                .await;
         }
 
         // incr requests
@@ -935,6 +1492,34 @@ impl Database {
         true
     }
 
+    /// Add to a [`Project`]'s billing bandwidth (in bytes, redis)
+    ///
+    /// The cache can only increment by one, so the counter is read and written back while holding
+    /// [`Database::billing_lock`] (concurrent responses would otherwise lose each other's bytes).
+    /// The counter is reset with the billing timestamp (see [`Database::incr_project_requests`]).
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `bytes` - size of the response body
+    pub async fn incr_project_bandwidth(&self, name: &String, bytes: u64) -> bool {
+        let key = format!("billing:bandwidth:{}", name);
+        let _lock = self.billing_lock.lock().await;
+
+        let current = self
+            .base
+            .cachedb
+            .get(key.clone())
+            .await
+            .unwrap_or(String::from("0"))
+            .parse::<u64>()
+            .unwrap_or(0);
+
+        self.base
+            .cachedb
+            .set(key, (current + bytes).to_string())
+            .await
+    }
+
     /// Update a [`Project`]'s [`metadata`](ProjectMetadata) by its `name`
     pub async fn edit_project_metadata_by_name(
         &self,
@@ -989,6 +1574,97 @@ impl Database {
             };
         }
 
+        // hash project password (an empty password keeps the current one)
+        let mut metadata = metadata;
+        let mut password = project.private_metadata.password.clone();
+
+        if metadata.file_privacy != ProjectFilePrivacy::Protected {
+            password = String::new();
+        } else if !metadata.password.is_empty() {
+            // hash (off the async runtime, argon2 is slow on purpose)
+            let new_password = metadata.password.clone();
+            let hash =
+                actix_web::web::block(move || protection::hash_password(&new_password)).await;
+
+            if hash.is_err() {
+                return DefaultReturn {
+                    success: false,
+                    message: hash.err().unwrap().to_string(),
+                    payload: Option::None,
+                };
+            }
+
+            password = hash.unwrap();
+        } else if password.is_empty() {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Protected projects require a password"),
+                payload: Option::None,
+            };
+        }
+
+        metadata.password = String::new();
+
+        // check content type overrides
+        for rule in &metadata.content_types {
+            if !rule.value.contains("/")
+                || actix_web::http::header::HeaderValue::from_str(&rule.value).is_err()
+            {
+                return DefaultReturn {
+                    success: false,
+                    message: format!("Invalid content type for \"{}\"", rule.path),
+                    payload: Option::None,
+                };
+            }
+        }
+
+        // check cors policy
+        if let Err(e) = metadata.cors.check() {
+            return DefaultReturn {
+                success: false,
+                message: e,
+                payload: Option::None,
+            };
+        }
+
+        // check hotlink substitute
+        let substitute = &metadata.hotlink.substitute;
+
+        if !substitute.is_empty() && !substitute.starts_with("/") {
+            metadata.hotlink.substitute = format!("/{substitute}");
+        }
+
+        // check maintenance page
+        let page = &metadata.maintenance.page;
+
+        if !page.is_empty() && !page.starts_with("/") {
+            metadata.maintenance.page = format!("/{page}");
+        }
+
+        // store password hash
+        if password != project.private_metadata.password {
+            let private_metadata = self.get_current_private_metadata(&name).await;
+
+            if !private_metadata.success {
+                return DefaultReturn {
+                    success: false,
+                    message: private_metadata.message,
+                    payload: Option::None,
+                };
+            }
+
+            let mut private_metadata = private_metadata.payload.unwrap();
+            private_metadata.password = password;
+
+            let res = self
+                .edit_project_private_metadata_by_name(name.clone(), private_metadata)
+                .await;
+
+            if !res.success {
+                return res;
+            }
+        }
+
         // update project
         let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
             "UPDATE \"Projects\" SET \"metadata\" = ? WHERE \"name\" = ?"
@@ -1141,62 +1817,98 @@ impl Database {
         };
     }
 
-    /// Delete a [`Project`] given its `name`
-    pub async fn delete_project(
-        &self,
-        name: String,
-        delete_as: Option<String>, // username of account that is deleting this project
-    ) -> DefaultReturn<Option<String>> {
-        // make sure project exists
-        let existing = &self.get_project_by_id(name.clone()).await;
-        if !existing.success {
+    /// Mark a [`Project`]'s files as changed (see [`ProjectPrivateMetadata::last_modified`])
+    ///
+    /// Also clears everything cached per project version (like parsed rule files).
+    ///
+    /// Only `updated` is changed. The private metadata is read again right before it's written, so
+    /// changes made since the caller loaded the project (like a revoked share link) are kept.
+    pub async fn touch_project(&self, name: &String) -> DefaultReturn<Option<String>> {
+        self.base
+            .cachedb
+            .remove_starting_with(format!("project:{}:rules:*", name))
+            .await;
+
+        self.file_cache
+            .remove_starting_with(&format!("project:{}:path:", name));
+
+        // get current private metadata (not from the cache)
+        let private_metadata = self.get_current_private_metadata(name).await;
+
+        if !private_metadata.success {
             return DefaultReturn {
                 success: false,
-                message: String::from("Project does not exist!"),
+                message: private_metadata.message,
                 payload: Option::None,
             };
         }
 
-        let project = existing.payload.as_ref().unwrap();
+        let mut private_metadata = private_metadata.payload.unwrap();
+        private_metadata.updated = utility::unix_epoch_timestamp();
 
-        // get delete_as user account
-        let ua = if delete_as.is_some() {
-            Option::Some(
-                self.auth
-                    .get_user_by_username(delete_as.clone().unwrap())
-                    .await
-                    .payload,
-            )
+        self.edit_project_private_metadata_by_name(name.to_owned(), private_metadata)
+            .await
+    }
+
+    /// Get a [`Project`]'s [`private metadata`](ProjectPrivateMetadata) from the database (not from
+    /// the cache), for changes that must keep everything else changed since the project was loaded
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    async fn get_current_private_metadata(
+        &self,
+        name: &String,
+    ) -> DefaultReturn<Option<ProjectPrivateMetadata>> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT \"private_metadata\" FROM \"Projects\" WHERE \"name\" = ?"
         } else {
-            Option::None
+            "SELECT \"private_metadata\" FROM \"Projects\" WHERE \"name\" = $1"
         };
 
-        // make sure we can do this
-        if ua.is_some() {
-            let user = ua.unwrap().unwrap();
-            let can_delete: bool = (user.user.username == project.owner)
-                | (user.level.permissions.contains(&String::from("VIB:Admin")));
+        let c = &self.base.db.client;
+        let res = sqlquery(query).bind::<&String>(name).fetch_one(c).await;
 
-            if can_delete == false {
-                return DefaultReturn {
-                    success: false,
-                    message: String::from(
-                        "You do not have permission to manage this project's contents.",
-                    ),
-                    payload: Option::None,
-                };
-            }
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from(res.err().unwrap().to_string()),
+                payload: Option::None,
+            };
         }
 
-        // update project
+        let row = self.base.textify_row(res.unwrap()).data;
+        let private_metadata =
+            serde_json::from_str::<ProjectPrivateMetadata>(row.get("private_metadata").unwrap());
+
+        if private_metadata.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: private_metadata.err().unwrap().to_string(),
+                payload: Option::None,
+            };
+        }
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Project exists"),
+            payload: Option::Some(private_metadata.unwrap()),
+        }
+    }
+
+    /// Delete the row, files and file variants of a [`Project`] (without any checks)
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    async fn delete_project_data(&self, name: &String) -> DefaultReturn<Option<String>> {
+        // delete project
         let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
             "DELETE FROM \"Projects\" WHERE \"name\" = ?"
         } else {
-            "DELETE FROM \"Projects\" WHERE \"name\" = $2"
+            "DELETE FROM \"Projects\" WHERE \"name\" = $1"
         };
 
         let c = &self.base.db.client;
-        let res = sqlquery(query).bind::<&String>(&name).execute(c).await;
+        let res = sqlquery(query).bind::<&String>(name).execute(c).await;
 
         if res.is_err() {
             return DefaultReturn {
@@ -1210,11 +1922,29 @@ impl Database {
         let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
             "DELETE FROM \"ProjectFiles\" WHERE \"project\" = ?"
         } else {
-            "DELETE FROM \"ProjectFiles\" WHERE \"project\" = $2"
+            "DELETE FROM \"ProjectFiles\" WHERE \"project\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query).bind::<&String>(name).execute(c).await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from(res.err().unwrap().to_string()),
+                payload: Option::None,
+            };
+        }
+
+        // remove file variants
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "DELETE FROM \"ProjectFileVariants\" WHERE \"project\" = ?"
+        } else {
+            "DELETE FROM \"ProjectFileVariants\" WHERE \"project\" = $1"
         };
 
         let c = &self.base.db.client;
-        let res = sqlquery(query).bind::<&String>(&name).execute(c).await;
+        let res = sqlquery(query).bind::<&String>(name).execute(c).await;
 
         if res.is_err() {
             return DefaultReturn {
@@ -1224,109 +1954,351 @@ impl Database {
             };
         }
 
+        // remove unfinished uploads
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "DELETE FROM \"ProjectUploadChunks\" WHERE \"upload\" IN (SELECT \"id\" FROM \"ProjectUploads\" WHERE \"project\" = ?)"
+        } else {
+            "DELETE FROM \"ProjectUploadChunks\" WHERE \"upload\" IN (SELECT \"id\" FROM \"ProjectUploads\" WHERE \"project\" = $1)"
+        };
+
+        let c = &self.base.db.client;
+        let _ = sqlquery(query).bind::<&String>(name).execute(c).await;
+
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "DELETE FROM \"ProjectUploads\" WHERE \"project\" = ?"
+        } else {
+            "DELETE FROM \"ProjectUploads\" WHERE \"project\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let _ = sqlquery(query).bind::<&String>(name).execute(c).await;
+
+        // remove access logs
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "DELETE FROM \"ProjectAccessLogs\" WHERE \"project\" = ?"
+        } else {
+            "DELETE FROM \"ProjectAccessLogs\" WHERE \"project\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let _ = sqlquery(query).bind::<&String>(name).execute(c).await;
+
         // update cache
-        // self.base.cachedb.remove(format!("project:{}", name)).await;
         self.base.cachedb.remove(format!("project:{}", name)).await;
         self.base
             .cachedb
             .remove_starting_with(format!("project:{}:*", name))
             .await;
 
-        if delete_as.is_some() {
-            self.base
-                .cachedb
-                .remove_starting_with(format!(
-                    "projects-by-owner:{}:*",
-                    delete_as.as_ref().unwrap()
-                ))
-                .await;
-        }
+        self.file_cache
+            .remove_starting_with(&format!("project:{}:", name));
 
         // return
-        return DefaultReturn {
+        DefaultReturn {
             success: true,
             message: String::from("Project deleted!"),
-            payload: Option::Some(name),
-        };
+            payload: Option::Some(name.to_owned()),
+        }
     }
 
-    // files
-
-    // GET
-    /// Get a file by `path` in the given [`Project`]
-    pub async fn get_file_in_project(
+    /// Delete a [`Project`] given its `name`
+    pub async fn delete_project(
         &self,
         name: String,
-        mut path: String,
-        as_user: Option<String>,
+        delete_as: Option<String>, // username of account that is deleting this project
+    ) -> DefaultReturn<Option<String>> {
+        // make sure project exists
+        let existing = &self.get_project_by_id(name.clone()).await;
+        if !existing.success {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Project does not exist!"),
+                payload: Option::None,
+            };
+        }
+
+        let project = existing.payload.as_ref().unwrap();
+
+        // get delete_as user account
+        let ua = if delete_as.is_some() {
+            Option::Some(
+                self.auth
+                    .get_user_by_username(delete_as.clone().unwrap())
+                    .await
+                    .payload,
+            )
+        } else {
+            Option::None
+        };
+
+        // make sure we can do this
+        if ua.is_some() {
+            let user = ua.unwrap().unwrap();
+            let can_delete: bool = (user.user.username == project.owner)
+                | (user.level.permissions.contains(&String::from("VIB:Admin")));
+
+            if can_delete == false {
+                return DefaultReturn {
+                    success: false,
+                    message: String::from(
+                        "You do not have permission to manage this project's contents.",
+                    ),
+                    payload: Option::None,
+                };
+            }
+        }
+
+        // delete previews
+        if project.private_metadata.preview_of.is_none() {
+            for preview in self.list_project_previews(&name).await {
+                let res = self.delete_project_data(&preview.name).await;
+
+                if !res.success {
+                    return res;
+                }
+            }
+        }
+
+        // delete project
+        let res = self.delete_project_data(&name).await;
+
+        if !res.success {
+            return res;
+        }
+
+        if delete_as.is_some() {
+            self.base
+                .cachedb
+                .remove_starting_with(format!(
+                    "projects-by-owner:{}:*",
+                    delete_as.as_ref().unwrap()
+                ))
+                .await;
+        }
+
+        // return
+        return DefaultReturn {
+            success: true,
+            message: String::from("Project deleted!"),
+            payload: Option::Some(name),
+        };
+    }
+
+    // files
+
+    // GET
+    /// Get a file by `path` in the given [`Project`]
+    ///
+    /// A valid share link token (`share`) grants access to the files in its scope like the project
+    /// owner would have.
+    pub async fn get_file_in_project(
+        &self,
+        name: String,
+        path: String,
+        as_user: Option<String>,
+        share: Option<String>,
         bypass_user_checks: bool,
         protected: bool,
-    ) -> DefaultReturn<Option<Vec<u8>>> {
+    ) -> DefaultReturn<Option<Bytes>> {
         // get project
         let existing = self.get_project_by_id(name.clone()).await;
 
         if existing.success == false {
             return DefaultReturn {
                 success: false,
-                message: String::from("Project does not exist!"),
+                message: FileError::ProjectNotFound.to_string(),
                 payload: Option::None,
             };
         }
 
-        let project = existing.payload.unwrap();
+        // ...
+        let file = self
+            .get_file_in_loaded_project(
+                &existing.payload.unwrap(),
+                path,
+                as_user,
+                share,
+                bypass_user_checks,
+                protected,
+            )
+            .await;
+
+        match file {
+            Ok((path, content)) => DefaultReturn {
+                success: true,
+                message: path,
+                payload: Option::Some(content),
+            },
+            Err(e) => DefaultReturn {
+                success: false,
+                message: e.to_string(),
+                payload: Option::None,
+            },
+        }
+    }
+
+    /// Get a file by `path` in a [`Project`] that has already been fetched
+    ///
+    /// Same as [`Database::get_file_in_project`], without fetching the project again. Returns the
+    /// path the file was found at (see [`paths::candidates`]) and its content.
+    pub async fn get_file_in_loaded_project(
+        &self,
+        project: &Project,
+        path: String,
+        as_user: Option<String>,
+        share: Option<String>,
+        bypass_user_checks: bool,
+        protected: bool,
+    ) -> Result<(String, Bytes), FileError> {
+        // check file privacy (share links can only read files in their scope)
+        let share_scope = self.get_read_scope(project, as_user, share, bypass_user_checks)?;
+
+        // protected
+        if !protected {
+            if path.contains(".secrets") {
+                return Err(FileError::Protected);
+            }
+        }
+
+        // check request limit
+        let limit = self.get_project_request_limit(project).await;
+
+        if limit.success == false {
+            return Err(FileError::Other(limit.message));
+        }
+
+        let limit = limit.payload.unwrap();
+
+        if limit != ProjectRequestLimit::Disabled {
+            let current_usage = self
+                .base
+                .cachedb
+                .get(format!("billing:requests:{}", project.name))
+                .await
+                .unwrap_or(String::from("0"))
+                .parse::<i32>()
+                .unwrap();
+
+            // ...
+            if current_usage >= (limit as i32) {
+                return Err(FileError::LimitExceeded);
+            }
+        }
+
+        // resolve path (directory indexes, clean paths)
+        let candidates = paths::candidates(&path, project.metadata.clean_paths);
+
+        for candidate in &candidates {
+            if share_scope.is_some() && !share::in_scope(share_scope.as_ref().unwrap(), candidate) {
+                continue;
+            }
+
+            let file = self
+                .get_project_file_content(project.name.clone(), candidate.to_owned())
+                .await;
+
+            if file.success {
+                return Ok((file.message, file.payload.unwrap()));
+            }
+        }
+
+        return Err(FileError::NotFound);
+    }
+
+    /// Get what a user can read in a [`Project`] (file privacy and share links)
+    ///
+    /// Returns `None` if every file can be read, or the path scope of the share link the files are
+    /// read through (see [`share::in_scope`]).
+    ///
+    /// # Arguments:
+    /// * `project` - the [`Project`]
+    /// * `as_user` - username of the user reading files
+    /// * `share` - share token of the request
+    /// * `bypass_user_checks` - skip the file privacy (for unlocked protected projects)
+    pub fn get_read_scope(
+        &self,
+        project: &Project,
+        as_user: Option<String>,
+        share: Option<String>,
+        bypass_user_checks: bool,
+    ) -> Result<Option<String>, FileError> {
+        let mut denied = false;
 
-        // check file privacy
         if bypass_user_checks == false {
             if as_user.is_some() {
                 let user = as_user.unwrap();
 
                 // "Confidential" is basically the same as "Public" in ProjectFilePrivacy
-                if (project.metadata.file_privacy == ProjectFilePrivacy::Private)
+                // "Protected" projects are unlocked by ServeAssets (which bypasses these checks)
+                if ((project.metadata.file_privacy == ProjectFilePrivacy::Private)
+                    | (project.metadata.file_privacy == ProjectFilePrivacy::Protected))
                     && (user != project.owner)
                 {
-                    return DefaultReturn {
-                        success: false,
-                        message: String::from("Not allowed to view project files!"),
-                        payload: Option::None,
-                    };
+                    denied = true;
                 }
             } else {
                 // TODO: possibly make "Public" be required here (make "Confidential" hide from non-authenticated users)
-                if project.metadata.file_privacy == ProjectFilePrivacy::Private {
-                    return DefaultReturn {
-                        success: false,
-                        message: String::from("Not allowed to view project files!"),
-                        payload: Option::None,
-                    };
+                if (project.metadata.file_privacy == ProjectFilePrivacy::Private)
+                    | (project.metadata.file_privacy == ProjectFilePrivacy::Protected)
+                {
+                    denied = true;
                 }
             }
         }
 
-        // protected
-        if !protected {
-            if path.contains(".secrets") {
-                return DefaultReturn {
-                    success: false,
-                    message: String::from("This path requires protected read access."),
-                    payload: Option::None,
-                };
+        // share links (only files in the link's scope can be read)
+        if denied {
+            let link = if share.is_some() {
+                self.check_share_token(project, &share.unwrap())
+            } else {
+                Option::None
+            };
+
+            if link.is_none() {
+                return Err(FileError::NotAllowed);
             }
-        }
 
-        // check path
-        if path == "/" {
-            path = String::from("/index.html");
-        } else if !path.starts_with("/") {
-            path = format!("/{}", path);
+            return Ok(Option::Some(link.unwrap().path));
         }
 
-        // clean paths
-        if (project.metadata.clean_paths == true) && (!path.contains(".")) {
-            path = format!("{path}.html");
+        Ok(Option::None)
+    }
+
+    /// Get the [`ProjectRequestLimit`] of a [`Project`] (from the permissions of its owner)
+    ///
+    /// The limit is cached for [`REQUEST_LIMIT_CACHE_TIME`] so serving files doesn't look up the
+    /// owner on every request.
+    pub async fn get_project_request_limit(
+        &self,
+        project: &Project,
+    ) -> DefaultReturn<Option<ProjectRequestLimit>> {
+        let now = utility::unix_epoch_timestamp();
+
+        // check in cache
+        let cached = self
+            .base
+            .cachedb
+            .get(format!("project:{}:limit", project.name))
+            .await;
+
+        if cached.is_some() {
+            let cached = serde_json::from_str::<CachedRequestLimit>(&cached.unwrap());
+
+            if cached.is_ok() {
+                let cached = cached.unwrap();
+
+                if now.saturating_sub(cached.fetched) < REQUEST_LIMIT_CACHE_TIME {
+                    return DefaultReturn {
+                        success: true,
+                        message: String::from("Limit exists"),
+                        payload: Option::Some(cached.limit),
+                    };
+                }
+            }
         }
 
         // get project owner
-        let user = self.auth.get_user_by_username(project.owner).await;
+        let user = self.auth.get_user_by_username(project.owner.clone()).await;
 
         if user.success == false {
             return DefaultReturn {
@@ -1339,48 +2311,103 @@ impl Database {
         // check permission
         let user = user.payload.unwrap();
 
-        if !user
+        let limit = if user
             .level
             .permissions
             .contains(&"VIB:RequestLimit:Disabled".to_string())
         {
-            let user_limit = if user
-                .level
-                .permissions
-                .contains(&"VIB:RequestLimit:Enterprise".to_string())
-            {
-                ProjectRequestLimit::Enterprise
-            } else {
-                ProjectRequestLimit::Default
-            };
+            ProjectRequestLimit::Disabled
+        } else if user
+            .level
+            .permissions
+            .contains(&"VIB:RequestLimit:Enterprise".to_string())
+        {
+            ProjectRequestLimit::Enterprise
+        } else {
+            ProjectRequestLimit::Default
+        };
 
-            let current_usage = self
-                .base
-                .cachedb
-                .get(format!("billing:requests:{}", name))
-                .await
-                .unwrap_or(String::from("0"))
-                .parse::<i32>()
-                .unwrap();
+        // store in cache
+        self.base
+            .cachedb
+            .set(
+                format!("project:{}:limit", project.name),
+                serde_json::to_string::<CachedRequestLimit>(&CachedRequestLimit {
+                    limit: limit.clone(),
+                    fetched: now,
+                })
+                .unwrap(),
+            )
+            .await;
 
-            // ...
-            if ((user_limit == ProjectRequestLimit::Enterprise) && (current_usage >= 100_000_000))
-                | ((user_limit == ProjectRequestLimit::Default) && (current_usage >= 1_000_000))
-            {
-                return DefaultReturn {
-                    success: false,
-                    message: String::from("Limit exceeded!"),
-                    payload: Option::None,
-                };
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Limit exists"),
+            payload: Option::Some(limit),
+        }
+    }
+
+    /// Check if a request `path` resolves to a file in the given [`Project`] (see [`paths::candidates`])
+    ///
+    /// Only the path is selected, so this is cheaper than loading the file. Like
+    /// [`Database::get_project_file_content`], this doesn't check who is asking.
+    ///
+    /// # Arguments:
+    /// * `project` - the [`Project`]
+    /// * `path` - request path
+    pub async fn project_file_exists(&self, project: &Project, path: &str) -> bool {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT \"path\" FROM \"ProjectFiles\" WHERE \"project\" = ? AND \"path\" = ?"
+        } else {
+            "SELECT \"path\" FROM \"ProjectFiles\" WHERE \"project\" = $1 AND \"path\" = $2"
+        };
+
+        let c = &self.base.db.client;
+
+        for candidate in paths::candidates(path, project.metadata.clean_paths) {
+            let res = sqlquery(query)
+                .bind::<&String>(&project.name)
+                .bind::<&String>(&candidate)
+                .fetch_one(c)
+                .await;
+
+            if res.is_ok() {
+                return true;
             }
         }
 
+        false
+    }
+
+    /// Get the content of a file by its exact `path` in the given [`Project`]
+    ///
+    /// This skips every check [`Database::get_file_in_project`] does (privacy, request limits and path
+    /// resolution), so it should only be used for files the server reads for itself.
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `path` - file path, including the leading slash
+    pub async fn get_project_file_content(
+        &self,
+        name: String,
+        path: String,
+    ) -> DefaultReturn<Option<Bytes>> {
+        let key = format!("project:{}:path:{}", name, path);
+
+        // check in memory
+        let in_memory = self.file_cache.get(&key);
+
+        if in_memory.is_some() {
+            return DefaultReturn {
+                success: true,
+                message: path,
+                payload: in_memory,
+            };
+        }
+
         // check in cache
-        let cached = self
-            .base
-            .cachedb
-            .get(format!("project:{}:path:{}", name, path))
-            .await;
+        let cached = self.base.cachedb.get(key.clone()).await;
 
         if cached.is_some() {
             // ...
@@ -1397,7 +2424,10 @@ impl Database {
                 };
             }
 
-            let bytes = bytes.unwrap();
+            let bytes = Bytes::from(bytes.unwrap());
+
+            // store in memory
+            self.file_cache.insert(key, bytes.clone());
 
             // return
             return DefaultReturn {
@@ -1445,17 +2475,16 @@ impl Database {
             };
         }
 
-        let bytes = bytes.unwrap();
+        let bytes = Bytes::from(bytes.unwrap());
 
         // store in cache
         self.base
             .cachedb
-            .set(
-                format!("project:{}:path:{}", name, path),
-                original_base64.to_string(),
-            )
+            .set(key.clone(), original_base64.to_string())
             .await;
 
+        self.file_cache.insert(key, bytes.clone());
+
         // return
         return DefaultReturn {
             success: true,
@@ -1650,8 +2679,15 @@ impl Database {
             path = format!("/{}", path);
         }
 
-        // ...
-        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+        // check rules files
+        let res = self.check_rules_file(&path, &content);
+
+        if !res.success {
+            return res;
+        }
+
+        // ...
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
             "INSERT INTO \"ProjectFiles\" VALUES (?, ?, ?)"
         } else {
             "INSERT INTO \"ProjectFiles\" VALUES ($1, $2, $3)"
@@ -1673,12 +2709,25 @@ impl Database {
             };
         }
 
+        // store compressed variants
+        if encoding::is_compressible(&path) {
+            let bytes = base64::engine::general_purpose::STANDARD.decode(&content);
+
+            if bytes.is_ok() {
+                self.store_file_variants(&name, &path, &bytes.unwrap())
+                    .await;
+            }
+        }
+
         // store in cache
         self.base
             .cachedb
             .set(format!("project:{}:path:{}", name, path), content)
             .await;
 
+        // bump project version
+        self.touch_project(&project.name).await;
+
         // return
         return DefaultReturn {
             success: true,
@@ -1748,6 +2797,13 @@ impl Database {
             path = format!("/{}", path);
         }
 
+        // check rules files
+        let res = self.check_rules_file(&path, &content);
+
+        if !res.success {
+            return res;
+        }
+
         // ...
         let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
             "UPDATE \"ProjectFiles\" SET \"content\" = ? WHERE \"project\" = ? AND \"path\" = ?"
@@ -1771,12 +2827,28 @@ impl Database {
             };
         }
 
+        // store compressed variants
+        if encoding::is_compressible(&path) {
+            let bytes = base64::engine::general_purpose::STANDARD.decode(&content);
+
+            if bytes.is_ok() {
+                self.store_file_variants(&name, &path, &bytes.unwrap())
+                    .await;
+            }
+        } else if images::is_transformable(&path) {
+            // transformed images are made again from the new file
+            self.delete_file_variants(&name, &path).await;
+        }
+
         // store in cache
         self.base
             .cachedb
             .set(format!("project:{}:path:{}", name, path), content)
             .await;
 
+        // bump project version
+        self.touch_project(&project.name).await;
+
         // return
         return DefaultReturn {
             success: true,
@@ -1862,12 +2934,18 @@ impl Database {
             };
         }
 
+        // remove variants
+        self.delete_file_variants(&name, &path).await;
+
         // remove from cache
         self.base
             .cachedb
             .remove(format!("project:{}:path:{}", name, path))
             .await;
 
+        // bump project version
+        self.touch_project(&project.name).await;
+
         // return
         return DefaultReturn {
             success: true,
@@ -1941,6 +3019,15 @@ impl Database {
             new_path = format!("/{}", new_path);
         }
 
+        // check rules files
+        let rules = self
+            .check_moved_rules_file(&name, &path, &new_path, Option::None)
+            .await;
+
+        if !rules.success {
+            return rules;
+        }
+
         // ...
         let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
             "UPDATE \"ProjectFiles\" SET \"path\" = ? WHERE \"project\" = ? AND \"path\" = ?"
@@ -1964,12 +3051,43 @@ impl Database {
             };
         }
 
+        // move variants
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "UPDATE \"ProjectFileVariants\" SET \"path\" = ? WHERE \"project\" = ? AND \"path\" = ?"
+        } else {
+            "UPDATE \"ProjectFileVariants\" SET (\"path\") = ($1) WHERE \"project\" = $2 AND \"path\" = $3"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&new_path)
+            .bind::<&String>(&name)
+            .bind::<&String>(&path)
+            .execute(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from(res.err().unwrap().to_string()),
+                payload: Option::None,
+            };
+        }
+
+        self.base
+            .cachedb
+            .remove_starting_with(format!("project:{}:path:{}:encoding:*", name, path))
+            .await;
+
         // remove from cache
         self.base
             .cachedb
             .remove(format!("project:{}:path:{}", name, path))
             .await;
 
+        // bump project version
+        self.touch_project(&project.name).await;
+
         // return
         return DefaultReturn {
             success: true,
@@ -1978,11 +3096,26 @@ impl Database {
         };
     }
 
-    // social
-
-    // GET
-    /// Get the number of [`ProjectFavoriteLog`]s a [`Project`] has
-    pub async fn get_project_favorites(&self, name: String) -> DefaultReturn<i32> {
+    /// Apply a batch of [`FileOperation`]s to the given [`Project`], all or nothing
+    ///
+    /// Every operation is checked against the project's files (as changed by the operations before
+    /// it) before anything is written, then all of them are written in a single transaction. No
+    /// file is changed unless every operation succeeds, including when a file is changed by
+    /// something else between the checks and the transaction.
+    ///
+    /// The payload has the result of every operation, in order. When the batch fails, operations
+    /// that didn't fail themselves are `"Not applied"`.
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `operations` - operations to apply, in order
+    /// * `edit_as` - username of the user applying the batch
+    pub async fn apply_file_batch(
+        &self,
+        name: String,
+        operations: Vec<FileOperation>,
+        edit_as: Option<String>,
+    ) -> DefaultReturn<Vec<DefaultReturn<Option<String>>>> {
         // get project
         let existing = self.get_project_by_id(name.clone()).await;
 
@@ -1990,168 +3123,3174 @@ impl Database {
             return DefaultReturn {
                 success: false,
                 message: String::from("Project does not exist!"),
-                payload: 0,
+                payload: Vec::new(),
             };
         }
 
-        // get favorites
-        DefaultReturn {
-            success: true,
-            message: name.clone(),
-            // favorites are stored in the "Logs" table AS WELL AS an incremented value in the cache,
-            // we read the value from cache when checking the project's favorites, but read the cache value when fetching number
-            payload: self
-                .base
-                .cachedb
-                .get(format!("social:project-favorites:{}", name))
-                .await
-                .unwrap_or(String::from("0"))
-                .parse::<i32>()
-                .unwrap(),
+        let project = existing.payload.unwrap();
+
+        // make sure we can do this
+        if !self.can_manage_project(&project, edit_as.clone()).await {
+            return DefaultReturn {
+                success: false,
+                message: String::from(
+                    "You do not have permission to manage this project's contents.",
+                ),
+                payload: Vec::new(),
+            };
         }
-    }
 
-    pub async fn get_user_project_favorite(
-        &self,
-        user: String,
-        project: String,
-        skip_existing_check: bool,
-    ) -> DefaultReturn<Option<Log>> {
-        // get project
-        if skip_existing_check == false {
-            let existing = self.get_project_by_id(project.clone()).await;
+        // check batch size
+        if operations.is_empty() {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Batch must have at least one operation"),
+                payload: Vec::new(),
+            };
+        }
 
-            if existing.success == false {
-                return DefaultReturn {
-                    success: false,
-                    message: String::from("Project does not exist!"),
-                    payload: Option::None,
-                };
-            }
+        if operations.len() > MAX_FILE_BATCH_OPERATIONS {
+            return DefaultReturn {
+                success: false,
+                message: format!(
+                    "Batch can have at most {} operations",
+                    MAX_FILE_BATCH_OPERATIONS
+                ),
+                payload: Vec::new(),
+            };
         }
 
-        // ...
-        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
-            "SELECT * FROM \"Logs\" WHERE \"content\" = ? AND \"logtype\" = 'project_favorite'"
-        } else {
-            "SELECT * FROM \"Logs\" WHERE \"content\" = $1 AND \"logtype\" = 'project_favorite'"
-        };
+        // check paths
+        let operations: Vec<FileOperation> = operations
+            .into_iter()
+            .map(|op| {
+                let fix = |path: String| {
+                    if !path.starts_with("/") {
+                        format!("/{}", path)
+                    } else {
+                        path
+                    }
+                };
 
-        let c = &self.base.db.client;
-        let res = sqlquery(query)
-            .bind::<&String>(
-                &serde_json::to_string::<ProjectFavoriteLog>(&ProjectFavoriteLog {
-                    user,
-                    project: project.clone(),
-                })
-                .unwrap(),
-            )
-            .fetch_one(c)
+                match op {
+                    FileOperation::Create { path, content } => FileOperation::Create {
+                        path: fix(path),
+                        content,
+                    },
+                    FileOperation::Update { path, content } => FileOperation::Update {
+                        path: fix(path),
+                        content,
+                    },
+                    FileOperation::Move { path, new_path } => FileOperation::Move {
+                        path: fix(path),
+                        new_path: fix(new_path),
+                    },
+                    FileOperation::Delete { path } => FileOperation::Delete { path: fix(path) },
+                }
+            })
+            .collect();
+
+        // check operations
+        let files = self
+            .get_project_files(name.clone(), Option::None, true)
             .await;
 
-        if res.is_err() {
+        if !files.success {
             return DefaultReturn {
                 success: false,
-                message: String::from(res.err().unwrap().to_string()),
-                payload: Option::None,
+                message: files.message,
+                payload: Vec::new(),
             };
         }
 
-        // ...
-        let row = res.unwrap();
-        let row = self.base.textify_row(row).data;
+        let mut files: std::collections::HashSet<String> = files.payload.into_iter().collect();
+
+        // content written by the batch, by the path it ends up at
+        let mut written: HashMap<String, String> = HashMap::new();
+        let mut results: Vec<DefaultReturn<Option<String>>> = Vec::new();
+
+        for op in &operations {
+            let res = match op {
+                FileOperation::Create { path, content }
+                | FileOperation::Update { path, content } => {
+                    let is_create = matches!(op, FileOperation::Create { .. });
+                    let rules = self.check_rules_file(path, content);
+
+                    if is_create && files.contains(path) {
+                        Err(String::from("File already exists"))
+                    } else if !is_create && !files.contains(path) {
+                        Err(String::from("File does not exist"))
+                    } else if !rules.success {
+                        Err(rules.message)
+                    } else if base64::engine::general_purpose::STANDARD
+                        .decode(content)
+                        .is_err()
+                    {
+                        Err(String::from("Content is not valid base64"))
+                    } else {
+                        files.insert(path.to_owned());
+                        written.insert(path.to_owned(), content.to_owned());
+
+                        Ok(if is_create {
+                            "File inserted"
+                        } else {
+                            "File updated"
+                        })
+                    }
+                }
+                FileOperation::Move { path, new_path } => {
+                    let rules = if files.contains(path) {
+                        self.check_moved_rules_file(&name, path, new_path, written.get(path))
+                            .await
+                    } else {
+                        DefaultReturn {
+                            success: true,
+                            message: String::new(),
+                            payload: Option::None,
+                        }
+                    };
 
-        DefaultReturn {
-            success: true,
-            message: project,
-            payload: Option::Some(Log {
-                id: row.get("id").unwrap().to_string(),
-                logtype: row.get("logtype").unwrap().to_string(),
-                timestamp: row.get("timestamp").unwrap().parse::<u128>().unwrap(),
-                content: row.get("content").unwrap().to_string(),
-            }),
+                    if !files.contains(path) {
+                        Err(String::from("File does not exist"))
+                    } else if files.contains(new_path) {
+                        Err(String::from("A file already exists at the new path"))
+                    } else if !rules.success {
+                        Err(rules.message)
+                    } else {
+                        files.remove(path);
+                        files.insert(new_path.to_owned());
+
+                        if let Some(content) = written.remove(path) {
+                            written.insert(new_path.to_owned(), content);
+                        }
+
+                        Ok("File moved")
+                    }
+                }
+                FileOperation::Delete { path } => {
+                    if !files.contains(path) {
+                        Err(String::from("File does not exist"))
+                    } else {
+                        files.remove(path);
+                        written.remove(path);
+                        Ok("File deleted")
+                    }
+                }
+            };
+
+            let path = match op {
+                FileOperation::Create { path, .. }
+                | FileOperation::Update { path, .. }
+                | FileOperation::Move { path, .. }
+                | FileOperation::Delete { path } => path.to_owned(),
+            };
+
+            results.push(DefaultReturn {
+                success: res.is_ok(),
+                message: match res {
+                    Ok(message) => message.to_string(),
+                    Err(message) => message,
+                },
+                payload: Option::Some(path),
+            });
         }
-    }
 
-    // SET
-    /// Toggle a [`ProjectFavoriteLog`] on a [`Project`] by `user` and `project`
-    pub async fn toggle_user_project_favorite(
-        &self,
-        user: String,
-        project: String,
-    ) -> DefaultReturn<Option<String>> {
-        // get project
-        let existing = self.get_project_by_id(project.clone()).await;
+        let failed = results.iter().filter(|r| !r.success).count();
 
-        if existing.success == false {
+        if failed > 0 {
             return DefaultReturn {
                 success: false,
-                message: String::from("Project does not exist!"),
-                payload: Option::None,
+                message: format!("Batch not applied, {} operations failed", failed),
+                payload: not_applied(results),
             };
         }
 
-        // check if user is project owner
-        let existing = existing.payload.unwrap();
+        // apply
+        let is_postgres = (self.base.db._type != "sqlite") & (self.base.db._type != "mysql");
 
-        if existing.owner == user {
+        let tx = self.base.db.client.begin().await;
+
+        if tx.is_err() {
             return DefaultReturn {
                 success: false,
-                message: String::from("You're the project owner!"),
-                payload: Option::None,
+                message: String::from(tx.err().unwrap().to_string()),
+                payload: not_applied(results),
             };
         }
 
-        // attempt to get the user's existing favorite
-        let existing_favorite = self
-            .get_user_project_favorite(user.clone(), project.clone(), true)
-            .await;
+        let mut tx = tx.unwrap();
 
-        // if existing_favorite.message != project.clone() {
-        //     return DefaultReturn {
-        //         success: false,
-        //         message: existing_favorite.message,
-        //         payload: Option::None,
-        //     };
-        // }
+        for (i, op) in operations.iter().enumerate() {
+            let res = match op {
+                FileOperation::Create { path, content } => {
+                    let query: &str = if !is_postgres {
+                        "INSERT INTO \"ProjectFiles\" VALUES (?, ?, ?)"
+                    } else {
+                        "INSERT INTO \"ProjectFiles\" VALUES ($1, $2, $3)"
+                    };
 
-        // delete existing
-        if existing_favorite.success == true {
-            let payload = existing_favorite.payload.unwrap();
+                    sqlquery(query)
+                        .bind::<&String>(&name)
+                        .bind::<&String>(path)
+                        .bind::<&String>(content)
+                        .execute(&mut *tx)
+                        .await
+                        .map(|r| r.rows_affected())
+                }
+                FileOperation::Update { path, content } => {
+                    let query: &str = if !is_postgres {
+                        "UPDATE \"ProjectFiles\" SET \"content\" = ? WHERE \"project\" = ? AND \"path\" = ?"
+                    } else {
+                        "UPDATE \"ProjectFiles\" SET (\"content\") = ($1) WHERE \"project\" = $2 AND \"path\" = $3"
+                    };
 
-            // decr favorites
-            self.base
-                .cachedb
-                .decr(format!("social:project-favorites:{}", project.clone()))
-                .await;
+                    sqlquery(query)
+                        .bind::<&String>(content)
+                        .bind::<&String>(&name)
+                        .bind::<&String>(path)
+                        .execute(&mut *tx)
+                        .await
+                        .map(|r| r.rows_affected())
+                }
+                FileOperation::Move { path, new_path } => {
+                    let mut res = Ok(0);
+                    let mut moved = 0;
+
+                    // move file, then its variants
+                    for table in ["ProjectFiles", "ProjectFileVariants"] {
+                        let query = if !is_postgres {
+                            format!("UPDATE \"{table}\" SET \"path\" = ? WHERE \"project\" = ? AND \"path\" = ?")
+                        } else {
+                            format!("UPDATE \"{table}\" SET (\"path\") = ($1) WHERE \"project\" = $2 AND \"path\" = $3")
+                        };
+
+                        res = sqlquery(&query)
+                            .bind::<&String>(new_path)
+                            .bind::<&String>(&name)
+                            .bind::<&String>(path)
+                            .execute(&mut *tx)
+                            .await
+                            .map(|r| r.rows_affected());
+
+                        if res.is_err() {
+                            break;
+                        }
+
+                        if table == "ProjectFiles" {
+                            moved = *res.as_ref().unwrap();
+                        }
+                    }
+
+                    res.map(|_| moved)
+                }
+                FileOperation::Delete { path } => {
+                    let query: &str = if !is_postgres {
+                        "DELETE FROM \"ProjectFiles\" WHERE \"project\" = ? AND \"path\" = ?"
+                    } else {
+                        "DELETE FROM \"ProjectFiles\" WHERE \"project\" = $1 AND \"path\" = $2"
+                    };
 
-            // handle log
-            return self.logs.delete_log(payload.id).await;
+                    sqlquery(query)
+                        .bind::<&String>(&name)
+                        .bind::<&String>(path)
+                        .execute(&mut *tx)
+                        .await
+                        .map(|r| r.rows_affected())
+                }
+            };
+
+            // every operation changes exactly one file, anything else means the files changed
+            // since they were checked
+            let res = match res {
+                Ok(1) => Ok(()),
+                Ok(_) => Err(String::from("File was changed while the batch was applied")),
+                Err(e) => Err(e.to_string()),
+            };
+
+            if res.is_err() {
+                // dropping the transaction rolls it back
+                results[i].success = false;
+                results[i].message = res.err().unwrap();
+
+                return DefaultReturn {
+                    success: false,
+                    message: String::from("Batch not applied, an operation failed"),
+                    payload: not_applied(results),
+                };
+            }
         }
-        // add new
-        else {
-            // incr favorites
+
+        let res = tx.commit().await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from(res.err().unwrap().to_string()),
+                payload: not_applied(results),
+            };
+        }
+
+        // update variants and cache, files that are removed (or replaced) first
+        for op in &operations {
+            match op {
+                FileOperation::Move { path, .. } => {
+                    self.base
+                        .cachedb
+                        .remove_starting_with(format!("project:{}:path:{}:encoding:*", name, path))
+                        .await;
+
+                    self.base
+                        .cachedb
+                        .remove(format!("project:{}:path:{}", name, path))
+                        .await;
+                }
+                FileOperation::Delete { path } => {
+                    self.delete_file_variants(&name, path).await;
+
+                    self.base
+                        .cachedb
+                        .remove(format!("project:{}:path:{}", name, path))
+                        .await;
+                }
+                _ => (),
+            }
+        }
+
+        for (path, content) in written {
+            if encoding::is_compressible(&path) {
+                let bytes = base64::engine::general_purpose::STANDARD.decode(&content);
+
+                if bytes.is_ok() {
+                    self.store_file_variants(&name, &path, &bytes.unwrap())
+                        .await;
+                }
+            } else if images::is_transformable(&path) {
+                // transformed images are made again from the new file
+                self.delete_file_variants(&name, &path).await;
+            }
+
             self.base
                 .cachedb
-                .incr(format!("social:project-favorites:{}", project.clone()))
-                .await;
-
-            // handle log
-            return self
-                .logs
-                .create_log(
-                    String::from("project_favorite"),
-                    serde_json::to_string::<ProjectFavoriteLog>(&ProjectFavoriteLog {
-                        user,
-                        project,
-                    })
-                    .unwrap(),
-                )
+                .set(format!("project:{}:path:{}", name, path), content)
                 .await;
         }
-    }
 
-    // conductor
+        // bump project version
+        self.touch_project(&project.name).await;
+
+        // return
+        return DefaultReturn {
+            success: true,
+            message: format!("Applied {} operations", results.len()),
+            payload: results,
+        };
+    }
+
+    // file variants
+
+    // GET
+    /// Get a compressed variant of a file by `path` in the given [`Project`]
+    ///
+    /// This does not check permissions, it should only be used after [`Database::get_file_in_project`]
+    /// succeeded for the same file. Files stored before variants existed are compressed (and their
+    /// variants stored) the first time they're requested.
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `path` - resolved file path
+    /// * `encoding` - [`Encoding`] of the variant
+    /// * `content` - original file content, used to create missing variants
+    pub async fn get_file_variant_in_project(
+        &self,
+        name: String,
+        path: String,
+        encoding: Encoding,
+        content: &[u8],
+    ) -> DefaultReturn<Option<Bytes>> {
+        let key = format!("project:{}:path:{}:encoding:{}", name, path, encoding);
+
+        // check in memory
+        let in_memory = self.file_cache.get(&key);
+
+        if in_memory.is_some() {
+            return DefaultReturn {
+                success: true,
+                message: path,
+                payload: in_memory,
+            };
+        }
+
+        // check in cache
+        let cached = self.base.cachedb.get(key.clone()).await;
+
+        if cached.is_some() {
+            let bytes = base64::engine::general_purpose::STANDARD.decode(cached.unwrap());
+
+            if bytes.is_ok() {
+                let bytes = Bytes::from(bytes.unwrap());
+
+                self.file_cache.insert(key, bytes.clone());
+
+                return DefaultReturn {
+                    success: true,
+                    message: path,
+                    payload: Option::Some(bytes),
+                };
+            }
+        }
+
+        // ...
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"ProjectFileVariants\" WHERE \"project\" = ? AND \"path\" = ? AND \"encoding\" = ?"
+        } else {
+            "SELECT * FROM \"ProjectFileVariants\" WHERE \"project\" = $1 AND \"path\" = $2 AND \"encoding\" = $3"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&name)
+            .bind::<&String>(&path)
+            .bind::<&String>(&encoding.to_string())
+            .fetch_one(c)
+            .await;
+
+        if res.is_err() {
+            // create variants now
+            let stored = self.store_file_variants(&name, &path, content).await;
+
+            if !stored.success {
+                return DefaultReturn {
+                    success: false,
+                    message: stored.message,
+                    payload: Option::None,
+                };
+            }
+
+            // use the variant that was just stored
+            let bytes = stored
+                .payload
+                .into_iter()
+                .find(|(e, _)| *e == encoding)
+                .map(|(_, bytes)| Bytes::from(bytes));
+
+            if bytes.is_some() {
+                self.file_cache.insert(key, bytes.clone().unwrap());
+            }
+
+            return DefaultReturn {
+                success: bytes.is_some(),
+                message: path,
+                payload: bytes,
+            };
+        }
+
+        // ...
+        let row = res.unwrap();
+        let row = self.base.textify_row(row).data;
+
+        let original_base64 = row.get("content").unwrap();
+        let bytes = base64::engine::general_purpose::STANDARD.decode(original_base64);
+
+        if bytes.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from(bytes.err().unwrap().to_string()),
+                payload: Option::None,
+            };
+        }
+
+        let bytes = Bytes::from(bytes.unwrap());
+
+        // store in cache
+        self.base
+            .cachedb
+            .set(key.clone(), original_base64.to_string())
+            .await;
+
+        self.file_cache.insert(key, bytes.clone());
+
+        // return
+        return DefaultReturn {
+            success: true,
+            message: path,
+            payload: Option::Some(bytes),
+        };
+    }
+
+    // SET
+    /// Compress a file and store its variants (one for each [`Encoding`]), replacing existing ones
+    ///
+    /// Files that aren't [compressible](encoding::is_compressible) are skipped. Compression runs
+    /// off the async runtime. The payload has the stored variants.
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `path` - file path
+    /// * `content` - original file content
+    pub async fn store_file_variants(
+        &self,
+        name: &String,
+        path: &String,
+        content: &[u8],
+    ) -> DefaultReturn<Vec<(Encoding, Vec<u8>)>> {
+        if !encoding::is_compressible(path) {
+            return DefaultReturn {
+                success: false,
+                message: String::from("File is not compressible"),
+                payload: Vec::new(),
+            };
+        }
+
+        // compress (off the async runtime, brotli at its best quality is slow)
+        let original = content.to_vec();
+        let variants = actix_web::web::block(move || {
+            Encoding::ALL
+                .into_iter()
+                .map(|e| encoding::compress(&original, e).map(|bytes| (e, bytes)))
+                .collect::<std::io::Result<Vec<(Encoding, Vec<u8>)>>>()
+        })
+        .await;
+
+        let variants = match variants {
+            Ok(Ok(variants)) => variants,
+            Ok(Err(e)) => {
+                return DefaultReturn {
+                    success: false,
+                    message: e.to_string(),
+                    payload: Vec::new(),
+                }
+            }
+            Err(e) => {
+                return DefaultReturn {
+                    success: false,
+                    message: e.to_string(),
+                    payload: Vec::new(),
+                }
+            }
+        };
+
+        // remove old variants
+        let res = self.delete_file_variants(name, path).await;
+
+        if !res.success {
+            return DefaultReturn {
+                success: false,
+                message: res.message,
+                payload: Vec::new(),
+            };
+        }
+
+        // ...
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "INSERT INTO \"ProjectFileVariants\" VALUES (?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"ProjectFileVariants\" VALUES ($1, $2, $3, $4)"
+        };
+
+        for (encoding, bytes) in &variants {
+            let as_base64 = base64::engine::general_purpose::STANDARD.encode(bytes);
+
+            let c = &self.base.db.client;
+            let res = sqlquery(query)
+                .bind::<&String>(name)
+                .bind::<&String>(path)
+                .bind::<&String>(&encoding.to_string())
+                .bind::<&String>(&as_base64)
+                .execute(c)
+                .await;
+
+            if res.is_err() {
+                return DefaultReturn {
+                    success: false,
+                    message: String::from(res.err().unwrap().to_string()),
+                    payload: Vec::new(),
+                };
+            }
+
+            // store in cache
+            self.base
+                .cachedb
+                .set(
+                    format!("project:{}:path:{}:encoding:{}", name, path, encoding),
+                    as_base64,
+                )
+                .await;
+        }
+
+        // return
+        return DefaultReturn {
+            success: true,
+            message: String::from("Variants stored"),
+            payload: variants,
+        };
+    }
+
+    /// Delete all stored variants of a file
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `path` - file path
+    pub async fn delete_file_variants(
+        &self,
+        name: &String,
+        path: &String,
+    ) -> DefaultReturn<Option<String>> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "DELETE FROM \"ProjectFileVariants\" WHERE \"project\" = ? AND \"path\" = ?"
+        } else {
+            "DELETE FROM \"ProjectFileVariants\" WHERE \"project\" = $1 AND \"path\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(name)
+            .bind::<&String>(path)
+            .execute(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from(res.err().unwrap().to_string()),
+                payload: Option::None,
+            };
+        }
+
+        // remove from cache
+        self.base
+            .cachedb
+            .remove_starting_with(format!("project:{}:path:{}:encoding:*", name, path))
+            .await;
+
+        // return
+        return DefaultReturn {
+            success: true,
+            message: String::from("Variants deleted"),
+            payload: Option::Some(path.to_owned()),
+        };
+    }
+
+    /// Get the strong [`EntityTag`] of a file (or one of its variants) in the given [`Project`]
+    ///
+    /// Tags are cached until the project's files change (see [`Database::touch_project`]), so every
+    /// version of a file is only hashed once.
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `key` - resolved file path (followed by `:encoding:{key}` for image variants)
+    /// * `content` - content to hash when the tag isn't cached
+    pub async fn get_file_etag_in_project(
+        &self,
+        name: &String,
+        key: &str,
+        content: &Bytes,
+    ) -> EntityTag {
+        let cache_key = format!("project:{}:rules:etag:{}", name, key);
+
+        // check in cache
+        if let Some(tag) = self.base.cachedb.get(cache_key.clone()).await {
+            return EntityTag::new_strong(tag);
+        }
+
+        // hash (off the async runtime, files can be large)
+        let hashed = content.clone();
+        let etag = actix_web::web::block(move || caching::etag(&hashed))
+            .await
+            .unwrap_or_else(|_| caching::etag(content));
+
+        // store in cache
+        self.base
+            .cachedb
+            .set(cache_key, etag.tag().to_string())
+            .await;
+
+        // return
+        etag
+    }
+
+    /// Get a transformed variant of an image by `path` in the given [`Project`]
+    ///
+    /// This does not check permissions, it should only be used after [`Database::get_file_in_project`]
+    /// succeeded for the same file. Variants are created (and stored) the first time they're requested.
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `path` - resolved file path
+    /// * `transform` - the requested [`Transform`]
+    /// * `content` - original file content, used to create missing variants
+    pub async fn get_image_variant_in_project(
+        &self,
+        name: String,
+        path: String,
+        transform: Transform,
+        content: &[u8],
+    ) -> DefaultReturn<Option<Bytes>> {
+        let key = transform.key();
+        let cache_key = format!("project:{}:path:{}:encoding:{}", name, path, key);
+
+        // check in memory
+        let in_memory = self.file_cache.get(&cache_key);
+
+        if in_memory.is_some() {
+            return DefaultReturn {
+                success: true,
+                message: path,
+                payload: in_memory,
+            };
+        }
+
+        // check in cache
+        let cached = self.base.cachedb.get(cache_key.clone()).await;
+
+        if cached.is_some() {
+            let bytes = base64::engine::general_purpose::STANDARD.decode(cached.unwrap());
+
+            if bytes.is_ok() {
+                let bytes = Bytes::from(bytes.unwrap());
+
+                self.file_cache.insert(cache_key, bytes.clone());
+
+                return DefaultReturn {
+                    success: true,
+                    message: path,
+                    payload: Option::Some(bytes),
+                };
+            }
+        }
+
+        // ...
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"ProjectFileVariants\" WHERE \"project\" = ? AND \"path\" = ? AND \"encoding\" = ?"
+        } else {
+            "SELECT * FROM \"ProjectFileVariants\" WHERE \"project\" = $1 AND \"path\" = $2 AND \"encoding\" = $3"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&name)
+            .bind::<&String>(&path)
+            .bind::<&String>(&key)
+            .fetch_one(c)
+            .await;
+
+        let as_base64 = if res.is_ok() {
+            let row = self.base.textify_row(res.unwrap()).data;
+            row.get("content").unwrap().to_owned()
+        } else {
+            // transform now (off the async runtime, decoding images is slow)
+            let original = content.to_vec();
+            let bytes =
+                actix_web::web::block(move || images::transform(&original, &transform)).await;
+
+            let bytes = match bytes {
+                Ok(Ok(bytes)) => bytes,
+                Ok(Err(e)) => {
+                    return DefaultReturn {
+                        success: false,
+                        message: e,
+                        payload: Option::None,
+                    }
+                }
+                Err(e) => {
+                    return DefaultReturn {
+                        success: false,
+                        message: e.to_string(),
+                        payload: Option::None,
+                    }
+                }
+            };
+
+            let as_base64 = base64::engine::general_purpose::STANDARD.encode(bytes);
+
+            // store variant
+            let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql")
+            {
+                "INSERT INTO \"ProjectFileVariants\" VALUES (?, ?, ?, ?)"
+            } else {
+                "INSERT INTO \"ProjectFileVariants\" VALUES ($1, $2, $3, $4)"
+            };
+
+            let c = &self.base.db.client;
+            let res = sqlquery(query)
+                .bind::<&String>(&name)
+                .bind::<&String>(&path)
+                .bind::<&String>(&key)
+                .bind::<&String>(&as_base64)
+                .execute(c)
+                .await;
+
+            if res.is_err() {
+                return DefaultReturn {
+                    success: false,
+                    message: String::from(res.err().unwrap().to_string()),
+                    payload: Option::None,
+                };
+            }
+
+            as_base64
+        };
+
+        let bytes = base64::engine::general_purpose::STANDARD.decode(&as_base64);
+
+        if bytes.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from(bytes.err().unwrap().to_string()),
+                payload: Option::None,
+            };
+        }
+
+        let bytes = Bytes::from(bytes.unwrap());
+
+        // store in cache
+        self.base.cachedb.set(cache_key.clone(), as_base64).await;
+
+        self.file_cache.insert(cache_key, bytes.clone());
+
+        // return
+        return DefaultReturn {
+            success: true,
+            message: path,
+            payload: Option::Some(bytes),
+        };
+    }
+
+    // uploads
+
+    /// Get the [`ProjectUploadLimit`] of a [`Project`] (from the permissions of its owner)
+    pub async fn get_project_upload_limit(
+        &self,
+        project: &Project,
+    ) -> DefaultReturn<Option<ProjectUploadLimit>> {
+        // get project owner
+        let user = self.auth.get_user_by_username(project.owner.clone()).await;
+
+        if user.success == false {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Project owner is invalid!"),
+                payload: Option::None,
+            };
+        }
+
+        // check permission
+        let user = user.payload.unwrap();
+
+        let limit = if user
+            .level
+            .permissions
+            .contains(&"VIB:UploadLimit:Enterprise".to_string())
+        {
+            ProjectUploadLimit::Enterprise
+        } else {
+            ProjectUploadLimit::Default
+        };
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Limit exists"),
+            payload: Option::Some(limit),
+        }
+    }
+
+    /// Get a [`ProjectUpload`] (without its received chunks), only for the user that started it
+    ///
+    /// Expired uploads are deleted when they're found.
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `id` - upload ID
+    /// * `as_user` - the username of the user getting the upload
+    async fn get_upload_session(
+        &self,
+        name: &String,
+        id: &String,
+        as_user: Option<String>,
+    ) -> DefaultReturn<Option<ProjectUpload>> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"ProjectUploads\" WHERE \"project\" = ? AND \"id\" = ?"
+        } else {
+            "SELECT * FROM \"ProjectUploads\" WHERE \"project\" = $1 AND \"id\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(name)
+            .bind::<&String>(id)
+            .fetch_one(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Upload does not exist!"),
+                payload: Option::None,
+            };
+        }
+
+        // ...
+        let row = self.base.textify_row(res.unwrap()).data;
+        let upload = serde_json::from_str::<ProjectUpload>(row.get("upload").unwrap());
+
+        if upload.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Upload is invalid!"),
+                payload: Option::None,
+            };
+        }
+
+        let upload = upload.unwrap();
+
+        // check owner
+        if as_user.as_ref() != Option::Some(&upload.owner) {
+            return DefaultReturn {
+                success: false,
+                message: String::from("You do not have permission to manage this upload."),
+                payload: Option::None,
+            };
+        }
+
+        // check expiry
+        if utility::unix_epoch_timestamp() > upload.expires {
+            self.delete_upload_data(id).await;
+
+            return DefaultReturn {
+                success: false,
+                message: String::from("Upload has expired!"),
+                payload: Option::None,
+            };
+        }
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Upload exists"),
+            payload: Option::Some(upload),
+        }
+    }
+
+    /// Get a [`ProjectUpload`] with the numbers of the chunks it has received so far
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `id` - upload ID
+    /// * `as_user` - the username of the user getting the upload
+    pub async fn get_upload(
+        &self,
+        name: String,
+        id: String,
+        as_user: Option<String>,
+    ) -> DefaultReturn<Option<ProjectUpload>> {
+        let upload = self.get_upload_session(&name, &id, as_user).await;
+
+        if !upload.success {
+            return upload;
+        }
+
+        let mut upload = upload.payload.unwrap();
+
+        // received chunks
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT \"number\" FROM \"ProjectUploadChunks\" WHERE \"upload\" = ?"
+        } else {
+            "SELECT \"number\" FROM \"ProjectUploadChunks\" WHERE \"upload\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query).bind::<&String>(&id).fetch_all(c).await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from(res.err().unwrap().to_string()),
+                payload: Option::None,
+            };
+        }
+
+        for row in res.unwrap() {
+            let row = self.base.textify_row(row).data;
+
+            if let Ok(number) = row.get("number").unwrap().parse::<usize>() {
+                upload.received.push(number);
+            }
+        }
+
+        upload.received.sort();
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Upload exists"),
+            payload: Option::Some(upload),
+        }
+    }
+
+    /// Start a [`ProjectUpload`] in a [`Project`]
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `path` - path the file is stored at once the upload is finished
+    /// * `size` - file size (in bytes), limited by the project's [`ProjectUploadLimit`]
+    /// * `chunk_size` - size of every chunk but the last (in bytes), [`uploads::DEFAULT_CHUNK_SIZE`] if `None`
+    /// * `as_user` - the username of the user starting the upload
+    pub async fn create_upload(
+        &self,
+        name: String,
+        mut path: String,
+        size: usize,
+        chunk_size: Option<usize>,
+        as_user: Option<String>,
+    ) -> DefaultReturn<Option<ProjectUpload>> {
+        // get project
+        let existing = self.get_project_by_id(name.clone()).await;
+
+        if existing.success == false {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Project does not exist!"),
+                payload: Option::None,
+            };
+        }
+
+        let project = existing.payload.unwrap();
+
+        // make sure we can do this
+        if !self.can_manage_project(&project, as_user.clone()).await {
+            return DefaultReturn {
+                success: false,
+                message: String::from(
+                    "You do not have permission to manage this project's contents.",
+                ),
+                payload: Option::None,
+            };
+        }
+
+        // check path
+        if !path.starts_with("/") {
+            path = format!("/{}", path);
+        }
+
+        if let Err(e) = uploads::check_path(&path) {
+            return DefaultReturn {
+                success: false,
+                message: e,
+                payload: Option::None,
+            };
+        }
+
+        // check sizes
+        let chunk_size = chunk_size.unwrap_or(uploads::DEFAULT_CHUNK_SIZE);
+
+        if let Err(e) = uploads::check_chunk_size(chunk_size) {
+            return DefaultReturn {
+                success: false,
+                message: e,
+                payload: Option::None,
+            };
+        }
+
+        if let Err(e) = uploads::check_chunk_count(size, chunk_size) {
+            return DefaultReturn {
+                success: false,
+                message: e,
+                payload: Option::None,
+            };
+        }
+
+        let limit = self.get_project_upload_limit(&project).await;
+
+        if limit.success == false {
+            return DefaultReturn {
+                success: false,
+                message: limit.message,
+                payload: Option::None,
+            };
+        }
+
+        let limit = limit.payload.unwrap() as usize;
+
+        if size > limit {
+            return DefaultReturn {
+                success: false,
+                message: format!("Files can be at most {limit} bytes."),
+                payload: Option::None,
+            };
+        }
+
+        // check upload count
+        self.delete_expired_uploads(&name).await;
+
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT \"id\" FROM \"ProjectUploads\" WHERE \"project\" = ?"
+        } else {
+            "SELECT \"id\" FROM \"ProjectUploads\" WHERE \"project\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query).bind::<&String>(&name).fetch_all(c).await;
+
+        if res.is_ok() && (res.unwrap().len() >= uploads::MAX_UPLOADS) {
+            return DefaultReturn {
+                success: false,
+                message: String::from("This project has too many unfinished uploads."),
+                payload: Option::None,
+            };
+        }
+
+        // create upload
+        let now = utility::unix_epoch_timestamp();
+        let upload = ProjectUpload {
+            id: utility::random_id(),
+            path,
+            owner: as_user.unwrap(),
+            size,
+            chunk_size,
+            chunks: uploads::chunk_count(size, chunk_size),
+            created: now,
+            expires: now + uploads::UPLOAD_EXPIRY,
+            received: Vec::new(),
+        };
+
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "INSERT INTO \"ProjectUploads\" VALUES (?, ?, ?)"
+        } else {
+            "INSERT INTO \"ProjectUploads\" VALUES ($1, $2, $3)"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&upload.id)
+            .bind::<&String>(&name)
+            .bind::<&String>(&serde_json::to_string(&upload).unwrap())
+            .execute(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from(res.err().unwrap().to_string()),
+                payload: Option::None,
+            };
+        }
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Upload started"),
+            payload: Option::Some(upload),
+        }
+    }
+
+    /// Store a chunk of a [`ProjectUpload`], replacing the chunk if it was already received
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `id` - upload ID
+    /// * `number` - chunk number (starting at `0`)
+    /// * `content` - chunk content, must be exactly as long as the chunk
+    /// * `as_user` - the username of the user sending the chunk
+    pub async fn store_upload_chunk(
+        &self,
+        name: String,
+        id: String,
+        number: usize,
+        content: &[u8],
+        as_user: Option<String>,
+    ) -> DefaultReturn<Option<usize>> {
+        let upload = self.get_upload_session(&name, &id, as_user).await;
+
+        if !upload.success {
+            return DefaultReturn {
+                success: false,
+                message: upload.message,
+                payload: Option::None,
+            };
+        }
+
+        let upload = upload.payload.unwrap();
+
+        // check length
+        let expected = uploads::chunk_len(upload.size, upload.chunk_size, number);
+
+        if expected.is_none() {
+            return DefaultReturn {
+                success: false,
+                message: format!("Chunk numbers go from 0 to {}.", upload.chunks - 1),
+                payload: Option::None,
+            };
+        }
+
+        let expected = expected.unwrap();
+
+        if content.len() != expected {
+            return DefaultReturn {
+                success: false,
+                message: format!("Chunk {number} must be {expected} bytes."),
+                payload: Option::None,
+            };
+        }
+
+        // remove previous chunk
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "DELETE FROM \"ProjectUploadChunks\" WHERE \"upload\" = ? AND \"number\" = ?"
+        } else {
+            "DELETE FROM \"ProjectUploadChunks\" WHERE \"upload\" = $1 AND \"number\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&id)
+            .bind::<&String>(&number.to_string())
+            .execute(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from(res.err().unwrap().to_string()),
+                payload: Option::None,
+            };
+        }
+
+        // store chunk
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "INSERT INTO \"ProjectUploadChunks\" VALUES (?, ?, ?)"
+        } else {
+            "INSERT INTO \"ProjectUploadChunks\" VALUES ($1, $2, $3)"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&id)
+            .bind::<&String>(&number.to_string())
+            .bind::<&String>(&base64::engine::general_purpose::STANDARD.encode(content))
+            .execute(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from(res.err().unwrap().to_string()),
+                payload: Option::None,
+            };
+        }
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Chunk stored"),
+            payload: Option::Some(number),
+        }
+    }
+
+    /// Finish a [`ProjectUpload`], storing the file in its [`Project`]
+    ///
+    /// The chunks are appended to an [`uploads::ASSEMBLED`] row one at a time, and that row replaces
+    /// the file (or creates it) in a single transaction. Compressed variants are made the first time
+    /// the file is requested (see [`Database::get_file_variant_in_project`]). The upload is only
+    /// deleted once the file is stored, so a failed finish can be tried again.
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `id` - upload ID
+    /// * `checksum` - SHA-256 of the whole file (hex)
+    /// * `as_user` - the username of the user finishing the upload
+    pub async fn finish_upload(
+        &self,
+        name: String,
+        id: String,
+        checksum: String,
+        as_user: Option<String>,
+    ) -> DefaultReturn<Option<String>> {
+        let upload = self
+            .get_upload(name.clone(), id.clone(), as_user.clone())
+            .await;
+
+        if !upload.success {
+            return DefaultReturn {
+                success: false,
+                message: upload.message,
+                payload: Option::None,
+            };
+        }
+
+        let upload = upload.payload.unwrap();
+
+        // make sure we can (still) do this
+        let project = self.get_project_by_id(name.clone()).await;
+
+        if !project.success
+            || !self
+                .can_manage_project(project.payload.as_ref().unwrap(), as_user)
+                .await
+        {
+            return DefaultReturn {
+                success: false,
+                message: String::from(
+                    "You do not have permission to manage this project's contents.",
+                ),
+                payload: Option::None,
+            };
+        }
+
+        // check path (again, it's stored without the checks files stored in a single request get)
+        if let Err(e) = uploads::check_path(&upload.path) {
+            return DefaultReturn {
+                success: false,
+                message: e,
+                payload: Option::None,
+            };
+        }
+
+        // check chunks
+        let missing = upload.chunks.saturating_sub(upload.received.len());
+
+        if missing > 0 {
+            return DefaultReturn {
+                success: false,
+                message: format!("{missing} chunks are still missing."),
+                payload: Option::None,
+            };
+        }
+
+        // assemble file (a few chunks at a time)
+        let is_postgres = (self.base.db._type != "sqlite") & (self.base.db._type != "mysql");
+        let assembled = uploads::ASSEMBLED.to_string();
+
+        let query: &str = if !is_postgres {
+            "DELETE FROM \"ProjectUploadChunks\" WHERE \"upload\" = ? AND \"number\" = ?"
+        } else {
+            "DELETE FROM \"ProjectUploadChunks\" WHERE \"upload\" = $1 AND \"number\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        let _ = sqlquery(query)
+            .bind::<&String>(&id)
+            .bind::<&String>(&assembled)
+            .execute(c)
+            .await;
+
+        let query: &str = if !is_postgres {
+            "INSERT INTO \"ProjectUploadChunks\" VALUES (?, ?, '')"
+        } else {
+            "INSERT INTO \"ProjectUploadChunks\" VALUES ($1, $2, '')"
+        };
+
+        let res = sqlquery(query)
+            .bind::<&String>(&id)
+            .bind::<&String>(&assembled)
+            .execute(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: res.err().unwrap().to_string(),
+                payload: Option::None,
+            };
+        }
+
+        let mut assembler = uploads::Assembler::default();
+        let mut pending = String::new();
+
+        for number in 0..upload.chunks {
+            let query: &str = if !is_postgres {
+                "SELECT \"content\" FROM \"ProjectUploadChunks\" WHERE \"upload\" = ? AND \"number\" = ?"
+            } else {
+                "SELECT \"content\" FROM \"ProjectUploadChunks\" WHERE \"upload\" = $1 AND \"number\" = $2"
+            };
+
+            let res = sqlquery(query)
+                .bind::<&String>(&id)
+                .bind::<&String>(&number.to_string())
+                .fetch_one(c)
+                .await;
+
+            if res.is_err() {
+                return DefaultReturn {
+                    success: false,
+                    message: format!("Chunk {number} is missing."),
+                    payload: Option::None,
+                };
+            }
+
+            let row = self.base.textify_row(res.unwrap()).data;
+            let bytes =
+                base64::engine::general_purpose::STANDARD.decode(row.get("content").unwrap());
+
+            if bytes.is_err() {
+                return DefaultReturn {
+                    success: false,
+                    message: bytes.err().unwrap().to_string(),
+                    payload: Option::None,
+                };
+            }
+
+            pending.push_str(&assembler.push(&bytes.unwrap()));
+
+            if pending.len() < uploads::ASSEMBLE_BUFFER_SIZE {
+                continue;
+            }
+
+            let res = self.append_to_assembled_upload(&id, &pending).await;
+
+            if !res.success {
+                return res;
+            }
+
+            pending.clear();
+        }
+
+        let size = assembler.size;
+        let (rest, digest) = assembler.finish();
+        pending.push_str(&rest);
+
+        let res = self.append_to_assembled_upload(&id, &pending).await;
+
+        if !res.success {
+            return res;
+        }
+
+        // check file
+        if size != upload.size {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Received file doesn't match the upload size."),
+                payload: Option::None,
+            };
+        }
+
+        if !uploads::checksum_matches(&digest, &checksum) {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Received file doesn't match the checksum."),
+                payload: Option::None,
+            };
+        }
+
+        // store file (replacing the existing one)
+        let tx = self.base.db.client.begin().await;
+
+        if tx.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: tx.err().unwrap().to_string(),
+                payload: Option::None,
+            };
+        }
+
+        let mut tx = tx.unwrap();
+
+        let query: &str = if !is_postgres {
+            "DELETE FROM \"ProjectFiles\" WHERE \"project\" = ? AND \"path\" = ?"
+        } else {
+            "DELETE FROM \"ProjectFiles\" WHERE \"project\" = $1 AND \"path\" = $2"
+        };
+
+        let res = sqlquery(query)
+            .bind::<&String>(&name)
+            .bind::<&String>(&upload.path)
+            .execute(&mut *tx)
+            .await;
+
+        if res.is_err() {
+            // dropping the transaction rolls it back
+            return DefaultReturn {
+                success: false,
+                message: res.err().unwrap().to_string(),
+                payload: Option::None,
+            };
+        }
+
+        let query: &str = if !is_postgres {
+            "INSERT INTO \"ProjectFiles\" SELECT ?, ?, \"content\" FROM \"ProjectUploadChunks\" WHERE \"upload\" = ? AND \"number\" = ?"
+        } else {
+            "INSERT INTO \"ProjectFiles\" SELECT $1, $2, \"content\" FROM \"ProjectUploadChunks\" WHERE \"upload\" = $3 AND \"number\" = $4"
+        };
+
+        let res = sqlquery(query)
+            .bind::<&String>(&name)
+            .bind::<&String>(&upload.path)
+            .bind::<&String>(&id)
+            .bind::<&String>(&assembled)
+            .execute(&mut *tx)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: res.err().unwrap().to_string(),
+                payload: Option::None,
+            };
+        }
+
+        let res = tx.commit().await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: res.err().unwrap().to_string(),
+                payload: Option::None,
+            };
+        }
+
+        // update cache (variants are made again the next time the file is requested)
+        self.delete_file_variants(&name, &upload.path).await;
+
+        self.base
+            .cachedb
+            .remove(format!("project:{}:path:{}", name, upload.path))
+            .await;
+
+        self.touch_project(&name).await;
+        self.delete_upload_data(&id).await;
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Upload finished"),
+            payload: Option::Some(upload.path),
+        }
+    }
+
+    /// Append base64 to the [`uploads::ASSEMBLED`] row of a [`ProjectUpload`] (without any checks)
+    async fn append_to_assembled_upload(
+        &self,
+        id: &String,
+        piece: &String,
+    ) -> DefaultReturn<Option<String>> {
+        let query: &str = if self.base.db._type == "mysql" {
+            "UPDATE \"ProjectUploadChunks\" SET \"content\" = CONCAT(\"content\", ?) WHERE \"upload\" = ? AND \"number\" = ?"
+        } else if self.base.db._type == "sqlite" {
+            "UPDATE \"ProjectUploadChunks\" SET \"content\" = \"content\" || ? WHERE \"upload\" = ? AND \"number\" = ?"
+        } else {
+            "UPDATE \"ProjectUploadChunks\" SET (\"content\") = (\"content\" || $1) WHERE \"upload\" = $2 AND \"number\" = $3"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(piece)
+            .bind::<&String>(id)
+            .bind::<&String>(&uploads::ASSEMBLED.to_string())
+            .execute(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: res.err().unwrap().to_string(),
+                payload: Option::None,
+            };
+        }
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Appended"),
+            payload: Option::Some(id.to_owned()),
+        }
+    }
+
+    /// Cancel a [`ProjectUpload`], deleting every chunk it received
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `id` - upload ID
+    /// * `as_user` - the username of the user cancelling the upload
+    pub async fn delete_upload(
+        &self,
+        name: String,
+        id: String,
+        as_user: Option<String>,
+    ) -> DefaultReturn<Option<String>> {
+        let upload = self.get_upload_session(&name, &id, as_user).await;
+
+        if !upload.success {
+            return DefaultReturn {
+                success: false,
+                message: upload.message,
+                payload: Option::None,
+            };
+        }
+
+        // ...
+        self.delete_upload_data(&id).await;
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Upload cancelled"),
+            payload: Option::Some(id),
+        }
+    }
+
+    /// Delete the row and chunks of a [`ProjectUpload`] (without any checks)
+    async fn delete_upload_data(&self, id: &String) {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "DELETE FROM \"ProjectUploadChunks\" WHERE \"upload\" = ?"
+        } else {
+            "DELETE FROM \"ProjectUploadChunks\" WHERE \"upload\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let _ = sqlquery(query).bind::<&String>(id).execute(c).await;
+
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "DELETE FROM \"ProjectUploads\" WHERE \"id\" = ?"
+        } else {
+            "DELETE FROM \"ProjectUploads\" WHERE \"id\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let _ = sqlquery(query).bind::<&String>(id).execute(c).await;
+    }
+
+    /// Delete every expired [`ProjectUpload`] of a [`Project`]
+    async fn delete_expired_uploads(&self, name: &String) {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"ProjectUploads\" WHERE \"project\" = ?"
+        } else {
+            "SELECT * FROM \"ProjectUploads\" WHERE \"project\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query).bind::<&String>(name).fetch_all(c).await;
+
+        if res.is_err() {
+            return;
+        }
+
+        let now = utility::unix_epoch_timestamp();
+
+        for row in res.unwrap() {
+            let row = self.base.textify_row(row).data;
+            let upload = serde_json::from_str::<ProjectUpload>(row.get("upload").unwrap());
+
+            // uploads that can't be read can't be finished either
+            if upload.is_err() || (now > upload.unwrap().expires) {
+                self.delete_upload_data(row.get("id").unwrap()).await;
+            }
+        }
+    }
+
+    // rules
+
+    /// Make sure a rules file parses before it's stored, files that aren't rules files always pass
+    ///
+    /// # Arguments:
+    /// * `path` - file path
+    /// * `content` - base64 file content
+    pub fn check_rules_file(
+        &self,
+        path: &String,
+        content: &String,
+    ) -> DefaultReturn<Option<String>> {
+        if !headers::is_headers_file(path) && (path != redirects::REDIRECTS_FILE) {
+            return DefaultReturn {
+                success: true,
+                message: String::new(),
+                payload: Option::None,
+            };
+        }
+
+        let bytes = base64::engine::general_purpose::STANDARD.decode(content);
+
+        if bytes.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from(bytes.err().unwrap().to_string()),
+                payload: Option::None,
+            };
+        }
+
+        let bytes = bytes.unwrap();
+        let parsed = if path == redirects::REDIRECTS_FILE {
+            redirects::parse_file(&bytes).map(|_| ())
+        } else {
+            headers::parse_file(path, &bytes).map(|_| ())
+        };
+
+        if parsed.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: format!(
+                    "Failed to parse {}: {}",
+                    path,
+                    parsed.err().unwrap().join("; ")
+                ),
+                payload: Option::None,
+            };
+        }
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Rules are valid"),
+            payload: Option::Some(path.to_owned()),
+        }
+    }
+
+    /// Check the file a move puts at `new_path` if it becomes a rules file (see [`Database::check_rules_file`])
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `path` - current file path
+    /// * `new_path` - path the file is moved to
+    /// * `content` - base64 file content, read from the project if it's not given
+    async fn check_moved_rules_file(
+        &self,
+        name: &String,
+        path: &String,
+        new_path: &String,
+        content: Option<&String>,
+    ) -> DefaultReturn<Option<String>> {
+        if !headers::is_headers_file(new_path) && (new_path != redirects::REDIRECTS_FILE) {
+            return DefaultReturn {
+                success: true,
+                message: String::new(),
+                payload: Option::None,
+            };
+        }
+
+        if let Some(content) = content {
+            return self.check_rules_file(new_path, content);
+        }
+
+        let existing = self
+            .get_project_file_content(name.to_owned(), path.to_owned())
+            .await;
+
+        if !existing.success {
+            return DefaultReturn {
+                success: false,
+                message: existing.message,
+                payload: Option::None,
+            };
+        }
+
+        let content = base64::engine::general_purpose::STANDARD.encode(existing.payload.unwrap());
+        self.check_rules_file(new_path, &content)
+    }
+
+    // GET
+    /// Get the parsed [`HeaderRule`]s of a [`Project`] (from [`headers::HEADERS_FILE`] or [`headers::HEADERS_TOML_FILE`])
+    ///
+    /// Parsed rules are cached until the project's files change (see [`Database::touch_project`]).
+    /// Rule files that fail to parse are treated as empty.
+    pub async fn get_project_header_rules(&self, name: String) -> Vec<HeaderRule> {
+        // check in cache
+        let cached = self
+            .base
+            .cachedb
+            .get(format!("project:{}:rules:headers", name))
+            .await;
+
+        if cached.is_some() {
+            let rules = serde_json::from_str::<Vec<HeaderRule>>(&cached.unwrap());
+
+            if rules.is_ok() {
+                return rules.unwrap();
+            }
+        }
+
+        // load rules file
+        let mut rules: Vec<HeaderRule> = Vec::new();
+
+        for path in [headers::HEADERS_FILE, headers::HEADERS_TOML_FILE] {
+            let file = self
+                .get_project_file_content(name.clone(), path.to_string())
+                .await;
+
+            if !file.success {
+                continue;
+            }
+
+            rules = headers::parse_file(path, &file.payload.unwrap()).unwrap_or(Vec::new());
+            break;
+        }
+
+        // store in cache
+        self.base
+            .cachedb
+            .set(
+                format!("project:{}:rules:headers", name),
+                serde_json::to_string::<Vec<HeaderRule>>(&rules).unwrap(),
+            )
+            .await;
+
+        // return
+        rules
+    }
+
+    /// Get the generated sitemap of a [`Project`] (see [`sitemap::sitemap`])
+    ///
+    /// Sitemaps are cached (by base URL) until the project's files change (see [`Database::touch_project`]).
+    /// Only public projects have a sitemap (see [`sitemap::is_generated`]).
+    ///
+    /// # Arguments:
+    /// * `project` - the [`Project`]
+    /// * `base_url` - `scheme://{project}.{base}` the sitemap is requested from (see [`sitemap::base_url`])
+    pub async fn get_project_sitemap(&self, project: &Project, base_url: &str) -> String {
+        let key = format!("project:{}:rules:sitemap:{}", project.name, base_url);
+
+        // check in cache
+        let cached = self.base.cachedb.get(key.clone()).await;
+
+        if cached.is_some() {
+            return cached.unwrap();
+        }
+
+        // generate (the listing is checked like any other anonymous request)
+        let files = self
+            .get_project_files(project.name.clone(), Option::None, false)
+            .await;
+
+        let out = sitemap::sitemap(project, base_url, &files.payload);
+
+        // store in cache
+        self.base.cachedb.set(key, out.clone()).await;
+
+        // return
+        out
+    }
+
+    /// Get the parsed [`RedirectRule`]s of a [`Project`] (from [`redirects::REDIRECTS_FILE`])
+    ///
+    /// Parsed rules are cached until the project's files change (see [`Database::touch_project`]).
+    /// Rule files that fail to parse are treated as empty.
+    pub async fn get_project_redirect_rules(&self, name: String) -> Vec<RedirectRule> {
+        // check in cache
+        let cached = self
+            .base
+            .cachedb
+            .get(format!("project:{}:rules:redirects", name))
+            .await;
+
+        if cached.is_some() {
+            let rules = serde_json::from_str::<Vec<RedirectRule>>(&cached.unwrap());
+
+            if rules.is_ok() {
+                return rules.unwrap();
+            }
+        }
+
+        // load rules file
+        let file = self
+            .get_project_file_content(name.clone(), redirects::REDIRECTS_FILE.to_string())
+            .await;
+
+        let rules = if file.success {
+            redirects::parse_file(&file.payload.unwrap()).unwrap_or(Vec::new())
+        } else {
+            Vec::new()
+        };
+
+        // store in cache
+        self.base
+            .cachedb
+            .set(
+                format!("project:{}:rules:redirects", name),
+                serde_json::to_string::<Vec<RedirectRule>>(&rules).unwrap(),
+            )
+            .await;
+
+        // return
+        rules
+    }
+
+    // share links
+
+    /// Check if `as_user` can manage a [`Project`] (project owner or "VIB:Admin")
+    pub async fn can_manage_project(&self, project: &Project, as_user: Option<String>) -> bool {
+        if as_user.is_none() {
+            return false;
+        }
+
+        let user = self.auth.get_user_by_username(as_user.unwrap()).await;
+
+        if !user.success {
+            return false;
+        }
+
+        let user = user.payload.unwrap();
+
+        (user.user.username == project.owner)
+            | (user.level.permissions.contains(&String::from("VIB:Admin")))
+    }
+
+    /// Get the token of a [`ShareLink`]
+    fn share_link_token(&self, project: &Project, link: &ShareLink) -> String {
+        share::share_token(
+            &self.signing_key,
+            &project.name,
+            &project.private_metadata.share_key,
+            &link.id,
+            link.expires,
+        )
+    }
+
+    /// Check a share link token, returning the [`ShareLink`] it belongs to if it's valid
+    ///
+    /// # Arguments:
+    /// * `project` - the [`Project`] the token is used on
+    /// * `token` - the token (see [`share::share_token`])
+    pub fn check_share_token(&self, project: &Project, token: &str) -> Option<ShareLink> {
+        let (id, expires, signature) = share::parse_share_token(token)?;
+
+        if project.private_metadata.share_key.is_empty()
+            | (expires < utility::unix_epoch_timestamp())
+        {
+            return Option::None;
+        }
+
+        // make sure the link wasn't revoked
+        let link = project
+            .private_metadata
+            .share_links
+            .iter()
+            .find(|l| (l.id == id) && (l.expires == expires))?;
+
+        if !protection::verify_signature(
+            &self.signing_key,
+            &format!(
+                "share:{}:{}:{}:{}",
+                project.name, project.private_metadata.share_key, id, expires
+            ),
+            signature,
+        ) {
+            return Option::None;
+        }
+
+        Option::Some(link.clone())
+    }
+
+    // GET
+    /// Get the share links of a [`Project`] (expired links are left out)
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `as_user` - username of the account listing the links
+    pub async fn get_share_links(
+        &self,
+        name: String,
+        as_user: Option<String>,
+    ) -> DefaultReturn<Option<Vec<ShareLinkWithToken>>> {
+        let existing = self.get_project_by_id(name.clone()).await;
+
+        if !existing.success {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Project does not exist!"),
+                payload: Option::None,
+            };
+        }
+
+        let project = existing.payload.unwrap();
+
+        if !self.can_manage_project(&project, as_user).await {
+            return DefaultReturn {
+                success: false,
+                message: String::from(
+                    "You do not have permission to manage this project's contents.",
+                ),
+                payload: Option::None,
+            };
+        }
+
+        // ...
+        let now = utility::unix_epoch_timestamp();
+        let links: Vec<ShareLinkWithToken> = project
+            .private_metadata
+            .share_links
+            .iter()
+            .filter(|l| l.expires >= now)
+            .map(|l| ShareLinkWithToken {
+                link: l.clone(),
+                token: self.share_link_token(&project, l),
+            })
+            .collect();
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Share links exist"),
+            payload: Option::Some(links),
+        }
+    }
+
+    // SET
+    /// Create a share link for a [`Project`]
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `path` - path scope, a directory (ending with `/`) or a single file
+    /// * `duration` - how long (in ms) the link is valid for, at most [`share::MAX_SHARE_TIME`]
+    /// * `as_user` - username of the account creating the link
+    pub async fn create_share_link(
+        &self,
+        name: String,
+        mut path: String,
+        duration: u128,
+        as_user: Option<String>,
+    ) -> DefaultReturn<Option<ShareLinkWithToken>> {
+        let existing = self.get_project_by_id(name.clone()).await;
+
+        if !existing.success {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Project does not exist!"),
+                payload: Option::None,
+            };
+        }
+
+        let mut project = existing.payload.unwrap();
+
+        if !self.can_manage_project(&project, as_user).await {
+            return DefaultReturn {
+                success: false,
+                message: String::from(
+                    "You do not have permission to manage this project's contents.",
+                ),
+                payload: Option::None,
+            };
+        }
+
+        // check values
+        if (duration == 0) | (duration > share::MAX_SHARE_TIME) {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Share links must expire within 90 days"),
+                payload: Option::None,
+            };
+        }
+
+        if !path.starts_with("/") {
+            path = format!("/{path}");
+        }
+
+        if path.contains(".secrets") {
+            return DefaultReturn {
+                success: false,
+                message: String::from("This path requires protected read access."),
+                payload: Option::None,
+            };
+        }
+
+        // create link
+        let now = utility::unix_epoch_timestamp();
+        let link = ShareLink {
+            id: utility::random_id(),
+            path,
+            expires: now + duration,
+            created: now,
+        };
+
+        if project.private_metadata.share_key.is_empty() {
+            project.private_metadata.share_key = utility::random_id();
+        }
+
+        // forget expired links
+        project
+            .private_metadata
+            .share_links
+            .retain(|l| l.expires >= now);
+        project.private_metadata.share_links.push(link.clone());
+
+        let res = self
+            .edit_project_private_metadata_by_name(name, project.private_metadata.clone())
+            .await;
+
+        if !res.success {
+            return DefaultReturn {
+                success: false,
+                message: res.message,
+                payload: Option::None,
+            };
+        }
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Share link created"),
+            payload: Option::Some(ShareLinkWithToken {
+                token: self.share_link_token(&project, &link),
+                link,
+            }),
+        }
+    }
+
+    // DELETE
+    /// Revoke a share link of a [`Project`]
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `id` - link ID
+    /// * `as_user` - username of the account revoking the link
+    pub async fn revoke_share_link(
+        &self,
+        name: String,
+        id: String,
+        as_user: Option<String>,
+    ) -> DefaultReturn<Option<String>> {
+        let existing = self.get_project_by_id(name.clone()).await;
+
+        if !existing.success {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Project does not exist!"),
+                payload: Option::None,
+            };
+        }
+
+        let mut project = existing.payload.unwrap();
+
+        if !self.can_manage_project(&project, as_user).await {
+            return DefaultReturn {
+                success: false,
+                message: String::from(
+                    "You do not have permission to manage this project's contents.",
+                ),
+                payload: Option::None,
+            };
+        }
+
+        // ...
+        let count = project.private_metadata.share_links.len();
+        project.private_metadata.share_links.retain(|l| l.id != id);
+
+        if project.private_metadata.share_links.len() == count {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Share link does not exist!"),
+                payload: Option::None,
+            };
+        }
+
+        let res = self
+            .edit_project_private_metadata_by_name(name, project.private_metadata)
+            .await;
+
+        if !res.success {
+            return res;
+        }
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Share link revoked"),
+            payload: Option::Some(id),
+        }
+    }
+
+    /// Rotate the share key of a [`Project`], revoking every share link
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `as_user` - username of the account rotating the key
+    pub async fn rotate_share_key(
+        &self,
+        name: String,
+        as_user: Option<String>,
+    ) -> DefaultReturn<Option<String>> {
+        let existing = self.get_project_by_id(name.clone()).await;
+
+        if !existing.success {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Project does not exist!"),
+                payload: Option::None,
+            };
+        }
+
+        let mut project = existing.payload.unwrap();
+
+        if !self.can_manage_project(&project, as_user).await {
+            return DefaultReturn {
+                success: false,
+                message: String::from(
+                    "You do not have permission to manage this project's contents.",
+                ),
+                payload: Option::None,
+            };
+        }
+
+        // ...
+        project.private_metadata.share_key = utility::random_id();
+        project.private_metadata.share_links = Vec::new();
+
+        let res = self
+            .edit_project_private_metadata_by_name(name.clone(), project.private_metadata)
+            .await;
+
+        if !res.success {
+            return res;
+        }
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Share links revoked"),
+            payload: Option::Some(name),
+        }
+    }
+
+    // previews
+
+    /// Get the preview projects of a [`Project`] (without any checks)
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    pub async fn list_project_previews(&self, name: &String) -> Vec<Project> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT \"name\" FROM \"Projects\" WHERE \"name\" LIKE ?"
+        } else {
+            "SELECT \"name\" FROM \"Projects\" WHERE \"name\" LIKE $1"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&previews::project_name(name, "%"))
+            .fetch_all(c)
+            .await;
+
+        if res.is_err() {
+            return Vec::new();
+        }
+
+        // ...
+        let mut out: Vec<Project> = Vec::new();
+
+        for row in res.unwrap() {
+            let row = self.base.textify_row(row).data;
+            let preview = self
+                .get_project_by_id(row.get("name").unwrap().to_string())
+                .await;
+
+            if !preview.success {
+                continue;
+            }
+
+            // "_" is a wildcard in LIKE, so make sure this is really one of our previews
+            let preview = preview.payload.unwrap();
+
+            if preview.private_metadata.preview_of.as_ref() == Option::Some(name) {
+                out.push(preview);
+            }
+        }
+
+        out
+    }
+
+    /// Get a preview of a [`Project`] that `as_user` can manage
+    ///
+    /// Returns `(project, preview project)`.
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `preview` - preview name
+    /// * `as_user` - username of the account managing the preview
+    async fn get_managed_preview(
+        &self,
+        name: &String,
+        preview: &str,
+        as_user: Option<String>,
+    ) -> DefaultReturn<Option<(Project, Project)>> {
+        let existing = self.get_project_by_id(name.clone()).await;
+
+        if !existing.success {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Project does not exist!"),
+                payload: Option::None,
+            };
+        }
+
+        let project = existing.payload.unwrap();
+
+        if !self.can_manage_project(&project, as_user).await {
+            return DefaultReturn {
+                success: false,
+                message: String::from(
+                    "You do not have permission to manage this project's contents.",
+                ),
+                payload: Option::None,
+            };
+        }
+
+        // ...
+        let existing = self
+            .get_project_by_id(previews::project_name(name, preview))
+            .await;
+
+        let preview = existing
+            .payload
+            .filter(|p| p.private_metadata.preview_of.as_ref() == Option::Some(name));
+
+        if preview.is_none() {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Preview does not exist!"),
+                payload: Option::None,
+            };
+        }
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Preview exists"),
+            payload: Option::Some((project, preview.unwrap())),
+        }
+    }
+
+    /// Build the [`ProjectPreview`] of a preview project
+    fn project_preview(&self, name: &str, preview: &Project) -> ProjectPreview {
+        ProjectPreview {
+            name: preview
+                .name
+                .strip_suffix(&previews::project_name(name, ""))
+                .unwrap_or(&preview.name)
+                .to_string(),
+            project: preview.name.clone(),
+            file_privacy: preview.metadata.file_privacy.clone(),
+            created: preview.private_metadata.created,
+            updated: preview.private_metadata.last_modified(),
+        }
+    }
+
+    // GET
+    /// Get the previews of a [`Project`]
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `as_user` - username of the account listing the previews
+    pub async fn get_project_previews(
+        &self,
+        name: String,
+        as_user: Option<String>,
+    ) -> DefaultReturn<Option<Vec<ProjectPreview>>> {
+        let existing = self.get_project_by_id(name.clone()).await;
+
+        if !existing.success {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Project does not exist!"),
+                payload: Option::None,
+            };
+        }
+
+        let project = existing.payload.unwrap();
+
+        if !self.can_manage_project(&project, as_user).await {
+            return DefaultReturn {
+                success: false,
+                message: String::from(
+                    "You do not have permission to manage this project's contents.",
+                ),
+                payload: Option::None,
+            };
+        }
+
+        // ...
+        let out: Vec<ProjectPreview> = self
+            .list_project_previews(&name)
+            .await
+            .iter()
+            .map(|p| self.project_preview(&name, p))
+            .collect();
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Previews exist"),
+            payload: Option::Some(out),
+        }
+    }
+
+    // SET
+    /// Create a preview of a [`Project`], starting with a copy of the project's files and settings
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `preview` - preview name
+    /// * `private` - make the preview's files [`ProjectFilePrivacy::Private`]
+    /// * `as_user` - username of the account creating the preview
+    pub async fn create_preview(
+        &self,
+        name: String,
+        preview: String,
+        private: bool,
+        as_user: Option<String>,
+    ) -> DefaultReturn<Option<ProjectPreview>> {
+        let existing = self.get_project_by_id(name.clone()).await;
+
+        if !existing.success {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Project does not exist!"),
+                payload: Option::None,
+            };
+        }
+
+        let project = existing.payload.unwrap();
+
+        if !self.can_manage_project(&project, as_user).await {
+            return DefaultReturn {
+                success: false,
+                message: String::from(
+                    "You do not have permission to manage this project's contents.",
+                ),
+                payload: Option::None,
+            };
+        }
+
+        // check values
+        if project.private_metadata.preview_of.is_some() {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Previews can't have their own previews."),
+                payload: Option::None,
+            };
+        }
+
+        if let Err(e) = previews::check_name(&preview) {
+            return DefaultReturn {
+                success: false,
+                message: e,
+                payload: Option::None,
+            };
+        }
+
+        if self.list_project_previews(&name).await.len() >= previews::MAX_PREVIEWS {
+            return DefaultReturn {
+                success: false,
+                message: format!(
+                    "Projects can have at most {} previews.",
+                    previews::MAX_PREVIEWS
+                ),
+                payload: Option::None,
+            };
+        }
+
+        let preview_name = previews::project_name(&name, &preview);
+
+        if !hosts::is_reachable(&preview_name) {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Preview name is too long for this project."),
+                payload: Option::None,
+            };
+        }
+
+        if self.get_project_by_id(preview_name.clone()).await.success {
+            return DefaultReturn {
+                success: false,
+                message: String::from("A preview with this name already exists!"),
+                payload: Option::None,
+            };
+        }
+
+        // previews count towards the owner's project limit
+        let owner = self.auth.get_user_by_username(project.owner.clone()).await;
+
+        if !owner.success {
+            return DefaultReturn {
+                success: false,
+                message: String::from("User is invalid!"),
+                payload: Option::None,
+            };
+        }
+
+        let owner = owner.payload.unwrap();
+        let quota = self
+            .check_project_quota(&owner.user.username, &owner.level.permissions)
+            .await;
+
+        if !quota.success {
+            return DefaultReturn {
+                success: false,
+                message: quota.message,
+                payload: Option::None,
+            };
+        }
+
+        // create project
+        let mut metadata = project.metadata.clone();
+
+        if private {
+            metadata.file_privacy = ProjectFilePrivacy::Private;
+        }
+
+        let private_metadata = ProjectPrivateMetadata {
+            r#type: project.private_metadata.r#type.clone(),
+            preview_of: Option::Some(name.clone()),
+            password: project.private_metadata.password.clone(),
+            ..Default::default()
+        };
+
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "INSERT INTO \"Projects\" VALUES (?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"Projects\" VALUES ($1, $2, $3, $4, $5)"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&preview_name)
+            .bind::<&String>(&project.owner)
+            .bind::<&String>(&utility::unix_epoch_timestamp().to_string())
+            .bind::<&String>(
+                &serde_json::to_string::<ProjectPrivateMetadata>(&private_metadata).unwrap(),
+            )
+            .bind::<&String>(&serde_json::to_string::<ProjectMetadata>(&metadata).unwrap())
+            .execute(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: res.err().unwrap().to_string(),
+                payload: Option::None,
+            };
+        }
+
+        // copy files (and their variants)
+        for query in [
+            if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                "INSERT INTO \"ProjectFiles\" SELECT ?, \"path\", \"content\" FROM \"ProjectFiles\" WHERE \"project\" = ?"
+            } else {
+                "INSERT INTO \"ProjectFiles\" SELECT $1, \"path\", \"content\" FROM \"ProjectFiles\" WHERE \"project\" = $2"
+            },
+            if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                "INSERT INTO \"ProjectFileVariants\" SELECT ?, \"path\", \"encoding\", \"content\" FROM \"ProjectFileVariants\" WHERE \"project\" = ?"
+            } else {
+                "INSERT INTO \"ProjectFileVariants\" SELECT $1, \"path\", \"encoding\", \"content\" FROM \"ProjectFileVariants\" WHERE \"project\" = $2"
+            },
+        ] {
+            let c = &self.base.db.client;
+            let res = sqlquery(query)
+                .bind::<&String>(&preview_name)
+                .bind::<&String>(&name)
+                .execute(c)
+                .await;
+
+            if res.is_err() {
+                self.delete_project_data(&preview_name).await;
+
+                return DefaultReturn {
+                    success: false,
+                    message: res.err().unwrap().to_string(),
+                    payload: Option::None,
+                };
+            }
+        }
+
+        // return
+        let created = self.get_project_by_id(preview_name).await;
+
+        if !created.success {
+            return DefaultReturn {
+                success: false,
+                message: created.message,
+                payload: Option::None,
+            };
+        }
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Preview created"),
+            payload: Option::Some(self.project_preview(&name, &created.payload.unwrap())),
+        }
+    }
+
+    /// Promote a preview to production, replacing the [`Project`]'s files with the preview's
+    ///
+    /// Only files are promoted, the project keeps its own settings. The preview is removed.
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `preview` - preview name
+    /// * `as_user` - username of the account promoting the preview
+    pub async fn promote_preview(
+        &self,
+        name: String,
+        preview: String,
+        as_user: Option<String>,
+    ) -> DefaultReturn<Option<String>> {
+        let existing = self.get_managed_preview(&name, &preview, as_user).await;
+
+        if !existing.success {
+            return DefaultReturn {
+                success: false,
+                message: existing.message,
+                payload: Option::None,
+            };
+        }
+
+        let (project, preview) = existing.payload.unwrap();
+
+        // swap files in one transaction, so production is never left without them
+        let tx = self.base.db.client.begin().await;
+
+        if tx.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: tx.err().unwrap().to_string(),
+                payload: Option::None,
+            };
+        }
+
+        let mut tx = tx.unwrap();
+
+        for table in ["ProjectFiles", "ProjectFileVariants"] {
+            // remove production files
+            let query = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                format!("DELETE FROM \"{table}\" WHERE \"project\" = ?")
+            } else {
+                format!("DELETE FROM \"{table}\" WHERE \"project\" = $1")
+            };
+
+            let res = sqlquery(&query)
+                .bind::<&String>(&name)
+                .execute(&mut *tx)
+                .await;
+
+            if res.is_err() {
+                // dropping the transaction rolls it back
+                return DefaultReturn {
+                    success: false,
+                    message: res.err().unwrap().to_string(),
+                    payload: Option::None,
+                };
+            }
+
+            // move preview files
+            let query = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                format!("UPDATE \"{table}\" SET \"project\" = ? WHERE \"project\" = ?")
+            } else {
+                format!("UPDATE \"{table}\" SET (\"project\") = ($1) WHERE \"project\" = $2")
+            };
+
+            let res = sqlquery(&query)
+                .bind::<&String>(&name)
+                .bind::<&String>(&preview.name)
+                .execute(&mut *tx)
+                .await;
+
+            if res.is_err() {
+                return DefaultReturn {
+                    success: false,
+                    message: res.err().unwrap().to_string(),
+                    payload: Option::None,
+                };
+            }
+        }
+
+        let res = tx.commit().await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: res.err().unwrap().to_string(),
+                payload: Option::None,
+            };
+        }
+
+        // remove preview
+        let res = self.delete_project_data(&preview.name).await;
+
+        if !res.success {
+            return res;
+        }
+
+        // update cache
+        self.base
+            .cachedb
+            .remove_starting_with(format!("project:{}:path:*", name))
+            .await;
+
+        let res = self.touch_project(&project.name).await;
+
+        if !res.success {
+            return res;
+        }
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Preview promoted"),
+            payload: Option::Some(name),
+        }
+    }
+
+    // DELETE
+    /// Delete a preview of a [`Project`]
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `preview` - preview name
+    /// * `as_user` - username of the account deleting the preview
+    pub async fn delete_preview(
+        &self,
+        name: String,
+        preview: String,
+        as_user: Option<String>,
+    ) -> DefaultReturn<Option<String>> {
+        let existing = self.get_managed_preview(&name, &preview, as_user).await;
+
+        if !existing.success {
+            return DefaultReturn {
+                success: false,
+                message: existing.message,
+                payload: Option::None,
+            };
+        }
+
+        let (_, preview) = existing.payload.unwrap();
+        let res = self.delete_project_data(&preview.name).await;
+
+        if !res.success {
+            return res;
+        }
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Preview deleted"),
+            payload: Option::Some(preview.name),
+        }
+    }
+
+    // access logs
+
+    /// Add a record to the access log of a [`Project`]
+    ///
+    /// Days past the project's retention are deleted the first time something is logged on a new day.
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `policy` - the project's [`AccessLogPolicy`]
+    /// * `record` - the [`AccessLogRecord`]
+    pub async fn log_access(
+        &self,
+        name: String,
+        policy: &AccessLogPolicy,
+        record: AccessLogRecord,
+    ) -> DefaultReturn<Option<String>> {
+        let day = caching::w3c_date(record.timestamp);
+
+        // rotate
+        let rotated = self
+            .base
+            .cachedb
+            .get(format!("project:{}:logs:rotated", name))
+            .await;
+
+        if rotated.as_ref() != Option::Some(&day) {
+            let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql")
+            {
+                "DELETE FROM \"ProjectAccessLogs\" WHERE \"project\" = ? AND \"day\" < ?"
+            } else {
+                "DELETE FROM \"ProjectAccessLogs\" WHERE \"project\" = $1 AND \"day\" < $2"
+            };
+
+            let c = &self.base.db.client;
+            let _ = sqlquery(query)
+                .bind::<&String>(&name)
+                .bind::<&String>(&policy.oldest_day(record.timestamp))
+                .execute(c)
+                .await;
+
+            self.base
+                .cachedb
+                .set(format!("project:{}:logs:rotated", name), day.clone())
+                .await;
+        }
+
+        // store record
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "INSERT INTO \"ProjectAccessLogs\" VALUES (?, ?, ?)"
+        } else {
+            "INSERT INTO \"ProjectAccessLogs\" VALUES ($1, $2, $3)"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&name)
+            .bind::<&String>(&day)
+            .bind::<&String>(&serde_json::to_string(&record).unwrap())
+            .execute(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from(res.err().unwrap().to_string()),
+                payload: Option::None,
+            };
+        }
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Request logged"),
+            payload: Option::Some(day),
+        }
+    }
+
+    /// Get the access log of a [`Project`] for a single day (oldest record first)
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `day` - day (`YYYY-MM-DD`, UTC)
+    /// * `as_user` - the username of the user getting the log
+    pub async fn get_access_log(
+        &self,
+        name: String,
+        day: String,
+        as_user: Option<String>,
+    ) -> DefaultReturn<Option<Vec<AccessLogRecord>>> {
+        // get project
+        let existing = self.get_project_by_id(name.clone()).await;
+
+        if existing.success == false {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Project does not exist!"),
+                payload: Option::None,
+            };
+        }
+
+        let project = existing.payload.unwrap();
+
+        // make sure we can do this
+        if !self.can_manage_project(&project, as_user).await {
+            return DefaultReturn {
+                success: false,
+                message: String::from("You do not have permission to view this project's logs."),
+                payload: Option::None,
+            };
+        }
+
+        // check day
+        if !access_log::is_day(&day) {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Days must be formatted as YYYY-MM-DD."),
+                payload: Option::None,
+            };
+        }
+
+        // (days past the retention may not have been rotated out yet)
+        let oldest = project
+            .metadata
+            .access_log
+            .oldest_day(utility::unix_epoch_timestamp());
+
+        if day < oldest {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Logs for this day are no longer kept."),
+                payload: Option::None,
+            };
+        }
+
+        // ...
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"ProjectAccessLogs\" WHERE \"project\" = ? AND \"day\" = ?"
+        } else {
+            "SELECT * FROM \"ProjectAccessLogs\" WHERE \"project\" = $1 AND \"day\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&name)
+            .bind::<&String>(&day)
+            .fetch_all(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from(res.err().unwrap().to_string()),
+                payload: Option::None,
+            };
+        }
+
+        let mut records: Vec<AccessLogRecord> = Vec::new();
+
+        for row in res.unwrap() {
+            let row = self.base.textify_row(row).data;
+            let record = serde_json::from_str::<AccessLogRecord>(row.get("record").unwrap());
+
+            if record.is_ok() {
+                records.push(record.unwrap());
+            }
+        }
+
+        records.sort_by_key(|r| r.timestamp);
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: day,
+            payload: Option::Some(records),
+        }
+    }
+
+    // protection
+
+    /// Get the failed unlock attempts of a client on a [`ProjectFilePrivacy::Protected`] project
+    ///
+    /// Returns `(attempts, first attempt timestamp)`, attempts older than [`protection::LOCKOUT_TIME`]
+    /// are forgotten.
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `client` - client address
+    pub async fn get_failed_unlocks(&self, name: &String, client: &String) -> (u32, u128) {
+        let cached = self
+            .base
+            .cachedb
+            .get(format!("project:{}:unlock:{}", name, client))
+            .await;
+
+        if cached.is_none() {
+            return (0, 0);
+        }
+
+        let cached = cached.unwrap();
+        let (attempts, since) = cached.split_once(":").unwrap_or(("0", "0"));
+
+        let attempts = attempts.parse::<u32>().unwrap_or(0);
+        let since = since.parse::<u128>().unwrap_or(0);
+
+        if utility::unix_epoch_timestamp().saturating_sub(since) >= protection::LOCKOUT_TIME {
+            return (0, 0);
+        }
+
+        (attempts, since)
+    }
+
+    /// Check if a client is locked out of unlocking a [`ProjectFilePrivacy::Protected`] project
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `client` - client address
+    pub async fn is_unlock_locked(&self, name: &String, client: &String) -> bool {
+        let (attempts, _) = self.get_failed_unlocks(name, client).await;
+        attempts >= protection::MAX_ATTEMPTS
+    }
+
+    /// Record a failed unlock attempt of a client on a [`ProjectFilePrivacy::Protected`] project
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `client` - client address
+    pub async fn record_failed_unlock(&self, name: &String, client: &String) {
+        let (attempts, since) = self.get_failed_unlocks(name, client).await;
+
+        let since = if attempts == 0 {
+            utility::unix_epoch_timestamp()
+        } else {
+            since
+        };
+
+        self.base
+            .cachedb
+            .set(
+                format!("project:{}:unlock:{}", name, client),
+                format!("{}:{}", attempts + 1, since),
+            )
+            .await;
+    }
+
+    /// Forget the failed unlock attempts of a client (after a successful unlock)
+    ///
+    /// # Arguments:
+    /// * `name` - project name
+    /// * `client` - client address
+    pub async fn clear_failed_unlocks(&self, name: &String, client: &String) {
+        self.base
+            .cachedb
+            .remove(format!("project:{}:unlock:{}", name, client))
+            .await;
+    }
+
+    // social
+
+    // GET
+    /// Get the number of [`ProjectFavoriteLog`]s a [`Project`] has
+    pub async fn get_project_favorites(&self, name: String) -> DefaultReturn<i32> {
+        // get project
+        let existing = self.get_project_by_id(name.clone()).await;
+
+        if existing.success == false {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Project does not exist!"),
+                payload: 0,
+            };
+        }
+
+        // get favorites
+        DefaultReturn {
+            success: true,
+            message: name.clone(),
+            // favorites are stored in the "Logs" table AS WELL AS an incremented value in the cache,
+            // we read the value from cache when checking the project's favorites, but read the cache value when fetching number
+            payload: self
+                .base
+                .cachedb
+                .get(format!("social:project-favorites:{}", name))
+                .await
+                .unwrap_or(String::from("0"))
+                .parse::<i32>()
+                .unwrap(),
+        }
+    }
+
+    pub async fn get_user_project_favorite(
+        &self,
+        user: String,
+        project: String,
+        skip_existing_check: bool,
+    ) -> DefaultReturn<Option<Log>> {
+        // get project
+        if skip_existing_check == false {
+            let existing = self.get_project_by_id(project.clone()).await;
+
+            if existing.success == false {
+                return DefaultReturn {
+                    success: false,
+                    message: String::from("Project does not exist!"),
+                    payload: Option::None,
+                };
+            }
+        }
+
+        // ...
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"Logs\" WHERE \"content\" = ? AND \"logtype\" = 'project_favorite'"
+        } else {
+            "SELECT * FROM \"Logs\" WHERE \"content\" = $1 AND \"logtype\" = 'project_favorite'"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(
+                &serde_json::to_string::<ProjectFavoriteLog>(&ProjectFavoriteLog {
+                    user,
+                    project: project.clone(),
+                })
+                .unwrap(),
+            )
+            .fetch_one(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from(res.err().unwrap().to_string()),
+                payload: Option::None,
+            };
+        }
+
+        // ...
+        let row = res.unwrap();
+        let row = self.base.textify_row(row).data;
+
+        DefaultReturn {
+            success: true,
+            message: project,
+            payload: Option::Some(Log {
+                id: row.get("id").unwrap().to_string(),
+                logtype: row.get("logtype").unwrap().to_string(),
+                timestamp: row.get("timestamp").unwrap().parse::<u128>().unwrap(),
+                content: row.get("content").unwrap().to_string(),
+            }),
+        }
+    }
+
+    // SET
+    /// Toggle a [`ProjectFavoriteLog`] on a [`Project`] by `user` and `project`
+    pub async fn toggle_user_project_favorite(
+        &self,
+        user: String,
+        project: String,
+    ) -> DefaultReturn<Option<String>> {
+        // get project
+        let existing = self.get_project_by_id(project.clone()).await;
+
+        if existing.success == false {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Project does not exist!"),
+                payload: Option::None,
+            };
+        }
+
+        // check if user is project owner
+        let existing = existing.payload.unwrap();
+
+        if existing.owner == user {
+            return DefaultReturn {
+                success: false,
+                message: String::from("You're the project owner!"),
+                payload: Option::None,
+            };
+        }
+
+        // attempt to get the user's existing favorite
+        let existing_favorite = self
+            .get_user_project_favorite(user.clone(), project.clone(), true)
+            .await;
+
+        // if existing_favorite.message != project.clone() {
+        //     return DefaultReturn {
+        //         success: false,
+        //         message: existing_favorite.message,
+        //         payload: Option::None,
+        //     };
+        // }
+
+        // delete existing
+        if existing_favorite.success == true {
+            let payload = existing_favorite.payload.unwrap();
+
+            // decr favorites
+            self.base
+                .cachedb
+                .decr(format!("social:project-favorites:{}", project.clone()))
+                .await;
+
+            // handle log
+            return self.logs.delete_log(payload.id).await;
+        }
+        // add new
+        else {
+            // incr favorites
+            self.base
+                .cachedb
+                .incr(format!("social:project-favorites:{}", project.clone()))
+                .await;
+
+            // handle log
+            return self
+                .logs
+                .create_log(
+                    String::from("project_favorite"),
+                    serde_json::to_string::<ProjectFavoriteLog>(&ProjectFavoriteLog {
+                        user,
+                        project,
+                    })
+                    .unwrap(),
+                )
+                .await;
+        }
+    }
+
+    // conductor
 
     // GET
     /// Get a [`Project`] score from the `/.secrets/palette.toml` file
@@ -2162,6 +6301,7 @@ impl Database {
                 project,
                 String::from("/.secrets/palette.toml"),
                 Option::None,
+                Option::None,
                 true,
                 true,
             )
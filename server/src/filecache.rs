@@ -0,0 +1,399 @@
+//! In-process cache of decoded file contents
+//!
+//! Files (and their variants) are stored in `cachedb` as base64 strings, so every hit still has to
+//! go through the cache and decode the file. The most requested files are kept here, already
+//! decoded, under the same keys (`project:{name}:path:{path}`). The least recently used entries are
+//! evicted once the cache is over its memory budget.
+//!
+//! This cache belongs to a single process, so it's cleared by the same process that writes the files.
+//! Entries don't expire, so only one server instance can use it: instances hold a lease in `cachedb`
+//! (see [`crate::db::Database::claim_file_cache`]) and the cache stays inactive without it.
+use actix_web::web::Bytes;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Memory budget (in bytes) used when `FILE_CACHE_SIZE` is not set
+pub const DEFAULT_BUDGET: usize = 64 * 1024 * 1024;
+
+/// Largest entry (in bytes) stored when `FILE_CACHE_MAX_ENTRY_SIZE` is not set
+pub const DEFAULT_MAX_ENTRY_SIZE: usize = 1024 * 1024;
+
+/// `cachedb` key of the lease on the file cache (`{instance} {timestamp}`)
+pub const LEASE_KEY: &str = "server:file_cache";
+
+/// How often the instance holding the lease renews it
+pub const LEASE_RENEWAL: Duration = Duration::from_secs(10);
+
+/// Time (in milliseconds) after which a lease that wasn't renewed can be taken by another instance
+pub const LEASE_TIME: u128 = 30_000;
+
+/// Result of claiming the lease on the file cache
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lease {
+    /// this instance already held the lease, it can use the cache
+    Held,
+    /// the lease was free (or expired) and is now this instance's, it can use the cache once the
+    /// lease is still held on the next claim (another instance may have taken it at the same time)
+    Taken,
+    /// another instance holds the lease
+    Busy,
+}
+
+/// Generate a random id for this server instance
+pub fn instance_id() -> String {
+    format!("{:016x}", OsRng.next_u64())
+}
+
+/// Hit rate and memory usage of a [`FileCache`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileCacheStats {
+    /// memory budget (in bytes)
+    pub budget: usize,
+    /// largest entry stored (in bytes)
+    pub max_entry_size: usize,
+    /// if this instance holds the lease on the cache (see [`Lease`])
+    pub active: bool,
+    /// memory used by entries (in bytes)
+    pub size: usize,
+    /// number of entries
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// entries removed to stay under the budget
+    pub evictions: u64,
+    /// `hits / (hits + misses)`, `0` before the first lookup
+    pub hit_rate: f64,
+}
+
+struct Entry {
+    bytes: Bytes,
+    /// last use, the key of the entry in [`Inner::order`]
+    used: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<String, Entry>,
+    /// keys by last use (oldest first)
+    order: BTreeMap<u64, String>,
+    /// memory used by entries (in bytes)
+    size: usize,
+    /// use counter
+    clock: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl Inner {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.used);
+            self.size -= entry_size(key, &entry.bytes);
+        }
+    }
+}
+
+/// Memory used by an entry (its content and its key)
+fn entry_size(key: &str, bytes: &Bytes) -> usize {
+    key.len() + bytes.len()
+}
+
+/// Size-bounded least recently used cache of decoded file contents
+pub struct FileCache {
+    /// memory budget (in bytes), `0` disables the cache
+    budget: usize,
+    /// largest entry stored (in bytes)
+    max_entry_size: usize,
+    /// if entries are read and stored (see [`FileCache::set_active`])
+    active: AtomicBool,
+    inner: Mutex<Inner>,
+}
+
+impl FileCache {
+    /// Create a new [`FileCache`]
+    ///
+    /// # Arguments:
+    /// * `budget` - memory budget (in bytes), `0` disables the cache
+    /// * `max_entry_size` - largest entry stored (in bytes)
+    pub fn new(budget: usize, max_entry_size: usize) -> Self {
+        FileCache {
+            budget,
+            max_entry_size: max_entry_size.min(budget),
+            active: AtomicBool::new(true),
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    /// Create a new [`FileCache`] configured by `FILE_CACHE_SIZE` and `FILE_CACHE_MAX_ENTRY_SIZE` (in bytes)
+    pub fn from_env() -> Self {
+        let budget = std::env::var("FILE_CACHE_SIZE")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_BUDGET);
+
+        let max_entry_size = std::env::var("FILE_CACHE_MAX_ENTRY_SIZE")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MAX_ENTRY_SIZE);
+
+        FileCache::new(budget, max_entry_size)
+    }
+
+    /// Check if entries are read and stored
+    pub fn is_active(&self) -> bool {
+        (self.budget > 0) && self.active.load(Ordering::SeqCst)
+    }
+
+    /// Start or stop using the cache, stopping drops every entry
+    ///
+    /// An inactive cache misses every lookup and stores nothing, so another instance can change
+    /// files without this one serving stale copies.
+    pub fn set_active(&self, active: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        self.active.store(active, Ordering::SeqCst);
+
+        if !active {
+            inner.entries.clear();
+            inner.order.clear();
+            inner.size = 0;
+        }
+    }
+
+    /// Get an entry, marking it as recently used
+    pub fn get(&self, key: &str) -> Option<Bytes> {
+        if !self.is_active() {
+            return Option::None;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        let used = inner.tick();
+
+        let Some(entry) = inner.entries.get_mut(key) else {
+            inner.misses += 1;
+            return Option::None;
+        };
+
+        let previous = std::mem::replace(&mut entry.used, used);
+        let bytes = entry.bytes.clone();
+
+        let key = inner.order.remove(&previous).unwrap_or(key.to_string());
+        inner.order.insert(used, key);
+        inner.hits += 1;
+
+        Option::Some(bytes)
+    }
+
+    /// Store an entry, evicting the least recently used entries until it fits
+    ///
+    /// Entries larger than the maximum entry size are not stored.
+    pub fn insert(&self, key: String, bytes: Bytes) {
+        let size = entry_size(&key, &bytes);
+
+        if size > self.max_entry_size {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+
+        // checked while locked, so nothing is stored after the cache was stopped
+        if !self.is_active() {
+            return;
+        }
+
+        inner.remove(&key);
+
+        // evict
+        while inner.size + size > self.budget {
+            let Some((_, oldest)) = inner.order.pop_first() else {
+                break;
+            };
+
+            if let Some(entry) = inner.entries.remove(&oldest) {
+                inner.size -= entry_size(&oldest, &entry.bytes);
+                inner.evictions += 1;
+            }
+        }
+
+        // insert
+        let used = inner.tick();
+        inner.order.insert(used, key.clone());
+        inner.entries.insert(key, Entry { bytes, used });
+        inner.size += size;
+    }
+
+    /// Remove an entry
+    pub fn remove(&self, key: &str) {
+        self.inner.lock().unwrap().remove(key);
+    }
+
+    /// Remove every entry whose key starts with `prefix`
+    pub fn remove_starting_with(&self, prefix: &str) {
+        let mut inner = self.inner.lock().unwrap();
+
+        let keys: Vec<String> = inner
+            .entries
+            .keys()
+            .filter(|k| k.starts_with(prefix))
+            .cloned()
+            .collect();
+
+        for key in keys {
+            inner.remove(&key);
+        }
+    }
+
+    /// Get the current [`FileCacheStats`]
+    pub fn stats(&self) -> FileCacheStats {
+        let inner = self.inner.lock().unwrap();
+        let lookups = inner.hits + inner.misses;
+
+        FileCacheStats {
+            budget: self.budget,
+            max_entry_size: self.max_entry_size,
+            active: self.is_active(),
+            size: inner.size,
+            entries: inner.entries.len(),
+            hits: inner.hits,
+            misses: inner.misses,
+            evictions: inner.evictions,
+            hit_rate: if lookups == 0 {
+                0.0
+            } else {
+                inner.hits as f64 / lookups as f64
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Content of `len` bytes (most keys here are 2 bytes long, so their entries use `len + 2` bytes)
+    fn entry(byte: u8, len: usize) -> Bytes {
+        Bytes::from(vec![byte; len])
+    }
+
+    fn keys(cache: &FileCache) -> Vec<String> {
+        let inner = cache.inner.lock().unwrap();
+        inner.order.values().cloned().collect()
+    }
+
+    #[test]
+    fn update_then_read_returns_new_bytes() {
+        let cache = FileCache::new(1024, 1024);
+
+        cache.insert("k1".into(), entry(1, 10));
+        cache.insert("k1".into(), entry(2, 20));
+
+        assert_eq!(cache.get("k1"), Some(entry(2, 20)));
+        assert_eq!(cache.stats().size, 22);
+        assert_eq!(cache.stats().entries, 1);
+        assert_eq!(keys(&cache), vec!["k1"]);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = FileCache::new(100, 100);
+
+        cache.insert("k1".into(), entry(1, 30));
+        cache.insert("k2".into(), entry(2, 30));
+        cache.insert("k3".into(), entry(3, 30));
+        assert_eq!(cache.stats().size, 96);
+
+        // reading k1 makes k2 the oldest
+        assert!(cache.get("k1").is_some());
+        assert_eq!(keys(&cache), vec!["k2", "k3", "k1"]);
+
+        cache.insert("k4".into(), entry(4, 30));
+        assert_eq!(keys(&cache), vec!["k3", "k1", "k4"]);
+        assert_eq!(cache.get("k2"), None);
+
+        // a large entry evicts as many entries as it needs
+        cache.insert("k5".into(), entry(5, 60));
+        assert_eq!(keys(&cache), vec!["k4", "k5"]);
+
+        let stats = cache.stats();
+        assert_eq!(stats.size, 32 + 62);
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.evictions, 3);
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+        assert_eq!(stats.hit_rate, 0.5);
+    }
+
+    #[test]
+    fn skips_large_entries() {
+        let cache = FileCache::new(100, 20);
+
+        cache.insert("k1".into(), entry(1, 18));
+        cache.insert("k2".into(), entry(2, 19));
+
+        assert!(cache.get("k1").is_some());
+        assert!(cache.get("k2").is_none());
+        assert_eq!(cache.stats().size, 20);
+
+        // the maximum entry size is never over the budget
+        assert_eq!(FileCache::new(10, 20).stats().max_entry_size, 10);
+    }
+
+    #[test]
+    fn removes_by_prefix() {
+        let cache = FileCache::new(1024, 1024);
+
+        for key in [
+            "project:a:path:/index.html",
+            "project:a:path:/index.html:encoding:br",
+            "project:ab:path:/index.html",
+            "project:b:path:/index.html",
+        ] {
+            cache.insert(key.into(), entry(1, 10));
+        }
+
+        cache.remove_starting_with("project:a:");
+        assert_eq!(
+            keys(&cache),
+            vec!["project:ab:path:/index.html", "project:b:path:/index.html"]
+        );
+
+        cache.remove("project:b:path:/index.html");
+        assert_eq!(keys(&cache), vec!["project:ab:path:/index.html"]);
+        assert_eq!(cache.stats().size, "project:ab:path:/index.html".len() + 10);
+    }
+
+    #[test]
+    fn inactive_caches_store_nothing() {
+        let cache = FileCache::new(1024, 1024);
+        cache.insert("k1".into(), entry(1, 10));
+
+        // stopping drops every entry
+        cache.set_active(false);
+        assert!(!cache.is_active());
+        assert_eq!(cache.stats().size, 0);
+        assert_eq!(cache.get("k1"), None);
+
+        cache.insert("k1".into(), entry(1, 10));
+        assert_eq!(cache.stats().entries, 0);
+
+        cache.set_active(true);
+        assert_eq!(cache.get("k1"), None);
+        cache.insert("k1".into(), entry(1, 10));
+        assert_eq!(cache.get("k1"), Some(entry(1, 10)));
+
+        // a cache without a budget is never active
+        let disabled = FileCache::new(0, 1024);
+        disabled.set_active(true);
+        disabled.insert("k1".into(), entry(1, 1));
+        assert!(!disabled.is_active());
+        assert_eq!(disabled.get("k1"), None);
+    }
+}
@@ -5,8 +5,12 @@ use dotenv;
 
 pub mod api;
 pub mod db;
+pub mod filecache;
 pub mod pages;
+pub mod serve;
 pub mod serve_middleware;
+pub mod tls;
+pub mod uploads;
 
 use crate::db::AppData;
 
@@ -27,6 +31,12 @@ async fn main() -> std::io::Result<()> {
 
     let static_dir_flag: Option<String> = dorsal::get_named_argument(&args, "static-dir");
 
+    // signing key (signed cookies and links stop working when this changes, so it can't be random)
+    let signing_key: String = match dorsal::get_var("SIGNING_KEY") {
+        Option::Some(key) if key.len() >= 32 => key,
+        _ => panic!("Missing required SIGNING_KEY setting (at least 32 characters)!"),
+    };
+
     // create database
     let db_type: Option<String> = dorsal::get_named_argument(&args, "db-type");
     let db_host: Option<String> = dorsal::get_var("DB_HOST");
@@ -42,32 +52,72 @@ async fn main() -> std::io::Result<()> {
         panic!("Missing required database config settings!");
     }
 
-    let db: Database = Database::new(dorsal::DatabaseOpts {
-        _type: db_type,
-        host: db_host,
-        user: if db_is_other {
-            db_user.unwrap()
-        } else {
-            String::new()
-        },
-        pass: if db_is_other {
-            db_pass.unwrap()
-        } else {
-            String::new()
-        },
-        name: if db_is_other {
-            db_name.unwrap()
-        } else {
-            String::new()
+    let db: Database = Database::new(
+        dorsal::DatabaseOpts {
+            _type: db_type,
+            host: db_host,
+            user: if db_is_other {
+                db_user.unwrap()
+            } else {
+                String::new()
+            },
+            pass: if db_is_other {
+                db_pass.unwrap()
+            } else {
+                String::new()
+            },
+            name: if db_is_other {
+                db_name.unwrap()
+            } else {
+                String::new()
+            },
         },
-    })
+        signing_key,
+    )
     .await;
 
     db.init().await;
 
+    // file cache (only used while this instance holds its lease, see Database::claim_file_cache)
+    if db.file_cache.is_active() {
+        let db = db.clone();
+        let instance = filecache::instance_id();
+
+        db.file_cache.set_active(false);
+
+        actix_web::rt::spawn(async move {
+            let mut busy = false;
+
+            loop {
+                let lease = db.claim_file_cache(&instance).await;
+                let held = lease == filecache::Lease::Held;
+
+                if held != db.file_cache.is_active() {
+                    db.file_cache.set_active(held);
+                }
+
+                if (lease == filecache::Lease::Busy) && !busy {
+                    println!("[filecache] another instance holds the file cache, disabled here");
+                }
+
+                busy = lease == filecache::Lease::Busy;
+                actix_web::rt::time::sleep(filecache::LEASE_RENEWAL).await;
+            }
+        });
+    }
+
+    // tls
+    let tls_resolver = if let Some(dir) = tls::cert_dir() {
+        let resolver = std::sync::Arc::new(tls::CertResolver::new(dir)?);
+        resolver.watch();
+        Option::Some(resolver)
+    } else {
+        Option::None
+    };
+
     // start server
     println!("Starting server at: http://localhost:{port}");
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         let client = awc::Client::default();
         let data = web::Data::new(AppData {
             db: db.clone(),
@@ -79,7 +129,8 @@ async fn main() -> std::io::Result<()> {
             port: port,
         });
 
-        let cors = actix_cors::Cors::default().send_wildcard();
+        // projects have their own policies (see serve::cors), the app only accepts its own origin
+        let cors = serve::cors::api_cors();
 
         App::new()
             .app_data(web::Data::clone(&data))
@@ -106,18 +157,34 @@ async fn main() -> std::io::Result<()> {
             .service(crate::api::projects::create_request)
             .service(crate::api::projects::insert_file_request) // StaticFiles projects
             .service(crate::api::projects::move_file_request) // StaticFiles projects
+            .service(crate::api::projects::file_batch_request) // StaticFiles projects
             .service(crate::api::projects::update_metadata_request)
             .service(crate::api::projects::update_fields_request)
             .service(crate::api::projects::favorite_request)
+            .service(crate::api::projects::rotate_share_key_request)
+            .service(crate::api::projects::create_share_link_request)
+            .service(crate::api::projects::promote_preview_request)
+            .service(crate::api::projects::create_preview_request)
+            .service(crate::api::projects::finish_upload_request)
+            .service(crate::api::projects::create_upload_request)
             // PUT api
             .service(crate::api::projects::update_file_request) // StaticFiles projects
+            .service(crate::api::projects::upload_chunk_request)
             // DELETE api
             .service(crate::api::projects::delete_file_request) // StaticFiles projects
+            .service(crate::api::projects::revoke_share_link_request)
+            .service(crate::api::projects::delete_preview_request)
+            .service(crate::api::projects::delete_upload_request)
             .service(crate::api::projects::delete_project_request)
             // GET api
             .service(crate::api::auth::logout)
             .service(crate::api::projects::get_project_files_request) // StaticFiles projects
             .service(crate::api::projects::read_file_request) // StaticFiles projects
+            .service(crate::api::projects::get_share_links_request)
+            .service(crate::api::projects::get_previews_request)
+            .service(crate::api::projects::get_upload_request)
+            .service(crate::api::projects::get_access_log_request)
+            .service(crate::api::admin::cache_stats_request)
             // GET root
             .service(crate::pages::home::home_request)
             .service(crate::pages::dashboard::dashboard_request)
@@ -138,7 +205,20 @@ async fn main() -> std::io::Result<()> {
                 return crate::pages::errors::error404(req, data).await;
             }))
     })
-    .bind(("0.0.0.0", port))?
-    .run()
-    .await
+    .bind(("0.0.0.0", port))?;
+
+    if tls_resolver.is_some() {
+        let tls_port = tls::tls_port();
+        println!("Starting server at: https://localhost:{tls_port}");
+
+        server
+            .bind_rustls(
+                ("0.0.0.0", tls_port),
+                tls::server_config(tls_resolver.unwrap()),
+            )?
+            .run()
+            .await
+    } else {
+        server.run().await
+    }
 }
@@ -58,7 +58,9 @@ struct ProjectViewTemplate {
     project: Project,
     files: Vec<String>,
     asset_requests: String,
+    asset_bandwidth: String,
     favorites_count: i32,
+    content_host: String,
     // required fields (super::base)
     auth_state: bool,
     guppy: String,
@@ -97,6 +99,8 @@ struct ProjectFileEditorTemplate {
 #[template(path = "dashboard/project/settings.html")]
 struct ProjectSettingsTemplate {
     project: Project,
+    /// project metadata JSON, safe to embed in a `<script>` element
+    metadata: String,
     // required fields (super::base)
     auth_state: bool,
     guppy: String,
@@ -346,7 +350,15 @@ pub async fn project_view_request(
                     .get(format!("billing:requests:{}", project_name))
                     .await
                     .unwrap_or("0".to_string()),
+                asset_bandwidth: data
+                    .db
+                    .base
+                    .cachedb
+                    .get(format!("billing:bandwidth:{}", project_name))
+                    .await
+                    .unwrap_or("0".to_string()),
                 favorites_count,
+                content_host: crate::serve::security::content_host().unwrap_or_default(),
                 // required fields
                 auth_state: base.auth_state,
                 guppy: base.guppy,
@@ -484,6 +496,7 @@ pub async fn project_file_editor_request(
             project_name.to_string(),
             path.to_string(),
             Option::None,
+            Option::None,
             true,
             true,
         )
@@ -557,6 +570,32 @@ pub async fn project_settings_request(
         return super::errors::error404(req, data).await;
     }
 
+    let project = project.payload.unwrap();
+
+    // make sure we have permission to manage this project
+    let username = token_user
+        .as_ref()
+        .unwrap()
+        .payload
+        .as_ref()
+        .unwrap()
+        .user
+        .username
+        .clone();
+
+    if !data
+        .db
+        .can_manage_project(&project, Option::Some(username))
+        .await
+    {
+        return super::errors::error404(req, data).await;
+    }
+
+    // "</script>" in a metadata string can't close the script element
+    let metadata = serde_json::to_string(&project.metadata)
+        .unwrap()
+        .replace("<", "\\u003c");
+
     // ...
     let base = base::get_base_values(token_user.is_some());
     return HttpResponse::Ok()
@@ -564,7 +603,8 @@ pub async fn project_settings_request(
         .append_header(("Content-Type", "text/html"))
         .body(
             ProjectSettingsTemplate {
-                project: project.payload.unwrap(),
+                project,
+                metadata,
                 // required fields
                 auth_state: base.auth_state,
                 guppy: base.guppy,
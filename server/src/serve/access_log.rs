@@ -0,0 +1,234 @@
+//! Per-project access logs (see [`crate::db::ProjectMetadata::access_log`])
+//!
+//! Records are stored by day (UTC) and kept for the project's retention period, older days are
+//! deleted the first time something is logged on a new day. Client IPs are anonymized before
+//! they're stored.
+use actix_web::body::{BodySize, MessageBody};
+use actix_web::http::header;
+use actix_web::{HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+
+/// How many days records are kept when the project doesn't pick a retention
+pub const DEFAULT_RETENTION_DAYS: u32 = 7;
+
+/// Longest retention (in days) a project can pick
+pub const MAX_RETENTION_DAYS: u32 = 30;
+
+/// Length of a day (in ms)
+pub const DAY: u128 = 86_400_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AccessLogPolicy {
+    /// log every request served for the project
+    #[serde(default)]
+    pub enabled: bool,
+    /// how many days records are kept (at most [`MAX_RETENTION_DAYS`])
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32,
+}
+
+impl Default for AccessLogPolicy {
+    fn default() -> Self {
+        AccessLogPolicy {
+            enabled: false,
+            retention_days: default_retention_days(),
+        }
+    }
+}
+
+fn default_retention_days() -> u32 {
+    DEFAULT_RETENTION_DAYS
+}
+
+impl AccessLogPolicy {
+    /// Get the retention (in days), clamped to `1..=MAX_RETENTION_DAYS`
+    pub fn retention(&self) -> u32 {
+        self.retention_days.clamp(1, MAX_RETENTION_DAYS)
+    }
+
+    /// Get the first day (`YYYY-MM-DD`) that's still kept on the day of `timestamp`
+    pub fn oldest_day(&self, timestamp: u128) -> String {
+        let kept = (self.retention() as u128 - 1) * DAY;
+        super::caching::w3c_date(timestamp.saturating_sub(kept))
+    }
+}
+
+/// A single request in a project's access log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessLogRecord {
+    /// unix epoch timestamp (in ms) of the request
+    pub timestamp: u128,
+    /// anonymized client IP (see [`anonymize_ip`])
+    pub ip: String,
+    pub method: String,
+    /// request path, including the query string
+    pub path: String,
+    /// HTTP version (`HTTP/1.1`)
+    pub protocol: String,
+    pub status: u16,
+    /// size of the response body (in bytes)
+    pub bytes: u64,
+    #[serde(default)]
+    pub referrer: String,
+    #[serde(default)]
+    pub user_agent: String,
+}
+
+/// Format access logs can be downloaded in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccessLogFormat {
+    /// Combined Log Format (Apache, nginx)
+    Combined,
+    /// one JSON [`AccessLogRecord`] per line
+    JsonLines,
+}
+
+impl AccessLogFormat {
+    /// Parse a format name (`combined` or `jsonl`)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "combined" => Option::Some(AccessLogFormat::Combined),
+            "jsonl" => Option::Some(AccessLogFormat::JsonLines),
+            _ => Option::None,
+        }
+    }
+
+    /// Get the `Content-Type` of logs in this format
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            AccessLogFormat::Combined => "text/plain; charset=utf-8",
+            AccessLogFormat::JsonLines => "application/x-ndjson",
+        }
+    }
+
+    /// Get the file extension of logs in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AccessLogFormat::Combined => "log",
+            AccessLogFormat::JsonLines => "jsonl",
+        }
+    }
+}
+
+/// Check if `day` is a valid `YYYY-MM-DD` day
+pub fn is_day(day: &str) -> bool {
+    let parts: Vec<&str> = day.split("-").collect();
+
+    (parts.len() == 3)
+        && (parts[0].len() == 4)
+        && (parts[1].len() == 2)
+        && (parts[2].len() == 2)
+        && parts.iter().all(|p| p.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Anonymize a client address by zeroing its host part (the last octet of IPv4 addresses, everything
+/// after the first 48 bits of IPv6 addresses), `-` if it's not an IP address
+pub fn anonymize_ip(addr: &str) -> String {
+    let ip = addr
+        .parse::<IpAddr>()
+        .ok()
+        .or(addr.parse::<SocketAddr>().ok().map(|a| a.ip()))
+        .or(addr
+            .trim_start_matches("[")
+            .trim_end_matches("]")
+            .parse::<IpAddr>()
+            .ok());
+
+    match ip.map(|ip| ip.to_canonical()) {
+        Some(IpAddr::V4(ip)) => {
+            let [a, b, c, _] = ip.octets();
+            format!("{a}.{b}.{c}.0")
+        }
+        Some(IpAddr::V6(ip)) => {
+            let [a, b, c, ..] = ip.segments();
+            std::net::Ipv6Addr::new(a, b, c, 0, 0, 0, 0, 0).to_string()
+        }
+        None => String::from("-"),
+    }
+}
+
+/// Build the [`AccessLogRecord`] of a served request
+///
+/// # Arguments:
+/// * `req` - the request
+/// * `res` - the response it got
+/// * `timestamp` - unix epoch timestamp (in ms) of the request
+pub fn record(req: &HttpRequest, res: &HttpResponse, timestamp: u128) -> AccessLogRecord {
+    let get_header = |name: header::HeaderName| {
+        req.headers()
+            .get(name)
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or("")
+            .to_string()
+    };
+
+    AccessLogRecord {
+        timestamp,
+        ip: anonymize_ip(&super::security::client_addr(req)),
+        method: req.method().to_string(),
+        path: req
+            .uri()
+            .path_and_query()
+            .map(|p| p.as_str())
+            .unwrap_or("/")
+            .to_string(),
+        protocol: format!("{:?}", req.version()),
+        status: res.status().as_u16(),
+        bytes: match res.body().size() {
+            BodySize::Sized(size) => size,
+            _ => 0,
+        },
+        referrer: get_header(header::REFERER),
+        user_agent: get_header(header::USER_AGENT),
+    }
+}
+
+/// Format an [`AccessLogRecord`] as a Combined Log Format line
+pub fn combined(record: &AccessLogRecord) -> String {
+    // "Sun, 06 Nov 1994 08:49:37 GMT" -> "06/Nov/1994:08:49:37 +0000"
+    let date = super::caching::http_date(record.timestamp).to_string();
+    let parts: Vec<&str> = date.split(" ").collect();
+
+    let time = if parts.len() == 6 {
+        format!("{}/{}/{}:{} +0000", parts[1], parts[2], parts[3], parts[4])
+    } else {
+        date.clone()
+    };
+
+    let quote = |value: &str| {
+        if value.is_empty() {
+            String::from("-")
+        } else {
+            value.replace("\\", "\\\\").replace("\"", "\\\"")
+        }
+    };
+
+    format!(
+        "{} - - [{time}] \"{} {} {}\" {} {} \"{}\" \"{}\"",
+        record.ip,
+        record.method,
+        quote(&record.path),
+        record.protocol,
+        record.status,
+        record.bytes,
+        quote(&record.referrer),
+        quote(&record.user_agent)
+    )
+}
+
+/// Format [`AccessLogRecord`]s in the given [`AccessLogFormat`] (one record per line)
+pub fn format(records: &[AccessLogRecord], format: AccessLogFormat) -> String {
+    let mut out = String::new();
+
+    for record in records {
+        match format {
+            AccessLogFormat::Combined => out.push_str(&combined(record)),
+            AccessLogFormat::JsonLines => out.push_str(&serde_json::to_string(record).unwrap()),
+        }
+
+        out.push('\n');
+    }
+
+    out
+}
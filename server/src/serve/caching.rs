@@ -0,0 +1,85 @@
+//! HTTP caching (`ETag`, `Last-Modified`, `Cache-Control`)
+use actix_web::http::header::{self, EntityTag, Header, HttpDate};
+use actix_web::HttpRequest;
+use sha2::{Digest, Sha256};
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::db::{Project, ProjectFilePrivacy};
+
+/// `Cache-Control` used when no project rule matches, browsers must revalidate before reusing
+pub const DEFAULT_CACHE_CONTROL: &str = "public, no-cache";
+
+/// `Cache-Control` used for [`ProjectFilePrivacy::Private`] and [`ProjectFilePrivacy::Protected`] projects
+/// (shared caches must never store these)
+pub const PRIVATE_CACHE_CONTROL: &str = "private, no-cache";
+
+/// Build a strong [`EntityTag`] from the given file content
+pub fn etag(content: &[u8]) -> EntityTag {
+    EntityTag::new_strong(format!("{:x}", Sha256::digest(content)))
+}
+
+/// Convert a unix epoch timestamp (in milliseconds) into an [`HttpDate`]
+///
+/// HTTP dates only have second precision, so the timestamp is truncated to keep comparisons honest.
+pub fn http_date(timestamp: u128) -> HttpDate {
+    HttpDate::from(UNIX_EPOCH + Duration::from_secs((timestamp / 1000) as u64))
+}
+
+/// Format a unix epoch timestamp (in milliseconds) as a W3C date (`YYYY-MM-DD`)
+pub fn w3c_date(timestamp: u128) -> String {
+    // civil date from days since the epoch (Howard Hinnant's algorithm)
+    let days = (timestamp / 86_400_000) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Check if the request's validators still match the file, meaning we can respond with `304 Not Modified`
+///
+/// `If-None-Match` takes precedence over `If-Modified-Since` (RFC 9110, section 13.2.2).
+///
+/// # Arguments:
+/// * `req` - the request being answered
+/// * `etag` - current [`EntityTag`] of the file
+/// * `last_modified` - unix epoch timestamp (in milliseconds) of the last change to the file
+pub fn is_not_modified(req: &HttpRequest, etag: &EntityTag, last_modified: u128) -> bool {
+    if req.headers().contains_key(header::IF_NONE_MATCH) {
+        return match header::IfNoneMatch::parse(req) {
+            Ok(header::IfNoneMatch::Any) => true,
+            // If-None-Match uses the weak comparison function
+            Ok(header::IfNoneMatch::Items(tags)) => tags.iter().any(|t| t.weak_eq(etag)),
+            Err(_) => false,
+        };
+    }
+
+    match header::IfModifiedSince::parse(req) {
+        Ok(header::IfModifiedSince(since)) => http_date(last_modified) <= since,
+        Err(_) => false,
+    }
+}
+
+/// Get the `Cache-Control` value for a file in a [`Project`]
+///
+/// The first [`CacheControlRule`](crate::db::CacheControlRule) matching `path` wins.
+pub fn cache_control(project: &Project, path: &str) -> String {
+    if (project.metadata.file_privacy == ProjectFilePrivacy::Private)
+        | (project.metadata.file_privacy == ProjectFilePrivacy::Protected)
+    {
+        return PRIVATE_CACHE_CONTROL.to_string();
+    }
+
+    for rule in &project.metadata.cache_control {
+        if super::pattern::matches(&rule.path, path) {
+            return rule.value.clone();
+        }
+    }
+
+    DEFAULT_CACHE_CONTROL.to_string()
+}
@@ -0,0 +1,256 @@
+//! Cross-origin resource sharing (per-project policies, and the policy of the API)
+use actix_web::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use actix_web::http::{Method, StatusCode};
+use actix_web::middleware::Condition;
+use actix_web::{HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+
+/// How long (in seconds) browsers can cache preflight responses of the API
+pub const API_MAX_AGE: usize = 3600;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CorsPolicy {
+    /// origins that can read project files (`https://example.com`), `*` allows every origin
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    /// methods cross-origin requests can use
+    #[serde(default = "default_methods")]
+    pub allowed_methods: Vec<String>,
+    /// request headers cross-origin requests can send, `*` allows every header
+    #[serde(default)]
+    pub allowed_headers: Vec<String>,
+    /// response headers cross-origin requests can read
+    #[serde(default)]
+    pub exposed_headers: Vec<String>,
+    /// allow requests with credentials (cookies), can't be used when every origin (`*`) is allowed
+    #[serde(default)]
+    pub allow_credentials: bool,
+    /// how long (in seconds) browsers can cache preflight responses, `0` to not send `Access-Control-Max-Age`
+    #[serde(default)]
+    pub max_age: u32,
+}
+
+impl Default for CorsPolicy {
+    fn default() -> Self {
+        CorsPolicy {
+            allowed_origins: Vec::new(),
+            allowed_methods: default_methods(),
+            allowed_headers: Vec::new(),
+            exposed_headers: Vec::new(),
+            allow_credentials: false,
+            max_age: 0,
+        }
+    }
+}
+
+fn default_methods() -> Vec<String> {
+    vec![String::from("GET"), String::from("HEAD")]
+}
+
+impl CorsPolicy {
+    /// Make sure every value of the policy can be sent in a header, returning why it can't be used
+    pub fn check(&self) -> Result<(), String> {
+        for origin in &self.allowed_origins {
+            let host = origin
+                .strip_prefix("https://")
+                .or_else(|| origin.strip_prefix("http://"))
+                .unwrap_or("");
+
+            if (origin != "*") && (host.is_empty() || host.contains("/")) {
+                return Err(format!(
+                    "\"{origin}\" is not a valid origin (scheme://host[:port])"
+                ));
+            }
+        }
+
+        if self.allow_credentials && self.allowed_origins.iter().any(|o| o == "*") {
+            return Err(String::from(
+                "credentials can't be allowed for every origin (\"*\"), list the origins instead",
+            ));
+        }
+
+        for method in &self.allowed_methods {
+            if Method::from_bytes(method.as_bytes()).is_err() {
+                return Err(format!("\"{method}\" is not a valid method"));
+            }
+        }
+
+        for name in self.allowed_headers.iter().chain(&self.exposed_headers) {
+            if (name != "*") && HeaderName::from_bytes(name.as_bytes()).is_err() {
+                return Err(format!("\"{name}\" is not a valid header name"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Get the `Access-Control-Allow-Origin` value for `origin`, `None` if the origin isn't allowed
+pub fn allowed_origin(policy: &CorsPolicy, origin: &str) -> Option<String> {
+    if policy.allowed_origins.iter().any(|o| o == "*") {
+        // browsers refuse credentials with "*", checked policies never combine the two
+        return Option::Some(String::from("*"));
+    }
+
+    if policy
+        .allowed_origins
+        .iter()
+        .any(|o| o.trim_end_matches("/").eq_ignore_ascii_case(origin))
+    {
+        return Option::Some(origin.to_string());
+    }
+
+    Option::None
+}
+
+/// Get a header of the request as a string
+fn header_str(req: &HttpRequest, name: HeaderName) -> Option<&str> {
+    req.headers().get(name).and_then(|v| v.to_str().ok())
+}
+
+/// Check if a request is a CORS preflight request
+pub fn is_preflight(req: &HttpRequest) -> bool {
+    (req.method() == Method::OPTIONS)
+        && req.headers().contains_key(header::ORIGIN)
+        && req
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD)
+}
+
+/// Answer a preflight request
+///
+/// Allowed requests get `204 No Content` with the policy's headers, everything else gets
+/// `403 Forbidden` (and no CORS headers).
+pub fn preflight(policy: &CorsPolicy, req: &HttpRequest) -> HttpResponse {
+    let forbidden = || HttpResponse::build(StatusCode::FORBIDDEN).finish();
+
+    let origin = header_str(req, header::ORIGIN).unwrap_or("");
+    let Some(allow_origin) = allowed_origin(policy, origin) else {
+        return forbidden();
+    };
+
+    // method
+    let method = header_str(req, header::ACCESS_CONTROL_REQUEST_METHOD).unwrap_or("");
+
+    if !policy
+        .allowed_methods
+        .iter()
+        .any(|m| m.eq_ignore_ascii_case(method))
+    {
+        return forbidden();
+    }
+
+    // headers
+    let requested: Vec<&str> = header_str(req, header::ACCESS_CONTROL_REQUEST_HEADERS)
+        .unwrap_or("")
+        .split(",")
+        .map(|h| h.trim())
+        .filter(|h| !h.is_empty())
+        .collect();
+
+    let any_header = policy.allowed_headers.iter().any(|h| h == "*");
+
+    if !any_header
+        && !requested.iter().all(|r| {
+            policy
+                .allowed_headers
+                .iter()
+                .any(|h| h.eq_ignore_ascii_case(r))
+        })
+    {
+        return forbidden();
+    }
+
+    // build response
+    let mut res = HttpResponse::NoContent();
+
+    res.insert_header((header::ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin.clone()))
+        .insert_header((
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            policy.allowed_methods.join(", ").to_uppercase(),
+        ));
+
+    if !requested.is_empty() {
+        res.insert_header((header::ACCESS_CONTROL_ALLOW_HEADERS, requested.join(", ")));
+    }
+
+    if policy.allow_credentials {
+        res.insert_header((header::ACCESS_CONTROL_ALLOW_CREDENTIALS, "true"));
+    }
+
+    if policy.max_age > 0 {
+        res.insert_header((header::ACCESS_CONTROL_MAX_AGE, policy.max_age.to_string()));
+    }
+
+    if allow_origin != "*" {
+        res.insert_header((header::VARY, "Origin"));
+    }
+
+    res.finish()
+}
+
+/// Add the policy's headers to the response of a (non-preflight) cross-origin request
+///
+/// # Arguments:
+/// * `policy` - the project's [`CorsPolicy`]
+/// * `req` - the request
+/// * `headers` - headers of the response
+pub fn apply(policy: &CorsPolicy, req: &HttpRequest, headers: &mut HeaderMap) {
+    if policy.allowed_origins.is_empty() {
+        return;
+    }
+
+    // the response depends on the origin unless every origin gets "*"
+    if allowed_origin(policy, "").as_deref() != Option::Some("*") {
+        headers.append(header::VARY, HeaderValue::from_static("Origin"));
+    }
+
+    let Some(origin) = header_str(req, header::ORIGIN) else {
+        return;
+    };
+
+    let Some(allow_origin) = allowed_origin(policy, origin) else {
+        return;
+    };
+
+    let Ok(value) = HeaderValue::from_str(&allow_origin) else {
+        return;
+    };
+
+    headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, value);
+
+    if policy.allow_credentials {
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+            HeaderValue::from_static("true"),
+        );
+    }
+
+    if !policy.exposed_headers.is_empty() {
+        if let Ok(value) = HeaderValue::from_str(&policy.exposed_headers.join(", ")) {
+            headers.insert(header::ACCESS_CONTROL_EXPOSE_HEADERS, value);
+        }
+    }
+}
+
+/// Build the CORS policy of the API and dashboard, only the app itself (`HOST`, over http or https)
+/// can use them
+///
+/// Without `HOST` the app's own origin isn't known, so the policy is disabled.
+pub fn api_cors() -> Condition<actix_cors::Cors> {
+    let host = super::security::site_host();
+
+    let mut cors = actix_cors::Cors::default()
+        .allowed_methods(vec!["GET", "POST", "PUT", "DELETE"])
+        .allowed_header(header::CONTENT_TYPE)
+        .supports_credentials()
+        .max_age(API_MAX_AGE);
+
+    if let Some(ref host) = host {
+        cors = cors
+            .allowed_origin(&format!("https://{host}"))
+            .allowed_origin(&format!("http://{host}"));
+    }
+
+    Condition::new(host.is_some(), cors)
+}
@@ -0,0 +1,117 @@
+//! Content encoding (`Accept-Encoding`, compressed file variants)
+use actix_web::http::header;
+use actix_web::HttpRequest;
+use std::io::Write;
+
+/// File extensions that get compressed variants
+pub const COMPRESSIBLE_EXTENSIONS: &[&str] = &[
+    "html", "htm", "css", "js", "mjs", "json", "svg", "xml", "txt",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    /// `br`
+    Brotli,
+    /// `gzip`
+    Gzip,
+}
+
+impl Encoding {
+    /// All encodings we store variants for, in order of preference
+    pub const ALL: [Encoding; 2] = [Encoding::Brotli, Encoding::Gzip];
+
+    /// Get the `Content-Encoding` token for this encoding
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+}
+
+impl std::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Check if the file at `path` should have compressed variants
+pub fn is_compressible(path: &str) -> bool {
+    let ext = path.split(".").collect::<Vec<&str>>().pop().unwrap_or("");
+    COMPRESSIBLE_EXTENSIONS.contains(&ext.to_lowercase().as_str())
+}
+
+/// Compress `content` with the given [`Encoding`]
+pub fn compress(content: &[u8], encoding: Encoding) -> std::io::Result<Vec<u8>> {
+    match encoding {
+        Encoding::Brotli => {
+            let mut out = Vec::new();
+            let params = brotli::enc::BrotliEncoderParams {
+                quality: 11,
+                ..Default::default()
+            };
+
+            brotli::BrotliCompress(&mut &content[..], &mut out, &params)?;
+            Ok(out)
+        }
+        Encoding::Gzip => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+
+            encoder.write_all(content)?;
+            encoder.finish()
+        }
+    }
+}
+
+/// Pick the best [`Encoding`] the client accepts, `None` means the file should be sent as is
+///
+/// Follows the `Accept-Encoding` quality values; when they are equal, brotli wins.
+pub fn negotiate(req: &HttpRequest) -> Option<Encoding> {
+    let accept = req.headers().get(header::ACCEPT_ENCODING)?.to_str().ok()?;
+
+    let mut best: Option<(Encoding, f32)> = Option::None;
+    let mut wildcard: Option<f32> = Option::None;
+    let mut listed: Vec<(Encoding, f32)> = Vec::new();
+
+    for item in accept.split(",") {
+        let mut parts = item.split(";");
+        let token = parts.next().unwrap_or("").trim().to_lowercase();
+
+        // quality value, defaults to 1
+        let mut quality: f32 = 1.0;
+
+        for param in parts {
+            let param = param.trim();
+
+            if let Some(q) = param.strip_prefix("q=") {
+                quality = q.trim().parse::<f32>().unwrap_or(0.0);
+            }
+        }
+
+        match token.as_str() {
+            "br" => listed.push((Encoding::Brotli, quality)),
+            "gzip" | "x-gzip" => listed.push((Encoding::Gzip, quality)),
+            "*" => wildcard = Option::Some(quality),
+            _ => (),
+        }
+    }
+
+    for encoding in Encoding::ALL {
+        // encodings that aren't listed get the wildcard's quality
+        let quality = match listed.iter().find(|(e, _)| *e == encoding) {
+            Some((_, q)) => *q,
+            None => wildcard.unwrap_or(0.0),
+        };
+
+        if quality <= 0.0 {
+            continue;
+        }
+
+        if best.is_none() || (quality > best.unwrap().1) {
+            best = Option::Some((encoding, quality));
+        }
+    }
+
+    best.map(|(encoding, _)| encoding)
+}
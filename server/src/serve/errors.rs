@@ -0,0 +1,148 @@
+//! Error responses for project assets (custom error pages)
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+
+use crate::db::{Database, FileError, Project};
+
+/// Statuses a project can provide its own page for (`/404.html`, ...)
+pub const CUSTOM_PAGES: &[StatusCode] = &[
+    StatusCode::FORBIDDEN,
+    StatusCode::NOT_FOUND,
+    StatusCode::TOO_MANY_REQUESTS,
+];
+
+/// Message shown for [`FileError::Other`] (the error itself is only logged)
+pub const INTERNAL_ERROR_MESSAGE: &str = "Something went wrong, please try again later.";
+
+/// Who an error page is for, a project's own pages are only used when they could read them
+pub struct PageAccess<'a> {
+    pub project: &'a Project,
+    /// username of the user making the request
+    pub as_user: Option<String>,
+    /// share token of the request
+    pub share: Option<String>,
+    /// the request unlocked a protected project (see [`super::protection`])
+    pub unlocked: bool,
+}
+
+/// Get the response status for a failed [`Database::get_file_in_loaded_project`] call
+pub fn status_for(error: &FileError) -> StatusCode {
+    match error {
+        FileError::ProjectNotFound | FileError::NotFound => StatusCode::NOT_FOUND,
+        FileError::NotAllowed | FileError::Protected => StatusCode::FORBIDDEN,
+        FileError::LimitExceeded => StatusCode::TOO_MANY_REQUESTS,
+        FileError::Other(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Get the message shown for a failed [`Database::get_file_in_loaded_project`] call, database
+/// errors are logged instead of shown
+///
+/// # Arguments:
+/// * `error` - the error
+/// * `project` - project name
+/// * `path` - requested path
+pub fn message_for(error: &FileError, project: &str, path: &str) -> String {
+    if let FileError::Other(e) = error {
+        println!("[serve] failed to read {path} of {project}: {e}");
+        return String::from(INTERNAL_ERROR_MESSAGE);
+    }
+
+    error.to_string()
+}
+
+/// Escape the characters HTML doesn't allow in text
+fn escape_html(input: &str) -> String {
+    input
+        .replace("&", "&amp;")
+        .replace("<", "&lt;")
+        .replace(">", "&gt;")
+        .replace("\"", "&quot;")
+}
+
+/// Render the built-in error page
+///
+/// # Arguments:
+/// * `message` - error message (escaped here)
+/// * `site_host` - `HOST` of the server (for the stylesheet and homepage link)
+pub fn default_page(message: &str, site_host: &str) -> String {
+    let message = escape_html(message);
+
+    format!("<!DOCTYPE html>
+
+<html lang=\"en\">
+    <head>
+        <meta charset=\"UTF-8\" />
+        <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\" />
+        <title>Error! (Vibrant)</title>
+
+        <link rel=\"stylesheet\" href=\"//{site_host}/static/style.css\" />
+    </head>
+
+    <body>
+        <main class=\"small flex flex-column g-4\">
+            <div class=\"card secondary border round full flex justify-center align-center\">
+                <h3 class=\"no-margin text-center\">{message}</h3>
+            </div>
+
+            <div class=\"flex justify-center footernav\">
+                <span class=\"item\"><a href=\"/\">Root</a></span>
+                <span class=\"item\"><a href=\"//{site_host}\">🌸 Homepage</a></span>
+                <span class=\"item\"><a href=\"https://code.stellular.org/stellular/vibrant\">Source Code</a></span>
+            </div>
+        </main>
+    </body>
+</html>")
+}
+
+/// Build an error response for a project, using the project's own page for `status` when it has one
+/// that the visitor can read
+///
+/// # Arguments:
+/// * `db` - [`Database`] to load the error page from
+/// * `access` - the project and who is asking, `None` if the project doesn't exist
+/// * `status` - response status
+/// * `message` - error message (used by the built-in page)
+/// * `site_host` - `HOST` of the server
+pub async fn error_response(
+    db: &Database,
+    access: Option<PageAccess<'_>>,
+    status: StatusCode,
+    message: &str,
+    site_host: &str,
+) -> HttpResponse {
+    let mut res = HttpResponse::build(status);
+    res.append_header(("Content-Type", "text/html"));
+
+    for (name, value) in super::security::DEFAULT_HEADERS {
+        res.insert_header((*name, *value));
+    }
+
+    // custom page (only when the project's file privacy lets the visitor read it)
+    if let Some(access) = access {
+        let path = format!("/{}.html", status.as_u16());
+        let readable = match db.get_read_scope(
+            access.project,
+            access.as_user,
+            access.share,
+            access.unlocked,
+        ) {
+            Ok(Option::None) => true,
+            Ok(Option::Some(scope)) => super::share::in_scope(&scope, &path),
+            Err(_) => false,
+        };
+
+        if readable && CUSTOM_PAGES.contains(&status) {
+            let page = db
+                .get_project_file_content(access.project.name.clone(), path)
+                .await;
+
+            if page.success {
+                return res.body(page.payload.unwrap());
+            }
+        }
+    }
+
+    // built-in page
+    res.body(default_page(message, site_host))
+}
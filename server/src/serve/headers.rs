@@ -0,0 +1,230 @@
+//! Custom response headers (`/_headers`, `/.vibrant/headers.toml`)
+//!
+//! ```text
+//! # comments start with "#"
+//! /assets/*
+//!   Cache-Control: public, max-age=31536000, immutable
+//!
+//! /*
+//!   X-Frame-Options: DENY
+//! ```
+//!
+//! Every rule matching the path of the served file (the request path for redirects and error
+//! pages) is applied in file order, so later rules replace headers set by earlier ones. Rules apply
+//! to every response of the project, including `304 Not Modified` responses.
+use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue, CACHE_CONTROL};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Path of the `_headers` rules file
+pub const HEADERS_FILE: &str = "/_headers";
+
+/// Path of the TOML rules file, only used when [`HEADERS_FILE`] doesn't exist
+pub const HEADERS_TOML_FILE: &str = "/.vibrant/headers.toml";
+
+/// Headers that describe the body or the connection, projects can't set these
+pub const FORBIDDEN_HEADERS: &[&str] = &[
+    "connection",
+    "content-encoding",
+    "content-length",
+    "content-range",
+    "keep-alive",
+    "set-cookie",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HeaderRule {
+    /// path pattern (see [`super::pattern`])
+    pub path: String,
+    /// `(name, value)` pairs
+    pub headers: Vec<(String, String)>,
+}
+
+/// `/.vibrant/headers.toml` file
+#[derive(Deserialize)]
+struct HeadersToml {
+    #[serde(default)]
+    headers: Vec<HeadersTomlRule>,
+}
+
+#[derive(Deserialize)]
+struct HeadersTomlRule {
+    r#for: String,
+    #[serde(default)]
+    values: BTreeMap<String, String>,
+}
+
+/// Check if `path` is one of the header rules files
+pub fn is_headers_file(path: &str) -> bool {
+    (path == HEADERS_FILE) | (path == HEADERS_TOML_FILE)
+}
+
+/// Validate a single header, returning why it can't be used
+fn check_header(name: &str, value: &str) -> Result<(), String> {
+    if HeaderName::from_bytes(name.as_bytes()).is_err() {
+        return Err(format!("\"{name}\" is not a valid header name"));
+    }
+
+    if FORBIDDEN_HEADERS.contains(&name.to_lowercase().as_str()) {
+        return Err(format!("\"{name}\" can't be set by projects"));
+    }
+
+    if HeaderValue::from_str(value).is_err() {
+        return Err(format!("value of \"{name}\" is not a valid header value"));
+    }
+
+    Ok(())
+}
+
+/// Parse a `_headers` file
+///
+/// Returns every problem found (with its line number) if the file is invalid.
+pub fn parse(content: &str) -> Result<Vec<HeaderRule>, Vec<String>> {
+    let mut rules: Vec<HeaderRule> = Vec::new();
+    let mut errors: Vec<String> = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let number = i + 1;
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() | trimmed.starts_with("#") {
+            continue;
+        }
+
+        // unindented lines start a new rule
+        if !line.starts_with(char::is_whitespace) {
+            if !trimmed.starts_with("/") && !trimmed.starts_with("*") {
+                errors.push(format!(
+                    "line {number}: expected a path starting with \"/\""
+                ));
+                continue;
+            }
+
+            rules.push(HeaderRule {
+                path: trimmed.to_string(),
+                headers: Vec::new(),
+            });
+
+            continue;
+        }
+
+        // indented lines are headers
+        let Some(rule) = rules.last_mut() else {
+            errors.push(format!("line {number}: header is not under a path"));
+            continue;
+        };
+
+        let Some((name, value)) = trimmed.split_once(":") else {
+            errors.push(format!("line {number}: expected \"Name: value\""));
+            continue;
+        };
+
+        let (name, value) = (name.trim(), value.trim());
+
+        if let Err(e) = check_header(name, value) {
+            errors.push(format!("line {number}: {e}"));
+            continue;
+        }
+
+        rule.headers.push((name.to_string(), value.to_string()));
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(rules)
+}
+
+/// Parse a `/.vibrant/headers.toml` file
+///
+/// ```toml
+/// [[headers]]
+/// for = "/assets/*"
+///
+/// [headers.values]
+/// Cache-Control = "public, max-age=31536000, immutable"
+/// ```
+pub fn parse_toml(content: &str) -> Result<Vec<HeaderRule>, Vec<String>> {
+    let file = toml::from_str::<HeadersToml>(content).map_err(|e| vec![e.to_string()])?;
+
+    let mut rules: Vec<HeaderRule> = Vec::new();
+    let mut errors: Vec<String> = Vec::new();
+
+    for (i, rule) in file.headers.into_iter().enumerate() {
+        if !rule.r#for.starts_with("/") && !rule.r#for.starts_with("*") {
+            errors.push(format!("headers[{i}]: expected a path starting with \"/\""));
+        }
+
+        let mut headers: Vec<(String, String)> = Vec::new();
+
+        for (name, value) in rule.values {
+            if let Err(e) = check_header(&name, &value) {
+                errors.push(format!("headers[{i}]: {e}"));
+                continue;
+            }
+
+            headers.push((name, value));
+        }
+
+        rules.push(HeaderRule {
+            path: rule.r#for,
+            headers,
+        });
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(rules)
+}
+
+/// Parse a header rules file based on its `path`
+pub fn parse_file(path: &str, content: &[u8]) -> Result<Vec<HeaderRule>, Vec<String>> {
+    let content = std::str::from_utf8(content).map_err(|e| vec![e.to_string()])?;
+
+    if path == HEADERS_TOML_FILE {
+        parse_toml(content)
+    } else {
+        parse(content)
+    }
+}
+
+/// Path of the file a response served (after rewrites, path resolution and fallbacks), kept in
+/// the response extensions so rules match the file instead of the request path
+#[derive(Debug, Clone)]
+pub struct ServedPath(pub String);
+
+/// Apply every rule matching `path` to the given response headers
+///
+/// Responses that must not be cached (`Cache-Control: no-store`, like password and maintenance
+/// pages) keep their `Cache-Control`.
+pub fn apply(rules: &[HeaderRule], path: &str, headers: &mut HeaderMap) {
+    let no_store = headers
+        .get(CACHE_CONTROL)
+        .is_some_and(|v| v.as_bytes() == b"no-store");
+
+    for rule in rules {
+        if !super::pattern::matches(&rule.path, path) {
+            continue;
+        }
+
+        for (name, value) in &rule.headers {
+            // rules are validated when they're parsed
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                if no_store && (name == CACHE_CONTROL) {
+                    continue;
+                }
+
+                headers.insert(name, value);
+            }
+        }
+    }
+}
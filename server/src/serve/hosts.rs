@@ -0,0 +1,440 @@
+//! Resolving the project a request is for from its `Host` header
+//!
+//! Hosts are case-folded, stripped of their port and trailing dot, and mapped to ASCII with IDNA,
+//! so `Café.Example.com:443` and `xn--caf-dma.example.com` are the same host. The label in front of
+//! a base domain (`{label}.{CONTENT_HOST}`) is mapped back to unicode to get the project name.
+
+/// Labels in front of a base domain that never belong to a project
+pub const RESERVED_LABELS: &[&str] = &["www"];
+
+/// Prefix of IDNA (punycode) labels
+const ACE_PREFIX: &str = "xn--";
+
+/// Get the domains projects are served under, longest first
+///
+/// `CONTENT_HOST` can be a comma-separated list (the first domain is used for links), `HOST` is
+/// used when it isn't set.
+pub fn base_domains() -> Vec<String> {
+    let Some(hosts) = std::env::var("CONTENT_HOST")
+        .ok()
+        .or_else(super::security::site_host)
+    else {
+        return Vec::new();
+    };
+
+    parse_base_domains(&hosts)
+}
+
+/// Parse a comma-separated list of base domains (see [`base_domains`])
+pub fn parse_base_domains(hosts: &str) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+
+    for host in hosts.split(",") {
+        if let Some(host) = normalize(host) {
+            if !out.contains(&host) {
+                out.push(host);
+            }
+        }
+    }
+
+    // "a.example.com" has to be checked before "example.com"
+    out.sort_by_key(|h| std::cmp::Reverse(h.len()));
+    out
+}
+
+/// Normalize the value of a `Host` header
+///
+/// Returns the lowercase ASCII (IDNA) host without its port and trailing dot, or `None` if the
+/// value isn't a valid host. IPv6 literals keep their brackets.
+pub fn normalize(host: &str) -> Option<String> {
+    let host = host.trim();
+
+    if host.is_empty()
+        | host
+            .chars()
+            .any(|c| c.is_whitespace() | c.is_control() | "/\\@?#%".contains(c))
+    {
+        return Option::None;
+    }
+
+    // ipv6 literal
+    if host.starts_with("[") {
+        let (literal, rest) = host.split_once("]")?;
+
+        if !rest.is_empty() && !is_port(rest.strip_prefix(":")?) {
+            return Option::None;
+        }
+
+        let literal = &literal[1..];
+
+        if literal.is_empty() || !literal.chars().all(|c| c.is_ascii_hexdigit() | (c == ':')) {
+            return Option::None;
+        }
+
+        return Option::Some(format!("[{}]", literal.to_ascii_lowercase()));
+    }
+
+    // port
+    let host = match host.rsplit_once(":") {
+        Some((host, port)) => {
+            if !is_port(port) {
+                return Option::None;
+            }
+
+            host
+        }
+        None => host,
+    };
+
+    // fully qualified hosts end with a dot
+    let host = host.strip_suffix(".").unwrap_or(host);
+
+    if host.is_empty() | host.split(".").any(|l| l.is_empty()) {
+        return Option::None;
+    }
+
+    let ascii = idna::domain_to_ascii(host).ok()?;
+
+    // idna doesn't reject every broken punycode label
+    if ascii.is_empty()
+        | !ascii
+            .split(".")
+            .all(|l| is_ascii_label(l) && (!l.starts_with(ACE_PREFIX) || decode_label(l).is_some()))
+    {
+        return Option::None;
+    }
+
+    Option::Some(ascii)
+}
+
+/// Check if `label` is a valid ASCII host label (`_` is allowed, browsers accept it)
+fn is_ascii_label(label: &str) -> bool {
+    !label.is_empty()
+        && label
+            .chars()
+            .all(|c| c.is_ascii_lowercase() | c.is_ascii_digit() | (c == '-') | (c == '_'))
+}
+
+/// Check if `port` is a valid port (empty ports are allowed, `example.com:` is `example.com`)
+fn is_port(port: &str) -> bool {
+    port.is_empty() || (port.chars().all(|c| c.is_ascii_digit()) && port.parse::<u16>().is_ok())
+}
+
+/// Get the label in front of one of the `bases` of a [normalized](normalize) host
+///
+/// Returns `None` for the base domains themselves, nested subdomains (`a.b.{base}`) and
+/// [`RESERVED_LABELS`].
+pub fn project_label<'a>(host: &'a str, bases: &[String]) -> Option<&'a str> {
+    if bases.iter().any(|b| b == host) {
+        return Option::None;
+    }
+
+    for base in bases {
+        let Some(label) = host
+            .strip_suffix(base.as_str())
+            .and_then(|l| l.strip_suffix("."))
+        else {
+            continue;
+        };
+
+        if label.is_empty() | label.contains(".") | RESERVED_LABELS.contains(&label) {
+            return Option::None;
+        }
+
+        return Option::Some(label);
+    }
+
+    Option::None
+}
+
+/// Map a host label to a project name
+///
+/// Punycode labels are decoded (`xn--caf-dma` is `café`), and previews (`{preview}--{project}`) keep
+/// their ASCII prefix so the project part can be decoded on its own.
+pub fn label_to_name(label: &str) -> Option<String> {
+    if label.starts_with(ACE_PREFIX) {
+        return decode_label(label);
+    }
+
+    if let Some((preview, project)) = label.split_once(super::previews::SEPARATOR) {
+        if project.starts_with(ACE_PREFIX) {
+            return Option::Some(super::previews::project_name(
+                &decode_label(project)?,
+                preview,
+            ));
+        }
+    }
+
+    Option::Some(label.to_string())
+}
+
+/// Decode a punycode label
+fn decode_label(label: &str) -> Option<String> {
+    let (name, res) = idna::domain_to_unicode(label);
+
+    // punycode can encode anything, so make sure the name encodes back to the same label
+    if res.is_err() || (encode_label(&name).as_deref() != Option::Some(label)) {
+        return Option::None;
+    }
+
+    Option::Some(name)
+}
+
+/// Map a project name to its host label (the reverse of [`label_to_name`])
+///
+/// Returns `None` if the name can't be used in a host.
+pub fn name_to_label(name: &str) -> Option<String> {
+    if name.is_empty() | name.contains(".") {
+        return Option::None;
+    }
+
+    // previews are only encoded on their own when the preview name is ascii (which it always is)
+    if let Some((preview, project)) = name.split_once(super::previews::SEPARATOR) {
+        if let (true, Some(project)) = (preview.is_ascii(), encode_label(project)) {
+            return Option::Some(super::previews::project_name(
+                &project,
+                &preview.to_ascii_lowercase(),
+            ));
+        }
+    }
+
+    encode_label(name)
+}
+
+/// Encode a single label with IDNA
+fn encode_label(label: &str) -> Option<String> {
+    let ascii = idna::domain_to_ascii(label).ok()?;
+
+    if !is_ascii_label(&ascii) {
+        return Option::None;
+    }
+
+    Option::Some(ascii)
+}
+
+/// Check if a project name can be reached through a host
+///
+/// Hosts are case-folded and IDNA-mapped, so the name has to survive a round trip through its host
+/// label (ignoring case).
+pub fn is_reachable(name: &str) -> bool {
+    let Some(label) = name_to_label(name) else {
+        return false;
+    };
+
+    label_to_name(&label).is_some_and(|n| n == name.to_lowercase())
+}
+
+/// Get the name of the project a `Host` header is for
+///
+/// The name is lowercase, projects with uppercase names have to be looked up without case
+/// (see [`crate::db::Database::get_project_by_host_name`]).
+///
+/// # Arguments:
+/// * `host` - value of the `Host` header
+/// * `bases` - domains projects are served under (see [`base_domains`])
+pub fn resolve(host: &str, bases: &[String]) -> Option<String> {
+    let host = normalize(host)?;
+    label_to_name(project_label(&host, bases)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small deterministic generator so failures can be reproduced
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            // xorshift64
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+            items[(self.next() % items.len() as u64) as usize]
+        }
+    }
+
+    /// Pieces hosts are built from, picked to hit edge cases
+    const PIECES: &[&str] = &[
+        "a",
+        "Z",
+        "0",
+        "-",
+        "--",
+        "_",
+        ".",
+        "..",
+        ":",
+        ":80",
+        ":99999",
+        "[",
+        "]",
+        "::1",
+        "xn--",
+        "xn--caf-dma",
+        "café",
+        "É",
+        "ß",
+        "例え",
+        "ＡＢ",
+        "\u{200d}",
+        "\u{0}",
+        " ",
+        "/",
+        "@",
+        "%",
+        "www",
+        "example",
+        "com",
+        "localhost",
+        "https://",
+        "staging",
+    ];
+
+    fn random_host(rng: &mut Rng) -> String {
+        let len = rng.next() % 8;
+        (0..len).map(|_| rng.pick(PIECES)).collect()
+    }
+
+    fn bases() -> Vec<String> {
+        parse_base_domains("example.com, Sites.Example.com:8080, ＥＸＡＭＰＬＥ.org.")
+    }
+
+    #[test]
+    fn parses_base_domains() {
+        assert_eq!(
+            bases(),
+            vec!["sites.example.com", "example.com", "example.org"]
+        );
+    }
+
+    #[test]
+    fn normalizes_hosts() {
+        assert_eq!(normalize("Example.COM"), Some("example.com".into()));
+        assert_eq!(normalize("example.com:8080"), Some("example.com".into()));
+        assert_eq!(normalize("example.com."), Some("example.com".into()));
+        assert_eq!(normalize("example.com:"), Some("example.com".into()));
+        assert_eq!(
+            normalize("Café.example.com"),
+            Some("xn--caf-dma.example.com".into())
+        );
+        assert_eq!(normalize("[::1]:8080"), Some("[::1]".into()));
+        assert_eq!(normalize("example.com:http"), None);
+        assert_eq!(normalize("example.com:99999"), None);
+        assert_eq!(normalize("https://example.com"), None);
+        assert_eq!(normalize("a..example.com"), None);
+        assert_eq!(normalize("user@example.com"), None);
+        assert_eq!(normalize(""), None);
+        assert_eq!(normalize("."), None);
+    }
+
+    #[test]
+    fn resolves_projects() {
+        let bases = bases();
+
+        assert_eq!(resolve("blog.example.com", &bases), Some("blog".into()));
+        assert_eq!(resolve("Blog.Example.com:443", &bases), Some("blog".into()));
+        assert_eq!(
+            resolve("blog.sites.example.com", &bases),
+            Some("blog".into())
+        );
+        assert_eq!(resolve("blog.example.org.", &bases), Some("blog".into()));
+        assert_eq!(
+            resolve("xn--caf-dma.example.com", &bases),
+            Some("café".into())
+        );
+        assert_eq!(resolve("CAFÉ.example.com", &bases), Some("café".into()));
+        assert_eq!(
+            resolve("staging--xn--caf-dma.example.com", &bases),
+            Some("staging--café".into())
+        );
+        assert_eq!(
+            resolve("my_site.example.com", &bases),
+            Some("my_site".into())
+        );
+
+        // not projects
+        assert_eq!(resolve("example.com", &bases), None);
+        assert_eq!(resolve("sites.example.com", &bases), None);
+        assert_eq!(resolve("www.example.com", &bases), None);
+        assert_eq!(resolve("a.b.example.org", &bases), None);
+        assert_eq!(resolve("blog.example.net", &bases), None);
+        assert_eq!(resolve("blogexample.com", &bases), None);
+        assert_eq!(resolve("[::1]", &bases), None);
+    }
+
+    #[test]
+    fn round_trips_names() {
+        for name in ["blog", "my_site", "café", "例え", "staging--café", "Blog"] {
+            assert!(is_reachable(name), "{name} should be reachable");
+            assert_eq!(
+                resolve(
+                    &format!("{}.example.com", name_to_label(name).unwrap()),
+                    &bases()
+                ),
+                Some(name.to_lowercase())
+            );
+        }
+
+        for name in ["ＡＢ", "a.b", "", "caf\u{0}e"] {
+            assert!(!is_reachable(name), "{name:?} shouldn't be reachable");
+        }
+    }
+
+    #[test]
+    fn fuzz_normalize() {
+        let mut rng = Rng(0x5eed_1234_abcd_ef01);
+
+        for _ in 0..50_000 {
+            let host = random_host(&mut rng);
+            let Some(normalized) = normalize(&host) else {
+                continue;
+            };
+
+            // normalized hosts are lowercase ascii without ports, and normalizing is idempotent
+            assert!(normalized.is_ascii(), "{host:?} -> {normalized:?}");
+            assert_eq!(normalized, normalized.to_ascii_lowercase(), "{host:?}");
+            assert!(!normalized.ends_with("."), "{host:?} -> {normalized:?}");
+            assert!(
+                normalized.starts_with("[") || !normalized.contains(":"),
+                "{host:?} -> {normalized:?}"
+            );
+            assert_eq!(normalize(&normalized), Some(normalized.clone()), "{host:?}");
+        }
+    }
+
+    #[test]
+    fn fuzz_resolve() {
+        let mut rng = Rng(0x0ddc_0ffe_ef00_d001);
+        let bases = bases();
+
+        for _ in 0..50_000 {
+            let label = random_host(&mut rng);
+            let base = rng.pick(&["example.com", "EXAMPLE.org.", "sites.example.com:80", "x"]);
+            let host = format!("{label}.{base}");
+
+            let Some(name) = resolve(&host, &bases) else {
+                continue;
+            };
+
+            // resolved names are single lowercase labels that map back to the same host
+            assert!(
+                !name.is_empty() && !name.contains("."),
+                "{host:?} -> {name:?}"
+            );
+            assert_eq!(name, name.to_lowercase(), "{host:?} -> {name:?}");
+
+            let label =
+                name_to_label(&name).unwrap_or_else(|| panic!("{host:?} -> {name:?} has no label"));
+            assert_eq!(
+                resolve(&format!("{label}.example.com"), &bases),
+                Some(name.clone()),
+                "{host:?} -> {name:?} -> {label:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Hotlink protection (only letting allowed sites embed project files)
+use serde::{Deserialize, Serialize};
+
+/// Extensions protected by default (images, video and audio)
+pub const DEFAULT_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "avif", "svg", "mp4", "webm", "mov", "mp3", "ogg", "wav",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HotlinkPolicy {
+    /// only requests with a `Referer` of the project itself or an allowed host are served
+    #[serde(default)]
+    pub enabled: bool,
+    /// hosts that can embed project files, `*.` matches any subdomain (`example.com`, `*.example.com`)
+    #[serde(default)]
+    pub allowed_referrers: Vec<String>,
+    /// serve requests without a `Referer` (direct visits, and clients that strip it)
+    #[serde(default = "default_allow_empty")]
+    pub allow_empty: bool,
+    /// protected file extensions
+    #[serde(default = "default_extensions")]
+    pub extensions: Vec<String>,
+    /// path of a file in the project served instead of blocked files, empty to respond with `403 Forbidden`
+    #[serde(default)]
+    pub substitute: String,
+}
+
+impl Default for HotlinkPolicy {
+    fn default() -> Self {
+        HotlinkPolicy {
+            enabled: false,
+            allowed_referrers: Vec::new(),
+            allow_empty: default_allow_empty(),
+            extensions: default_extensions(),
+            substitute: String::new(),
+        }
+    }
+}
+
+fn default_allow_empty() -> bool {
+    true
+}
+
+fn default_extensions() -> Vec<String> {
+    DEFAULT_EXTENSIONS.iter().map(|e| e.to_string()).collect()
+}
+
+/// Get the host (without port) of a `Referer` header
+pub fn referrer_host(referrer: &str) -> Option<String> {
+    let rest = referrer
+        .split_once("://")
+        .map(|(_, r)| r)
+        .unwrap_or(referrer);
+    let authority = rest.split(['/', '?', '#']).next()?;
+
+    // drop credentials and port
+    let host = authority.rsplit("@").next()?;
+    let host = if host.starts_with("[") {
+        host.split_inclusive("]").next()?
+    } else {
+        host.split(":").next()?
+    };
+
+    if host.is_empty() {
+        return Option::None;
+    }
+
+    Option::Some(host.to_lowercase())
+}
+
+/// Check if `host` matches an allowed referrer (`example.com` or `*.example.com`)
+fn host_matches(allowed: &str, host: &str) -> bool {
+    let allowed = allowed.trim().to_lowercase();
+
+    match allowed.strip_prefix("*.") {
+        Some(base) => host.ends_with(&format!(".{base}")),
+        None => host == allowed,
+    }
+}
+
+/// Check if a request for the file at `path` is allowed by a [`HotlinkPolicy`]
+///
+/// # Arguments:
+/// * `policy` - the project's [`HotlinkPolicy`]
+/// * `path` - resolved path of the file
+/// * `referrer` - the request's `Referer` header
+/// * `own_host` - host the project is being served from (the project can always embed its own files)
+pub fn is_allowed(
+    policy: &HotlinkPolicy,
+    path: &str,
+    referrer: Option<&str>,
+    own_host: &str,
+) -> bool {
+    if !policy.enabled {
+        return true;
+    }
+
+    let protected = super::mime::extension(path)
+        .is_some_and(|ext| policy.extensions.iter().any(|e| e.to_lowercase() == ext));
+
+    if !protected {
+        return true;
+    }
+
+    let Some(host) = referrer.and_then(referrer_host) else {
+        return policy.allow_empty;
+    };
+
+    let own_host = own_host.split(":").next().unwrap_or("").to_lowercase();
+
+    (host == own_host)
+        | policy
+            .allowed_referrers
+            .iter()
+            .any(|a| host_matches(a, &host))
+}
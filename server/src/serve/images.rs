@@ -0,0 +1,192 @@
+//! On-the-fly image transformations (`?w=400&format=webp`)
+//!
+//! Widths and formats come from an allowlist, so a file can only ever have a small, fixed number of
+//! transformed variants (which are stored like compressed variants, see
+//! [`crate::db::Database::get_image_variant_in_project`]).
+use actix_web::web::Query;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, ImageReader, Limits};
+use std::collections::HashMap;
+use std::io::Cursor;
+
+/// Widths images can be resized to
+pub const ALLOWED_WIDTHS: &[u32] = &[
+    64, 128, 256, 320, 400, 480, 640, 768, 960, 1024, 1280, 1536, 1920, 2560,
+];
+
+/// Largest width (or height) of an image that can be transformed
+pub const MAX_SOURCE_DIMENSION: u32 = 8192;
+
+/// Most memory (in bytes) decoding an image can use
+pub const MAX_SOURCE_ALLOC: u64 = 256 * 1024 * 1024;
+
+/// Name of the width query parameter
+pub const WIDTH_QUERY: &str = "w";
+
+/// Name of the format query parameter
+pub const FORMAT_QUERY: &str = "format";
+
+/// Formats images can be converted to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Png,
+    Jpeg,
+    Webp,
+}
+
+impl OutputFormat {
+    /// Parse the value of the `format` query parameter
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "png" => Option::Some(OutputFormat::Png),
+            "jpeg" | "jpg" => Option::Some(OutputFormat::Jpeg),
+            "webp" => Option::Some(OutputFormat::Webp),
+            _ => Option::None,
+        }
+    }
+
+    /// Get the `Content-Type` of the format
+    pub fn mime(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "image/png",
+            OutputFormat::Jpeg => "image/jpeg",
+            OutputFormat::Webp => "image/webp",
+        }
+    }
+
+    fn image_format(&self) -> ImageFormat {
+        match self {
+            OutputFormat::Png => ImageFormat::Png,
+            OutputFormat::Jpeg => ImageFormat::Jpeg,
+            OutputFormat::Webp => ImageFormat::WebP,
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Png => write!(f, "png"),
+            OutputFormat::Jpeg => write!(f, "jpeg"),
+            OutputFormat::Webp => write!(f, "webp"),
+        }
+    }
+}
+
+/// A requested image transformation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    /// width to resize to (images are never made larger), `None` to keep the width
+    pub width: Option<u32>,
+    /// format of the result
+    pub format: OutputFormat,
+}
+
+impl Transform {
+    /// Key of the transformed variant (stored in the `encoding` column of file variants)
+    pub fn key(&self) -> String {
+        match self.width {
+            Some(width) => format!("image:{width}:{}", self.format),
+            None => format!("image:original:{}", self.format),
+        }
+    }
+}
+
+/// Check if the file at `path` is an image that can be transformed
+pub fn is_transformable(path: &str) -> bool {
+    source_format(path).is_some()
+}
+
+/// Get the format a transformed image keeps when no `format` is requested
+fn source_format(path: &str) -> Option<OutputFormat> {
+    match super::mime::extension(path)?.as_str() {
+        "png" | "gif" => Option::Some(OutputFormat::Png),
+        "jpg" | "jpeg" => Option::Some(OutputFormat::Jpeg),
+        "webp" => Option::Some(OutputFormat::Webp),
+        _ => Option::None,
+    }
+}
+
+/// Read the requested [`Transform`] of an image from a query string
+///
+/// Returns `Ok(None)` when no transformation is requested, and an error when the width or format
+/// isn't allowed.
+///
+/// # Arguments:
+/// * `path` - path of the image
+/// * `query` - query string of the request
+pub fn from_query(path: &str, query: &str) -> Result<Option<Transform>, String> {
+    let Some(source) = source_format(path) else {
+        return Ok(Option::None);
+    };
+
+    let Ok(query) = Query::<HashMap<String, String>>::from_query(query) else {
+        return Ok(Option::None);
+    };
+
+    let width = query.get(WIDTH_QUERY);
+    let format = query.get(FORMAT_QUERY);
+
+    if width.is_none() && format.is_none() {
+        return Ok(Option::None);
+    }
+
+    // width
+    let width = match width {
+        Some(width) => match width.parse::<u32>() {
+            Ok(width) if ALLOWED_WIDTHS.contains(&width) => Option::Some(width),
+            _ => return Err(format!("Width must be one of {:?}", ALLOWED_WIDTHS)),
+        },
+        None => Option::None,
+    };
+
+    // format
+    let format = match format {
+        Some(format) => match OutputFormat::from_name(format) {
+            Some(format) => format,
+            None => return Err(String::from("Format must be one of png, jpeg or webp")),
+        },
+        None => source,
+    };
+
+    Ok(Option::Some(Transform { width, format }))
+}
+
+/// Apply a [`Transform`] to an image
+///
+/// This is CPU heavy, it should be run on a blocking thread.
+pub fn transform(content: &[u8], transform: &Transform) -> Result<Vec<u8>, String> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Option::Some(MAX_SOURCE_DIMENSION);
+    limits.max_image_height = Option::Some(MAX_SOURCE_DIMENSION);
+    limits.max_alloc = Option::Some(MAX_SOURCE_ALLOC);
+
+    let mut reader = ImageReader::new(Cursor::new(content))
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?;
+
+    reader.limits(limits);
+    let mut image = reader.decode().map_err(|e| e.to_string())?;
+
+    // resize (keeping the aspect ratio)
+    if let Some(width) = transform.width {
+        if width < image.width() {
+            image = image.resize(width, u32::MAX, FilterType::Lanczos3);
+        }
+    }
+
+    // jpeg has no alpha channel, and webp is only encoded from 8-bit images
+    image = match transform.format {
+        OutputFormat::Png => image,
+        OutputFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8()),
+        OutputFormat::Webp => DynamicImage::ImageRgba8(image.to_rgba8()),
+    };
+
+    let mut out = Cursor::new(Vec::new());
+
+    image
+        .write_to(&mut out, transform.format.image_format())
+        .map_err(|e| e.to_string())?;
+
+    Ok(out.into_inner())
+}
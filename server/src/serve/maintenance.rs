@@ -0,0 +1,112 @@
+//! Maintenance mode (taking a project offline with `503 Service Unavailable`)
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::db::{Database, Project};
+
+/// How long (in seconds) clients are told to wait by default
+pub const DEFAULT_RETRY_AFTER: u32 = 3600;
+
+/// Message of the built-in maintenance page
+pub const MESSAGE: &str = "This site is down for maintenance.";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MaintenancePolicy {
+    /// answer requests with `503 Service Unavailable`
+    #[serde(default)]
+    pub enabled: bool,
+    /// path of a file in the project shown while the project is down, empty to use `/503.html` (or the built-in page)
+    #[serde(default)]
+    pub page: String,
+    /// value of the `Retry-After` header (in seconds), `0` to not send it
+    #[serde(default = "default_retry_after")]
+    pub retry_after: u32,
+    /// paths that keep working, `*` matches anything (`/health`, `/status/*`)
+    #[serde(default)]
+    pub allowed_paths: Vec<String>,
+    /// users (besides the project owner) that still see the project
+    #[serde(default)]
+    pub allowed_users: Vec<String>,
+}
+
+impl Default for MaintenancePolicy {
+    fn default() -> Self {
+        MaintenancePolicy {
+            enabled: false,
+            page: String::new(),
+            retry_after: default_retry_after(),
+            allowed_paths: Vec::new(),
+            allowed_users: Vec::new(),
+        }
+    }
+}
+
+fn default_retry_after() -> u32 {
+    DEFAULT_RETRY_AFTER
+}
+
+/// Check if a request for `path` is taken down by a [`MaintenancePolicy`]
+///
+/// # Arguments:
+/// * `policy` - the project's [`MaintenancePolicy`]
+/// * `path` - request path
+/// * `can_bypass` - the request is from someone that can always see the project
+pub fn is_down(policy: &MaintenancePolicy, path: &str, can_bypass: bool) -> bool {
+    policy.enabled
+        && !can_bypass
+        && !policy
+            .allowed_paths
+            .iter()
+            .any(|p| super::pattern::matches(p, path))
+}
+
+/// Build the maintenance response of a [`Project`]
+///
+/// # Arguments:
+/// * `db` - [`Database`] to load the maintenance page from
+/// * `project` - the [`Project`]
+/// * `site_host` - `HOST` of the server
+pub async fn response(db: &Database, project: &Project, site_host: &str) -> HttpResponse {
+    let policy = &project.metadata.maintenance;
+
+    // custom page
+    let page = if !policy.page.is_empty() {
+        db.get_project_file_content(project.name.clone(), policy.page.clone())
+            .await
+    } else {
+        db.get_project_file_content(project.name.clone(), String::from("/503.html"))
+            .await
+    };
+
+    let mut res = if page.success {
+        let mut res = HttpResponse::build(StatusCode::SERVICE_UNAVAILABLE);
+        res.append_header(("Content-Type", "text/html"));
+
+        for (name, value) in super::security::DEFAULT_HEADERS {
+            res.insert_header((*name, *value));
+        }
+
+        res.body(page.payload.unwrap())
+    } else {
+        super::errors::error_response(
+            db,
+            Option::None,
+            StatusCode::SERVICE_UNAVAILABLE,
+            MESSAGE,
+            site_host,
+        )
+        .await
+    };
+
+    // the real site comes back, so the maintenance page must not be cached
+    let headers = res.headers_mut();
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+
+    if policy.retry_after > 0 {
+        headers.insert(header::RETRY_AFTER, HeaderValue::from(policy.retry_after));
+    }
+
+    res
+}
@@ -0,0 +1,212 @@
+//! Content type detection (extension table, magic bytes, owner overrides)
+use actix_files::file_extension_to_mime;
+
+use crate::db::Project;
+
+/// Type used when nothing else is known about a file
+pub const DEFAULT_TYPE: &str = "application/octet-stream";
+
+/// Extensions that are missing from (or wrong in) the table of [`file_extension_to_mime`]
+const EXTENSION_TYPES: &[(&str, &str)] = &[
+    ("atom", "application/atom+xml"),
+    ("avif", "image/avif"),
+    ("cjs", "text/javascript"),
+    ("glb", "model/gltf-binary"),
+    ("gltf", "model/gltf+json"),
+    ("ico", "image/x-icon"),
+    ("ics", "text/calendar"),
+    ("js", "text/javascript"),
+    ("jsonld", "application/ld+json"),
+    ("map", "application/json"),
+    ("markdown", "text/markdown"),
+    ("md", "text/markdown"),
+    ("mjs", "text/javascript"),
+    ("otf", "font/otf"),
+    ("rss", "application/rss+xml"),
+    ("toml", "application/toml"),
+    ("ttf", "font/ttf"),
+    ("vtt", "text/vtt"),
+    ("wasm", "application/wasm"),
+    ("webmanifest", "application/manifest+json"),
+    ("webp", "image/webp"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+];
+
+/// Magic bytes of common binary formats
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"\x00\x00\x01\x00", "image/x-icon"),
+    (b"%PDF-", "application/pdf"),
+    (b"\x00asm", "application/wasm"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"wOFF", "font/woff"),
+    (b"wOF2", "font/woff2"),
+    (b"OggS", "audio/ogg"),
+    (b"ID3", "audio/mpeg"),
+    (b"fLaC", "audio/flac"),
+    (b"\x1a\x45\xdf\xa3", "video/webm"),
+];
+
+/// Kinds of RIFF containers (`RIFF{size}{kind}`)
+const RIFF_KINDS: &[(&[u8], &str)] = &[
+    (b"WEBP", "image/webp"),
+    (b"WAVE", "audio/wav"),
+    (b"AVI ", "video/x-msvideo"),
+];
+
+/// Brands of ISO base media files (`{size}ftyp{brand}`), unknown brands are `video/mp4`
+const FTYP_BRANDS: &[(&[u8], &str)] = &[
+    (b"avif", "image/avif"),
+    (b"heic", "image/heic"),
+    (b"qt  ", "video/quicktime"),
+];
+
+/// Get the (lowercase) extension of the last segment of `path`
+///
+/// Dotfiles (`/.well-known/.htaccess`) don't count as having an extension.
+pub fn extension(path: &str) -> Option<String> {
+    let name = path.rsplit("/").next().unwrap_or("");
+    let (stem, ext) = name.rsplit_once(".")?;
+
+    if stem.is_empty() || ext.is_empty() {
+        return Option::None;
+    }
+
+    Option::Some(ext.to_lowercase())
+}
+
+/// Get the content type of an extension, `None` if the extension isn't known
+pub fn from_extension(ext: &str) -> Option<String> {
+    if let Some((_, value)) = EXTENSION_TYPES.iter().find(|(e, _)| *e == ext) {
+        return Option::Some(value.to_string());
+    }
+
+    let guess = file_extension_to_mime(ext).essence_str().to_string();
+
+    if guess == DEFAULT_TYPE {
+        return Option::None;
+    }
+
+    Option::Some(guess)
+}
+
+/// Guess the content type of a file from its first bytes, `None` if nothing matches
+///
+/// Binary formats are matched by their magic bytes. Content that is valid UTF-8 (and has no
+/// `NUL` bytes) is text, with a few checks for markup.
+pub fn sniff(content: &[u8]) -> Option<String> {
+    for (signature, value) in SIGNATURES {
+        if content.starts_with(signature) {
+            return Option::Some(value.to_string());
+        }
+    }
+
+    // containers
+    if content.len() >= 12 {
+        let kind = &content[8..12];
+
+        if content.starts_with(b"RIFF") {
+            if let Some((_, value)) = RIFF_KINDS.iter().find(|(k, _)| *k == kind) {
+                return Option::Some(value.to_string());
+            }
+        } else if &content[4..8] == b"ftyp" {
+            let value = FTYP_BRANDS
+                .iter()
+                .find(|(b, _)| *b == kind)
+                .map(|(_, v)| *v)
+                .unwrap_or("video/mp4");
+
+            return Option::Some(value.to_string());
+        }
+    }
+
+    // text
+    let head = &content[..content.len().min(1024)];
+
+    if head.contains(&0) {
+        return Option::None;
+    }
+
+    let text = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        // the head may cut a character in half
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&head[..e.valid_up_to()]).ok()?,
+        Err(_) => return Option::None,
+    };
+
+    let start = text
+        .trim_start_matches('\u{feff}')
+        .trim_start()
+        .to_lowercase();
+
+    if start.starts_with("<!doctype html") | start.starts_with("<html") {
+        return Option::Some(String::from("text/html"));
+    }
+
+    if start.starts_with("<svg") | (start.starts_with("<?xml") && start.contains("<svg")) {
+        return Option::Some(String::from("image/svg+xml"));
+    }
+
+    if start.starts_with("<?xml") {
+        return Option::Some(String::from("application/xml"));
+    }
+
+    Option::Some(String::from("text/plain"))
+}
+
+/// Check if a content type is text (and should be sent with a charset)
+pub fn is_text(value: &str) -> bool {
+    let essence = value.split(";").next().unwrap_or("").trim().to_lowercase();
+
+    essence.starts_with("text/")
+        | essence.ends_with("+json")
+        | essence.ends_with("+xml")
+        | [
+            "application/javascript",
+            "application/json",
+            "application/xml",
+            "application/toml",
+            "application/yaml",
+        ]
+        .contains(&essence.as_str())
+}
+
+/// Add `charset=utf-8` to text content types that don't specify a charset
+pub fn with_charset(value: String) -> String {
+    if !is_text(&value) || value.to_lowercase().contains("charset=") {
+        return value;
+    }
+
+    format!("{value}; charset=utf-8")
+}
+
+/// Get the `Content-Type` of a file in a [`Project`]
+///
+/// The first [`ContentTypeRule`](crate::db::ContentTypeRule) matching `path` wins. Otherwise the
+/// type is chosen by extension, then by sniffing `content` (for files without a known extension).
+///
+/// # Arguments:
+/// * `project` - the [`Project`] the file belongs to
+/// * `path` - resolved path of the file
+/// * `content` - file content (never a compressed variant)
+pub fn content_type(project: &Project, path: &str, content: &[u8]) -> String {
+    for rule in &project.metadata.content_types {
+        if super::pattern::matches(&rule.path, path) {
+            return with_charset(rule.value.clone());
+        }
+    }
+
+    let detected = extension(path)
+        .and_then(|ext| from_extension(&ext))
+        .or_else(|| sniff(content))
+        .unwrap_or(DEFAULT_TYPE.to_string());
+
+    with_charset(detected)
+}
@@ -0,0 +1,260 @@
+//! Helpers for serving project files
+use actix_web::http::header::{self, EntityTag};
+use actix_web::http::{Method, StatusCode};
+use actix_web::web::Bytes;
+use actix_web::{HttpRequest, HttpResponse};
+
+use crate::db::{Database, Project};
+use encoding::Encoding;
+
+pub mod access_log;
+pub mod caching;
+pub mod cors;
+pub mod encoding;
+pub mod errors;
+pub mod headers;
+pub mod hosts;
+pub mod hotlink;
+pub mod images;
+pub mod maintenance;
+pub mod mime;
+pub mod paths;
+pub mod pattern;
+pub mod previews;
+pub mod protection;
+pub mod range;
+pub mod redirects;
+pub mod security;
+pub mod share;
+pub mod sitemap;
+
+/// Build a redirect response
+///
+/// # Arguments:
+/// * `status` - redirect status (`301`, `302`, `307` or `308`)
+/// * `location` - value of the `Location` header
+pub fn redirect(status: StatusCode, location: String) -> HttpResponse {
+    HttpResponse::build(status)
+        .insert_header((header::LOCATION, location))
+        .finish()
+}
+
+/// Serve a file in a [`Project`], using a compressed variant when the client accepts one
+///
+/// # Arguments:
+/// * `req` - the request being answered
+/// * `db` - [`Database`] to load file variants from
+/// * `project` - the [`Project`] the file belongs to
+/// * `path` - resolved path of the file (`message` of [`Database::get_file_in_project`])
+/// * `content` - file content
+/// * `set_cookie` - value of the `Set-Cookie` header
+pub async fn serve_file(
+    req: &HttpRequest,
+    db: &Database,
+    project: &Project,
+    path: String,
+    content: Bytes,
+    set_cookie: String,
+) -> HttpResponse {
+    let mut variant: Option<(Encoding, Bytes)> = Option::None;
+
+    // byte ranges are always served from the original file
+    if encoding::is_compressible(&path) && !req.headers().contains_key(header::RANGE) {
+        if let Some(encoding) = encoding::negotiate(req) {
+            let res = db
+                .get_file_variant_in_project(project.name.clone(), path.clone(), encoding, &content)
+                .await;
+
+            if res.success {
+                variant = Option::Some((encoding, res.payload.unwrap()));
+            }
+        }
+    }
+
+    let content_type = mime::content_type(project, &path, &content);
+    let etag = db
+        .get_file_etag_in_project(&project.name, &path, &content)
+        .await;
+
+    let mut res = file_response(
+        req,
+        project,
+        &path,
+        content,
+        etag,
+        content_type,
+        variant,
+        set_cookie,
+    );
+
+    security::apply_defaults(res.headers_mut());
+    res
+}
+
+/// Serve a transformed image in a [`Project`] (see [`images`])
+///
+/// # Arguments:
+/// * `req` - the request being answered
+/// * `db` - [`Database`] to load image variants from
+/// * `project` - the [`Project`] the image belongs to
+/// * `path` - resolved path of the image
+/// * `content` - original image content
+/// * `transform` - the requested [`images::Transform`]
+/// * `set_cookie` - value of the `Set-Cookie` header
+pub async fn serve_image(
+    req: &HttpRequest,
+    db: &Database,
+    project: &Project,
+    path: String,
+    content: Bytes,
+    transform: images::Transform,
+    set_cookie: String,
+) -> HttpResponse {
+    let res = db
+        .get_image_variant_in_project(project.name.clone(), path.clone(), transform, &content)
+        .await;
+
+    // images that can't be transformed are served as they are
+    if !res.success {
+        return serve_file(req, db, project, path, content, set_cookie).await;
+    }
+
+    let content = res.payload.unwrap();
+    let etag = db
+        .get_file_etag_in_project(
+            &project.name,
+            &format!("{}:encoding:{}", path, transform.key()),
+            &content,
+        )
+        .await;
+
+    let mut res = file_response(
+        req,
+        project,
+        &path,
+        content,
+        etag,
+        transform.format.mime().to_string(),
+        Option::None,
+        set_cookie,
+    );
+
+    security::apply_defaults(res.headers_mut());
+    res
+}
+
+/// Build the response for a file in a [`Project`]
+///
+/// Responds with `304 Not Modified` (and no body) when the request's validators still match, and
+/// with `206 Partial Content` when the request asks for a byte range.
+///
+/// # Arguments:
+/// * `req` - the request being answered
+/// * `project` - the [`Project`] the file belongs to
+/// * `path` - resolved path of the file (`message` of [`crate::db::Database::get_file_in_project`])
+/// * `content` - file content
+/// * `etag` - strong [`EntityTag`] of `content` (see [`Database::get_file_etag_in_project`])
+/// * `content_type` - value of the `Content-Type` header (see [`mime::content_type`])
+/// * `variant` - compressed variant of the file to send instead of `content`
+/// * `set_cookie` - value of the `Set-Cookie` header
+#[allow(clippy::too_many_arguments)]
+pub fn file_response(
+    req: &HttpRequest,
+    project: &Project,
+    path: &str,
+    mut content: Bytes,
+    mut etag: EntityTag,
+    content_type: String,
+    variant: Option<(Encoding, Bytes)>,
+    set_cookie: String,
+) -> HttpResponse {
+    // validators
+    let last_modified = project.private_metadata.last_modified();
+
+    // every encoding is a different representation, so it needs its own strong etag
+    let mut content_encoding: Option<Encoding> = Option::None;
+
+    if let Some((encoding, bytes)) = variant {
+        etag = EntityTag::new_strong(format!("{}-{}", etag.tag(), encoding));
+        content = bytes;
+        content_encoding = Option::Some(encoding);
+    }
+
+    let not_modified = ((req.method() == Method::GET) | (req.method() == Method::HEAD))
+        && caching::is_not_modified(req, &etag, last_modified);
+
+    let mut res = if not_modified {
+        HttpResponse::NotModified()
+    } else {
+        HttpResponse::Ok()
+    };
+
+    res.append_header(("Set-Cookie", set_cookie))
+        .insert_header(header::ETag(etag.clone()))
+        .insert_header(header::LastModified(caching::http_date(last_modified)))
+        .insert_header((header::CACHE_CONTROL, caching::cache_control(project, path)));
+
+    if encoding::is_compressible(path) {
+        res.insert_header((header::VARY, "Accept-Encoding"));
+    }
+
+    if not_modified {
+        return res.finish();
+    }
+
+    res.append_header(("Accept-Ranges", "bytes"));
+
+    if let Some(encoding) = content_encoding {
+        res.insert_header((header::CONTENT_ENCODING, encoding.as_str()));
+    }
+
+    // ranges
+    if (req.method() == Method::GET) && content_encoding.is_none() {
+        let length = content.len() as u64;
+
+        match range::requested_ranges(req, &etag, length) {
+            range::Ranges::Full => (),
+            range::Ranges::Unsatisfiable => {
+                return res
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .insert_header((header::CONTENT_RANGE, format!("bytes */{length}")))
+                    .finish();
+            }
+            range::Ranges::Satisfiable(ranges) => {
+                res.status(StatusCode::PARTIAL_CONTENT);
+
+                // single range
+                if ranges.len() == 1 {
+                    let (start, end) = ranges[0];
+
+                    return res
+                        .insert_header((
+                            header::CONTENT_RANGE,
+                            format!("bytes {start}-{end}/{length}"),
+                        ))
+                        .append_header(("Content-Type", content_type))
+                        .body(content.slice(start as usize..=end as usize));
+                }
+
+                // multiple ranges
+                let boundary = etag.tag()[..32].to_string();
+
+                return res
+                    .append_header((
+                        "Content-Type",
+                        format!("multipart/byteranges; boundary={boundary}"),
+                    ))
+                    .body(range::multipart_body(
+                        &ranges,
+                        &content,
+                        &content_type,
+                        &boundary,
+                    ));
+            }
+        }
+    }
+
+    // return
+    res.append_header(("Content-Type", content_type))
+        .body(content)
+}
@@ -0,0 +1,104 @@
+//! Request path resolution (directory indexes, clean paths, trailing slashes)
+use crate::db::TrailingSlash;
+
+/// Check if the last segment of `path` has a file extension
+fn has_extension(path: &str) -> bool {
+    path.trim_end_matches("/")
+        .rsplit("/")
+        .next()
+        .unwrap_or("")
+        .contains(".")
+}
+
+/// Get every file path a request path can resolve to, in order of preference
+///
+/// * `/` resolves to `/index.html`
+/// * `/page` resolves to `/page`, then `/page.html` (with `clean_paths`), then `/page/index.html`
+/// * `/docs/` resolves to `/docs/index.html`, then `/docs.html` (with `clean_paths`), then `/docs`
+///   (files without an extension, like `/LICENSE`, are still found after [`TrailingSlash::Add`]
+///   redirects them to `/LICENSE/`)
+///
+/// # Arguments:
+/// * `path` - request path
+/// * `clean_paths` - value of [`crate::db::ProjectMetadata::clean_paths`]
+pub fn candidates(path: &str, clean_paths: bool) -> Vec<String> {
+    let path = if path.starts_with("/") {
+        path.to_string()
+    } else {
+        format!("/{path}")
+    };
+
+    let base = path.trim_end_matches("/");
+
+    if base.is_empty() {
+        return vec![String::from("/index.html")];
+    }
+
+    let mut out: Vec<String> = Vec::new();
+
+    if !path.ends_with("/") {
+        out.push(base.to_string());
+    }
+
+    if !has_extension(base) {
+        if clean_paths {
+            out.push(format!("{base}.html"));
+        }
+
+        out.push(format!("{base}/index.html"));
+    }
+
+    // directory index comes first when the path ends with a slash, the file itself last
+    if path.ends_with("/") {
+        out.reverse();
+        out.push(base.to_string());
+    }
+
+    out
+}
+
+/// Get the canonical form of a request path, `None` if `path` is already canonical
+///
+/// With `clean_paths`, `.html` is removed (`/page.html` -> `/page`, `/docs/index.html` -> `/docs/`).
+/// The [`TrailingSlash`] policy then adds or removes the trailing slash of paths without an
+/// extension.
+///
+/// # Arguments:
+/// * `path` - request path
+/// * `clean_paths` - value of [`crate::db::ProjectMetadata::clean_paths`]
+/// * `trailing_slash` - value of [`crate::db::ProjectMetadata::trailing_slash`]
+pub fn canonical(path: &str, clean_paths: bool, trailing_slash: &TrailingSlash) -> Option<String> {
+    let mut out = path.to_string();
+
+    // clean paths
+    if clean_paths {
+        if let Some(dir) = out.strip_suffix("/index.html") {
+            out = format!("{dir}/");
+        } else if let Some(page) = out.strip_suffix(".html") {
+            out = page.to_string();
+        }
+    }
+
+    // trailing slash
+    if (out != "/") && !has_extension(&out) {
+        match trailing_slash {
+            TrailingSlash::Add => {
+                if !out.ends_with("/") {
+                    out.push('/');
+                }
+            }
+            TrailingSlash::Strip => out = out.trim_end_matches("/").to_string(),
+            TrailingSlash::Ignore => (),
+        }
+    }
+
+    if out.is_empty() {
+        out = String::from("/");
+    }
+
+    if out == path {
+        return Option::None;
+    }
+
+    Option::Some(out)
+}
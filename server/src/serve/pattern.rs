@@ -0,0 +1,42 @@
+//! Path pattern matching for project rules
+//!
+//! Patterns are matched against the full request path. A `*` matches any
+//! sequence of characters (including `/`), everything else must match exactly.
+
+/// Check if `path` matches the given `pattern`
+///
+/// # Arguments:
+/// * `pattern` - path pattern (`/assets/*`, `*.js`, `/index.html`)
+/// * `path` - request path
+pub fn matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let path = path.as_bytes();
+
+    let (mut p, mut s) = (0, 0);
+    let mut star: Option<(usize, usize)> = Option::None;
+
+    while s < path.len() {
+        if (p < pattern.len()) && (pattern[p] == b'*') {
+            // remember where the wildcard started so we can backtrack
+            star = Option::Some((p, s));
+            p += 1;
+        } else if (p < pattern.len()) && (pattern[p] == path[s]) {
+            p += 1;
+            s += 1;
+        } else if let Some((star_p, star_s)) = star {
+            // let the last wildcard consume one more character
+            p = star_p + 1;
+            s = star_s + 1;
+            star = Option::Some((star_p, s));
+        } else {
+            return false;
+        }
+    }
+
+    // trailing wildcards can match nothing
+    while (p < pattern.len()) && (pattern[p] == b'*') {
+        p += 1;
+    }
+
+    p == pattern.len()
+}
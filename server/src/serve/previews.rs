@@ -0,0 +1,43 @@
+//! Preview deployments (named copies of a project served at `{preview}--{project}`)
+//!
+//! A preview is stored as its own project named `{preview}--{project}`, so it's served (and its files
+//! are uploaded) exactly like any other project. Project names can't contain the separator, so
+//! preview names never collide with real projects.
+
+/// Separator between the preview name and the project name
+pub const SEPARATOR: &str = "--";
+
+/// Most previews a project can have at once
+pub const MAX_PREVIEWS: usize = 10;
+
+/// Longest a preview name can be, `{preview}--{project}` also has to be reachable through its host
+/// (see [`super::hosts::is_reachable`]), so long project names allow shorter preview names
+pub const MAX_NAME_LENGTH: usize = 32;
+
+/// Get the project name of a preview (`{preview}--{project}`)
+pub fn project_name(project: &str, preview: &str) -> String {
+    format!("{preview}{SEPARATOR}{project}")
+}
+
+/// Make sure a preview name can be used, returning why it can't
+pub fn check_name(preview: &str) -> Result<(), String> {
+    if preview.is_empty() | (preview.len() > MAX_NAME_LENGTH) {
+        return Err(format!(
+            "Preview names must be 1 to {MAX_NAME_LENGTH} characters long"
+        ));
+    }
+
+    if !preview
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() | (c == '-') | (c == '_'))
+        | preview.starts_with("-")
+        | preview.ends_with("-")
+        | preview.contains(SEPARATOR)
+    {
+        return Err(String::from(
+            "Preview names can only use letters, numbers, \"_\" and single dashes",
+        ));
+    }
+
+    Ok(())
+}
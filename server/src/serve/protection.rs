@@ -0,0 +1,162 @@
+//! Password protected projects ([`crate::db::ProjectFilePrivacy::Protected`])
+//!
+//! Visitors unlock a project by posting its password to any path of the project. Access is then
+//! remembered with a cookie signed for that project (and that password, so changing the password
+//! signs everybody out).
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use dorsal::utility;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Name of the access cookie (cookies are host-only, so this is already scoped to the project subdomain)
+pub const ACCESS_COOKIE: &str = "__Secure-Vibrant-Access";
+
+/// How long (in ms) access is remembered after entering the password
+pub const ACCESS_TIME: u128 = 604_800_000;
+
+/// Name of the password form field
+pub const PASSWORD_FIELD: &str = "vibrant_password";
+
+/// Failed attempts allowed (per project and client) before the client is locked out
+pub const MAX_ATTEMPTS: u32 = 5;
+
+/// How long (in ms) a client is locked out after [`MAX_ATTEMPTS`] failed attempts
+pub const LOCKOUT_TIME: u128 = 900_000;
+
+/// Compare two byte strings without stopping at the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Hash a project password for storage (Argon2id, PHC string)
+pub fn hash_password(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("default Argon2 parameters are valid")
+        .to_string()
+}
+
+/// Check a password attempt against a stored password hash
+pub fn verify_password(stored: &str, attempt: &str) -> bool {
+    let Ok(hash) = PasswordHash::new(stored) else {
+        return false;
+    };
+
+    Argon2::default()
+        .verify_password(attempt.as_bytes(), &hash)
+        .is_ok()
+}
+
+/// Sign `message` with the server's signing key (HMAC-SHA256, hex)
+pub fn sign(key: &str, message: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("any key length is valid");
+    mac.update(message.as_bytes());
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+/// Check a signature created by [`sign`]
+pub fn verify_signature(key: &str, message: &str, signature: &str) -> bool {
+    constant_time_eq(sign(key, message).as_bytes(), signature.as_bytes())
+}
+
+/// Create an access token for a project (`{expires}.{signature}`)
+///
+/// # Arguments:
+/// * `key` - server signing key
+/// * `project` - project name
+/// * `password` - stored password hash of the project
+/// * `expires` - unix epoch timestamp (in ms) the token expires at
+pub fn access_token(key: &str, project: &str, password: &str, expires: u128) -> String {
+    let signature = sign(key, &format!("access:{project}:{password}:{expires}"));
+    format!("{expires}.{signature}")
+}
+
+/// Check an access token created by [`access_token`]
+///
+/// # Arguments:
+/// * `key` - server signing key
+/// * `project` - project name
+/// * `password` - stored password hash of the project
+/// * `token` - the token
+pub fn check_access_token(key: &str, project: &str, password: &str, token: &str) -> bool {
+    let Some((expires, signature)) = token.split_once(".") else {
+        return false;
+    };
+
+    let Ok(expires) = expires.parse::<u128>() else {
+        return false;
+    };
+
+    if expires < utility::unix_epoch_timestamp() {
+        return false;
+    }
+
+    verify_signature(
+        key,
+        &format!("access:{project}:{password}:{expires}"),
+        signature,
+    )
+}
+
+/// Build the `Set-Cookie` value for an access token
+pub fn access_cookie(token: &str) -> String {
+    format!(
+        "{ACCESS_COOKIE}={token}; SameSite=Lax; Secure; Path=/; HttpOnly=true; Max-Age={}",
+        ACCESS_TIME / 1000
+    )
+}
+
+/// Render the password page of a project
+///
+/// # Arguments:
+/// * `project` - project name
+/// * `message` - error message to show above the form (empty for none)
+/// * `site_host` - `HOST` of the server (for the stylesheet and homepage link)
+pub fn password_page(project: &str, message: &str, site_host: &str) -> String {
+    let message = if message.is_empty() {
+        String::new()
+    } else {
+        format!("<div class=\"card border round full\"><p class=\"no-margin\">{message}</p></div>")
+    };
+
+    format!("<!DOCTYPE html>
+
+<html lang=\"en\">
+    <head>
+        <meta charset=\"UTF-8\" />
+        <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\" />
+        <meta name=\"robots\" content=\"noindex\" />
+        <title>{project} (Vibrant)</title>
+
+        <link rel=\"stylesheet\" href=\"//{site_host}/static/style.css\" />
+    </head>
+
+    <body>
+        <main class=\"small flex flex-column g-4\">
+            <div class=\"card secondary border round full flex flex-column g-4\">
+                <h3 class=\"no-margin\">{project}</h3>
+                <p class=\"no-margin\">This project is password protected.</p>
+            </div>
+
+            {message}
+
+            <form method=\"POST\" class=\"card border round full flex flex-column g-4\">
+                <input type=\"password\" name=\"{PASSWORD_FIELD}\" placeholder=\"Password\" class=\"round full\" required autofocus />
+                <button class=\"round theme:primary full\">Continue</button>
+            </form>
+
+            <div class=\"flex justify-center footernav\">
+                <span class=\"item\"><a href=\"//{site_host}\">🌸 Homepage</a></span>
+            </div>
+        </main>
+    </body>
+</html>")
+}
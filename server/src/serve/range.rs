@@ -0,0 +1,99 @@
+//! HTTP range requests (`Range`, `If-Range`)
+use actix_web::http::header::{self, EntityTag, Header};
+use actix_web::HttpRequest;
+
+/// Maximum number of ranges served in a single `multipart/byteranges` response
+///
+/// Requests asking for more ranges than this are answered with the full file instead.
+pub const MAX_RANGES: usize = 32;
+
+/// The ranges a request asked for, resolved against the file length
+pub enum Ranges {
+    /// serve the full file (no `Range` header, an invalid one, or a stale `If-Range`)
+    Full,
+    /// serve the given end-inclusive `(start, end)` byte ranges
+    Satisfiable(Vec<(u64, u64)>),
+    /// none of the requested ranges overlap the file
+    Unsatisfiable,
+}
+
+/// Resolve the `Range` header of a request
+///
+/// # Arguments:
+/// * `req` - the request being answered
+/// * `etag` - current [`EntityTag`] of the file
+/// * `length` - length of the file in bytes
+pub fn requested_ranges(req: &HttpRequest, etag: &EntityTag, length: u64) -> Ranges {
+    if !req.headers().contains_key(header::RANGE) {
+        return Ranges::Full;
+    }
+
+    // a stale If-Range means the client's partial copy is outdated, so it gets everything
+    if req.headers().contains_key(header::IF_RANGE) {
+        let fresh = match header::IfRange::parse(req) {
+            // If-Range uses the strong comparison function
+            Ok(header::IfRange::EntityTag(tag)) => tag.strong_eq(etag),
+            // Last-Modified is shared by every file in the project, so a date can't tell if
+            // this file changed (only the etag is trusted)
+            Ok(header::IfRange::Date(_)) => false,
+            Err(_) => false,
+        };
+
+        if !fresh {
+            return Ranges::Full;
+        }
+    }
+
+    // invalid Range headers are ignored (RFC 9110, section 14.2)
+    let specs = match header::Range::parse(req) {
+        Ok(header::Range::Bytes(specs)) => specs,
+        _ => return Ranges::Full,
+    };
+
+    if specs.is_empty() | (specs.len() > MAX_RANGES) {
+        return Ranges::Full;
+    }
+
+    let ranges: Vec<(u64, u64)> = specs
+        .iter()
+        .filter_map(|spec| spec.to_satisfiable_range(length))
+        .collect();
+
+    if ranges.is_empty() {
+        return Ranges::Unsatisfiable;
+    }
+
+    Ranges::Satisfiable(ranges)
+}
+
+/// Build a `multipart/byteranges` body
+///
+/// # Arguments:
+/// * `ranges` - end-inclusive `(start, end)` byte ranges
+/// * `content` - full file content
+/// * `content_type` - `Content-Type` of the file
+/// * `boundary` - multipart boundary (also sent in the response `Content-Type`)
+pub fn multipart_body(
+    ranges: &[(u64, u64)],
+    content: &[u8],
+    content_type: &str,
+    boundary: &str,
+) -> Vec<u8> {
+    let mut body: Vec<u8> = Vec::new();
+    let length = content.len();
+
+    for (start, end) in ranges {
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Type: {content_type}\r\nContent-Range: bytes {start}-{end}/{length}\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+
+        body.extend_from_slice(&content[*start as usize..=*end as usize]);
+        body.extend_from_slice(b"\r\n");
+    }
+
+    body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+    body
+}
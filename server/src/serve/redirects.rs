@@ -0,0 +1,578 @@
+//! Redirect and rewrite rules (`/_redirects`)
+//!
+//! ```text
+//! # from                     to                    status
+//! /old-page                  /new-page             301
+//! /news/*                    /blog/:splat          302
+//! /blog/:year/:month/:slug   /posts/:year/:slug    308
+//! /store id=:id              /products/:id         301
+//! /app/*                     /app/index.html       200
+//! /admin/*                   /login                302!
+//! ```
+//!
+//! Rules are checked in file order and the first match wins. `:name` matches a single path
+//! segment, a trailing `*` matches the rest of the path (available as `:splat`). `key=value`
+//! pairs between the paths match query parameters (`:name` values capture them). A `200` status
+//! serves the target file without changing the URL. Rules are skipped when a file exists at the
+//! request path, unless their status ends with `!`.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Path of the redirect rules file
+pub const REDIRECTS_FILE: &str = "/_redirects";
+
+/// Status codes a rule can use
+pub const ALLOWED_STATUSES: &[u16] = &[200, 301, 302, 307, 308];
+
+/// Status used when a rule doesn't specify one
+pub const DEFAULT_STATUS: u16 = 301;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RedirectRule {
+    /// path pattern to match
+    pub from: String,
+    /// `(key, value)` query parameters that must be present, `:name` values capture the parameter
+    pub query: Vec<(String, String)>,
+    /// target path or URL, can use placeholders captured by `from` and `query`
+    pub to: String,
+    /// response status (`200` is a rewrite)
+    pub status: u16,
+    /// apply the rule even when a file exists at the request path (`!` after the status)
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// What to do with a request after checking the rules
+#[derive(Debug, PartialEq)]
+pub enum Resolution {
+    /// respond with `(status, location)`
+    Redirect(u16, String),
+    /// serve the file at this path instead
+    Rewrite(String),
+}
+
+/// Parse a `_redirects` file
+///
+/// Returns every problem found (with its line number) if the file is invalid.
+pub fn parse(content: &str) -> Result<Vec<RedirectRule>, Vec<String>> {
+    let mut rules: Vec<RedirectRule> = Vec::new();
+    let mut errors: Vec<String> = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let number = i + 1;
+        let line = line.trim();
+
+        if line.is_empty() | line.starts_with("#") {
+            continue;
+        }
+
+        let parts: Vec<&str> = line.split_whitespace().collect();
+
+        if parts.len() < 2 {
+            errors.push(format!("line {number}: expected \"from to [status]\""));
+            continue;
+        }
+
+        let from = parts[0];
+
+        if !from.starts_with("/") {
+            errors.push(format!("line {number}: \"{from}\" must start with \"/\""));
+            continue;
+        }
+
+        // the last part is the status if it's a number ("!" forces the rule over existing files)
+        let mut rest = &parts[1..];
+        let mut status = DEFAULT_STATUS;
+        let mut force = false;
+
+        let last = rest[rest.len() - 1];
+        let code = last.strip_suffix("!").unwrap_or(last);
+
+        if (rest.len() > 1) && !code.is_empty() && code.chars().all(|c| c.is_ascii_digit()) {
+            status = code.parse::<u16>().unwrap_or(0);
+            force = code.len() != last.len();
+            rest = &rest[..rest.len() - 1];
+        }
+
+        if !ALLOWED_STATUSES.contains(&status) {
+            errors.push(format!(
+                "line {number}: status must be one of 200, 301, 302, 307 or 308"
+            ));
+            continue;
+        }
+
+        // everything between "from" and "to" is a query condition
+        let to = rest[rest.len() - 1];
+        let mut query: Vec<(String, String)> = Vec::new();
+        let mut valid = true;
+
+        for condition in &rest[..rest.len() - 1] {
+            match condition.split_once("=") {
+                Some((key, value)) if !key.is_empty() => {
+                    query.push((key.to_string(), value.to_string()))
+                }
+                _ => {
+                    errors.push(format!(
+                        "line {number}: \"{condition}\" is not a \"key=value\" query condition"
+                    ));
+                    valid = false;
+                }
+            }
+        }
+
+        if !valid {
+            continue;
+        }
+
+        let is_url = to.starts_with("http://") | to.starts_with("https://");
+
+        if !to.starts_with("/") && !is_url {
+            errors.push(format!(
+                "line {number}: \"{to}\" must be a path starting with \"/\" or a URL"
+            ));
+            continue;
+        }
+
+        if (status == 200) && is_url {
+            errors.push(format!("line {number}: 200 rewrites must point to a path"));
+            continue;
+        }
+
+        rules.push(RedirectRule {
+            from: from.to_string(),
+            query,
+            to: to.to_string(),
+            status,
+            force,
+        });
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(rules)
+}
+
+/// Parse a `_redirects` file from its raw bytes
+pub fn parse_file(content: &[u8]) -> Result<Vec<RedirectRule>, Vec<String>> {
+    let content = std::str::from_utf8(content).map_err(|e| vec![e.to_string()])?;
+    parse(content)
+}
+
+/// Match `path` against a rule's `from` pattern, returning the captured placeholders
+fn match_path(pattern: &str, path: &str) -> Option<HashMap<String, String>> {
+    let pattern: Vec<&str> = pattern.trim_end_matches("/").split("/").collect();
+    let path: Vec<&str> = path.trim_end_matches("/").split("/").collect();
+
+    let mut captures: HashMap<String, String> = HashMap::new();
+
+    for (i, segment) in pattern.iter().enumerate() {
+        // splat, matches the rest of the path
+        if (*segment == "*") && (i == pattern.len() - 1) {
+            let rest = if path.len() > i {
+                path[i..].join("/")
+            } else {
+                String::new()
+            };
+
+            captures.insert(String::from("splat"), rest);
+            return Option::Some(captures);
+        }
+
+        let value = path.get(i)?;
+
+        if let Some(name) = segment.strip_prefix(":") {
+            if value.is_empty() {
+                return Option::None;
+            }
+
+            captures.insert(name.to_string(), value.to_string());
+        } else if segment != value {
+            return Option::None;
+        }
+    }
+
+    if path.len() != pattern.len() {
+        return Option::None;
+    }
+
+    Option::Some(captures)
+}
+
+/// Match a query string against a rule's query conditions, adding captured placeholders to `captures`
+fn match_query(
+    conditions: &[(String, String)],
+    query_string: &str,
+    captures: &mut HashMap<String, String>,
+) -> bool {
+    let params: Vec<(&str, &str)> = query_string
+        .split("&")
+        .filter(|p| !p.is_empty())
+        .map(|p| p.split_once("=").unwrap_or((p, "")))
+        .collect();
+
+    for (key, expected) in conditions {
+        let Some((_, value)) = params.iter().find(|(k, _)| k == key) else {
+            return false;
+        };
+
+        if let Some(name) = expected.strip_prefix(":") {
+            captures.insert(name.to_string(), escape_query_value(value));
+        } else if expected != value {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Percent-encode the characters of a captured query value that could change where a target
+/// points (`?id=/evil.com` must not turn `/:id` into `//evil.com`)
+fn escape_query_value(value: &str) -> String {
+    let mut out = String::new();
+
+    for c in value.chars() {
+        match c {
+            '/' => out.push_str("%2F"),
+            '\\' => out.push_str("%5C"),
+            ':' => out.push_str("%3A"),
+            '?' => out.push_str("%3F"),
+            '@' => out.push_str("%40"),
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Make sure a filled target that started as a path is still a path on the same host
+///
+/// `//host` and `/\host` are read as other hosts by browsers, so leading slashes are collapsed.
+fn keep_local(pattern: &str, target: String) -> String {
+    if !pattern.starts_with("/") {
+        return target;
+    }
+
+    let rest = target.trim_start_matches(['/', '\\']);
+
+    if rest.len() + 1 == target.len() {
+        return target;
+    }
+
+    format!("/{rest}")
+}
+
+/// Replace `:name` placeholders in `target` with their captured values
+fn fill_placeholders(target: &str, captures: &HashMap<String, String>) -> String {
+    let mut out = String::new();
+    let mut chars = target.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != ':' {
+            out.push(c);
+            continue;
+        }
+
+        // read placeholder name
+        let mut name = String::new();
+
+        while let Some(next) = chars.peek() {
+            if !next.is_ascii_alphanumeric() && (*next != '_') {
+                break;
+            }
+
+            name.push(*next);
+            chars.next();
+        }
+
+        match captures.get(&name) {
+            Some(value) => out.push_str(value),
+            // not a placeholder (like the ":" in "https://")
+            None => {
+                out.push(':');
+                out.push_str(&name);
+            }
+        }
+    }
+
+    out
+}
+
+/// Find the first rule matching a request
+///
+/// # Arguments:
+/// * `rules` - parsed [`RedirectRule`]s
+/// * `path` - request path
+/// * `query_string` - request query string (without the `?`)
+/// * `file_exists` - if a file exists at `path` (only forced rules apply then)
+pub fn resolve(
+    rules: &[RedirectRule],
+    path: &str,
+    query_string: &str,
+    file_exists: bool,
+) -> Option<Resolution> {
+    for rule in rules {
+        if file_exists && !rule.force {
+            continue;
+        }
+
+        let Some(mut captures) = match_path(&rule.from, path) else {
+            continue;
+        };
+
+        if !match_query(&rule.query, query_string, &mut captures) {
+            continue;
+        }
+
+        let target = keep_local(&rule.to, fill_placeholders(&rule.to, &captures));
+
+        if rule.status == 200 {
+            return Option::Some(Resolution::Rewrite(target));
+        }
+
+        // keep the original query string unless the rule consumed or replaced it
+        let location = if rule.query.is_empty() && !target.contains("?") && !query_string.is_empty()
+        {
+            format!("{target}?{query_string}")
+        } else {
+            target
+        };
+
+        return Option::Some(Resolution::Redirect(rule.status, location));
+    }
+
+    Option::None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = "
+# comments and blank lines are skipped
+
+/old-page                  /new-page             301
+/news/*                    /blog/:splat          302
+/blog/:year/:month/:slug   /posts/:year/:slug    308
+/store id=:id              /products/:id         301
+/search q=rust             /topics/rust
+/go id=:id                 /:id                  302
+/app/*                     /app/index.html       200
+/admin/*                   /login                302!
+/external                  https://example.com/:splat
+";
+
+    fn rules() -> Vec<RedirectRule> {
+        parse(RULES).unwrap()
+    }
+
+    fn redirect(status: u16, location: &str) -> Option<Resolution> {
+        Some(Resolution::Redirect(status, location.into()))
+    }
+
+    #[test]
+    fn parses_rules() {
+        let rules = rules();
+
+        assert_eq!(rules.len(), 9);
+        assert_eq!(
+            rules[0],
+            RedirectRule {
+                from: "/old-page".into(),
+                query: Vec::new(),
+                to: "/new-page".into(),
+                status: 301,
+                force: false,
+            }
+        );
+        assert_eq!(rules[3].query, vec![("id".into(), ":id".into())]);
+        assert_eq!(rules[4].status, DEFAULT_STATUS);
+        assert_eq!(rules[4].query, vec![("q".into(), "rust".into())]);
+        assert_eq!(rules[6].status, 200);
+        assert_eq!((rules[7].status, rules[7].force), (302, true));
+        assert!(rules.iter().filter(|r| r.force).count() == 1);
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let errors = parse(
+            "/ok /fine\n\
+             /missing-target\n\
+             relative /target\n\
+             /a /b 404\n\
+             /a /b 302!!\n\
+             /a =x /b\n\
+             /a target\n\
+             /a https://example.com 200\n\
+             /a /b !",
+        )
+        .unwrap_err();
+
+        assert_eq!(errors.len(), 8, "{errors:?}");
+        assert!(errors[0].starts_with("line 2:"), "{errors:?}");
+        assert!(errors[1].starts_with("line 3:"), "{errors:?}");
+        assert!(errors[2].contains("status must be"), "{errors:?}");
+        assert!(errors[7].starts_with("line 9:"), "{errors:?}");
+
+        assert!(parse_file(&[0xff, 0xfe]).is_err());
+        assert_eq!(parse_file(b"").unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn captures_placeholders() {
+        let rules = rules();
+
+        assert_eq!(
+            resolve(&rules, "/old-page", "", false),
+            redirect(301, "/new-page")
+        );
+        assert_eq!(
+            resolve(&rules, "/old-page/", "", false),
+            redirect(301, "/new-page")
+        );
+        assert_eq!(
+            resolve(&rules, "/news/2024/hello", "", false),
+            redirect(302, "/blog/2024/hello")
+        );
+        assert_eq!(resolve(&rules, "/news", "", false), redirect(302, "/blog/"));
+        assert_eq!(
+            resolve(&rules, "/blog/2024/05/hello", "", false),
+            redirect(308, "/posts/2024/hello")
+        );
+        assert_eq!(
+            resolve(&rules, "/app/settings/profile", "", false),
+            Some(Resolution::Rewrite("/app/index.html".into()))
+        );
+
+        // placeholders without a capture are kept as they are
+        assert_eq!(
+            resolve(&rules, "/external", "", false),
+            redirect(301, "https://example.com/:splat")
+        );
+
+        // segments must all match
+        assert_eq!(resolve(&rules, "/blog/2024/05", "", false), None);
+        assert_eq!(resolve(&rules, "/blog/2024/05/hello/more", "", false), None);
+        assert_eq!(resolve(&rules, "/old-page/more", "", false), None);
+        assert_eq!(resolve(&rules, "/Old-Page", "", false), None);
+    }
+
+    #[test]
+    fn matches_queries() {
+        let rules = rules();
+
+        assert_eq!(
+            resolve(&rules, "/store", "id=42", false),
+            redirect(301, "/products/42")
+        );
+        assert_eq!(
+            resolve(&rules, "/store", "ref=home&id=42", false),
+            redirect(301, "/products/42")
+        );
+        assert_eq!(resolve(&rules, "/store", "", false), None);
+        assert_eq!(
+            resolve(&rules, "/search", "q=rust", false),
+            redirect(301, "/topics/rust")
+        );
+        assert_eq!(resolve(&rules, "/search", "q=go", false), None);
+
+        // the query string is kept unless the rule matched on it
+        assert_eq!(
+            resolve(&rules, "/old-page", "utm=mail", false),
+            redirect(301, "/new-page?utm=mail")
+        );
+    }
+
+    #[test]
+    fn escapes_query_captures() {
+        let rules = rules();
+
+        assert_eq!(
+            resolve(&rules, "/go", "id=/evil.com", false),
+            redirect(302, "/%2Fevil.com")
+        );
+        assert_eq!(
+            resolve(&rules, "/go", "id=\\evil.com", false),
+            redirect(302, "/%5Cevil.com")
+        );
+        assert_eq!(
+            resolve(&rules, "/go", "id=https://evil.com", false),
+            redirect(302, "/https%3A%2F%2Fevil.com")
+        );
+        assert_eq!(
+            resolve(&rules, "/go", "id=a@b?c", false),
+            redirect(302, "/a%40b%3Fc")
+        );
+
+        // path captures can't leave the host either
+        let rules = parse("/r/*  /:splat  302").unwrap();
+        assert_eq!(
+            resolve(&rules, "/r//evil.com", "", false),
+            redirect(302, "/evil.com")
+        );
+        assert_eq!(
+            resolve(&rules, "/r/\\evil.com", "", false),
+            redirect(302, "/evil.com")
+        );
+    }
+
+    #[test]
+    fn files_shadow_rules_unless_forced() {
+        let rules = rules();
+
+        assert_eq!(resolve(&rules, "/old-page", "", true), None);
+        assert_eq!(resolve(&rules, "/app/style.css", "", true), None);
+        assert_eq!(
+            resolve(&rules, "/admin/index.html", "", true),
+            redirect(302, "/login")
+        );
+        assert_eq!(
+            resolve(&rules, "/admin/index.html", "", false),
+            redirect(302, "/login")
+        );
+    }
+
+    #[test]
+    fn fuzz_path_targets_stay_local() {
+        let rules = parse("/go id=:id  /:id  302\n/r/*  /:splat  302").unwrap();
+        let pieces = [
+            "/", "\\", ":", "?", "@", "%2F", ".", "evil.com", "a", " ", "#",
+        ];
+        let mut state: u64 = 0x5eed_0bad_f00d_0001;
+
+        // xorshift64
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..20_000 {
+            let len = next() % 6;
+            let value: String = (0..len)
+                .map(|_| pieces[(next() % pieces.len() as u64) as usize])
+                .collect();
+
+            for (path, query) in [
+                ("/go".to_string(), format!("id={value}")),
+                (format!("/r/{value}"), String::new()),
+            ] {
+                let Some(Resolution::Redirect(_, location)) = resolve(&rules, &path, &query, false)
+                else {
+                    continue;
+                };
+
+                assert!(
+                    location.starts_with("/"),
+                    "{path:?} {query:?} -> {location:?}"
+                );
+                assert!(
+                    !location.starts_with("//") && !location.starts_with("/\\"),
+                    "{path:?} {query:?} -> {location:?}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Origins and default security headers for project files
+//!
+//! Project files are served from subdomains of the user-content domain (`CONTENT_HOST`), which
+//! should be a different site than the app (`HOST`) so project JavaScript never shares an origin
+//! (or a site) with the dashboard session.
+use actix_web::http::header::{self, HeaderMap, HeaderValue};
+use actix_web::HttpRequest;
+use std::net::IpAddr;
+
+/// Headers sent with every project file, projects can replace them with header rules (names must be lowercase)
+pub const DEFAULT_HEADERS: &[(&str, &str)] = &[
+    ("x-content-type-options", "nosniff"),
+    ("referrer-policy", "strict-origin-when-cross-origin"),
+];
+
+/// `Content-Security-Policy` of project files served on the app origin (`/{name}/{path}` and the API)
+///
+/// The sandbox gives documents an opaque origin and blocks their scripts, so they can't use the
+/// session of the app.
+pub const APP_ORIGIN_CSP: &str = "sandbox";
+
+/// Get the `HOST` of the server
+pub fn site_host() -> Option<String> {
+    std::env::var("HOST").ok()
+}
+
+/// Get the user-content domain projects are served from (`CONTENT_HOST`, falling back to `HOST`)
+///
+/// `CONTENT_HOST` can list more than one domain (see [`super::hosts::base_domains`]), links use
+/// the first one.
+pub fn content_host() -> Option<String> {
+    let hosts = std::env::var("CONTENT_HOST").ok();
+
+    if hosts.is_none() {
+        return site_host();
+    }
+
+    hosts
+        .unwrap()
+        .split(",")
+        .map(|h| h.trim().to_string())
+        .find(|h| !h.is_empty())
+        .or_else(site_host)
+}
+
+/// Get the proxies allowed to forward client addresses (`TRUSTED_PROXIES`, a comma-separated list of IPs)
+pub fn trusted_proxies() -> Vec<IpAddr> {
+    std::env::var("TRUSTED_PROXIES")
+        .unwrap_or_default()
+        .split(",")
+        .filter_map(|ip| ip.trim().parse::<IpAddr>().ok())
+        .collect()
+}
+
+/// Get the address of the client that made a request
+///
+/// `Forwarded` and `X-Forwarded-For` are set by whoever sends the request, so they're only used
+/// when the connection comes from one of the [`trusted_proxies`]. Otherwise this is the address
+/// of the connection itself.
+pub fn client_addr(req: &HttpRequest) -> String {
+    let peer = req.peer_addr().map(|a| a.ip());
+
+    if peer.is_some_and(|ip| trusted_proxies().contains(&ip.to_canonical())) {
+        if let Some(addr) = req.connection_info().realip_remote_addr() {
+            return addr.to_string();
+        }
+    }
+
+    peer.map(|ip| ip.to_canonical().to_string())
+        .unwrap_or_default()
+}
+
+/// Check if projects are served from a domain other than the app's
+pub fn has_content_host() -> bool {
+    let content_host = content_host();
+    content_host.is_some() && (content_host != site_host())
+}
+
+/// Insert every header of [`DEFAULT_HEADERS`] that isn't set yet
+pub fn apply_defaults(headers: &mut HeaderMap) {
+    for (name, value) in DEFAULT_HEADERS {
+        let name = header::HeaderName::from_static(name);
+
+        if !headers.contains_key(&name) {
+            headers.insert(name, HeaderValue::from_static(value));
+        }
+    }
+}
+
+/// Lock down a project file that is served on the app origin (see [`APP_ORIGIN_CSP`])
+pub fn sandbox(headers: &mut HeaderMap) {
+    apply_defaults(headers);
+
+    headers.insert(
+        header::CONTENT_SECURITY_POLICY,
+        HeaderValue::from_static(APP_ORIGIN_CSP),
+    );
+}
+
+/// Get the (scheme-relative) URL of a file on the user-content domain
+///
+/// # Arguments:
+/// * `project` - project name
+/// * `path` - file path
+/// * `query` - query string of the request (empty for none)
+pub fn content_url(project: &str, path: &str, query: &str) -> Option<String> {
+    let content_host = content_host()?;
+    let project = super::hosts::name_to_label(project)?;
+    let path = path.trim_start_matches("/");
+
+    Option::Some(if query.is_empty() {
+        format!("//{project}.{content_host}/{path}")
+    } else {
+        format!("//{project}.{content_host}/{path}?{query}")
+    })
+}
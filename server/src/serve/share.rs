@@ -0,0 +1,74 @@
+//! Share links (signed, expiring links to files in private projects)
+//!
+//! A share link is a project URL with a `share` query parameter (`{id}.{expires}.{signature}`).
+//! The signature covers the project, the link and the project's share key, so rotating the share
+//! key revokes every link at once.
+use actix_web::web::Query;
+use actix_web::HttpRequest;
+use std::collections::HashMap;
+
+use super::protection;
+
+/// Name of the query parameter share tokens are read from
+pub const SHARE_QUERY: &str = "share";
+
+/// Name of the cookie that remembers a share token (so pages can load their own assets)
+pub const SHARE_COOKIE: &str = "__Secure-Vibrant-Share";
+
+/// Longest a share link can be valid for (in ms, 90 days)
+pub const MAX_SHARE_TIME: u128 = 7_776_000_000;
+
+/// Sign a share link
+///
+/// # Arguments:
+/// * `key` - server signing key
+/// * `project` - project name
+/// * `share_key` - the project's share key
+/// * `id` - link ID
+/// * `expires` - unix epoch timestamp (in ms) the link expires at
+pub fn share_token(key: &str, project: &str, share_key: &str, id: &str, expires: u128) -> String {
+    let signature = protection::sign(key, &format!("share:{project}:{share_key}:{id}:{expires}"));
+
+    format!("{id}.{expires}.{signature}")
+}
+
+/// Split a share token into its `(id, expires, signature)`
+pub fn parse_share_token(token: &str) -> Option<(&str, u128, &str)> {
+    let mut parts = token.splitn(3, ".");
+
+    let id = parts.next()?;
+    let expires = parts.next()?.parse::<u128>().ok()?;
+    let signature = parts.next()?;
+
+    Option::Some((id, expires, signature))
+}
+
+/// Check if `path` is covered by a share link's path scope
+///
+/// Scopes ending with `/` cover everything under them, other scopes only cover that exact file.
+pub fn in_scope(scope: &str, path: &str) -> bool {
+    if scope.ends_with("/") {
+        path.starts_with(scope)
+    } else {
+        path == scope
+    }
+}
+
+/// Get the share token of a request (from the `share` query parameter, then the share cookie)
+pub fn token_from_request(req: &HttpRequest) -> Option<String> {
+    if let Ok(query) = Query::<HashMap<String, String>>::from_query(req.query_string()) {
+        if let Some(token) = query.get(SHARE_QUERY) {
+            return Option::Some(token.to_owned());
+        }
+    }
+
+    req.cookie(SHARE_COOKIE).map(|c| c.value().to_string())
+}
+
+/// Build the `Set-Cookie` value remembering a share token until it expires
+pub fn share_cookie(token: &str, expires: u128, now: u128) -> String {
+    format!(
+        "{SHARE_COOKIE}={token}; SameSite=Lax; Secure; Path=/; HttpOnly=true; Max-Age={}",
+        expires.saturating_sub(now) / 1000
+    )
+}
@@ -0,0 +1,183 @@
+//! Generated `/sitemap.xml` and `/robots.txt` (see [`crate::db::ProjectMetadata::sitemap`])
+//!
+//! Both are only generated when the project doesn't have the file itself. The sitemap lists every
+//! HTML file of the project under its canonical path, using the project's host on the base domain
+//! the request was made to (so it's correct for every domain the project is served on). Only
+//! public projects get a sitemap, the file list of every other project stays hidden.
+use actix_web::web::Bytes;
+use actix_web::{HttpRequest, HttpResponse};
+
+use crate::db::{Project, ProjectFilePrivacy, ProjectMetadata};
+
+/// Path of the generated sitemap
+pub const SITEMAP_PATH: &str = "/sitemap.xml";
+
+/// Path of the generated robots file
+pub const ROBOTS_PATH: &str = "/robots.txt";
+
+/// Most URLs a single sitemap can hold (sitemaps.org protocol)
+pub const MAX_URLS: usize = 50_000;
+
+/// Check if `path` is generated for a [`Project`] when it doesn't exist
+pub fn is_generated(metadata: &ProjectMetadata, path: &str) -> bool {
+    if !metadata.sitemap {
+        return false;
+    }
+
+    ((path == SITEMAP_PATH) && (metadata.file_privacy == ProjectFilePrivacy::Public))
+        | (path == ROBOTS_PATH)
+}
+
+/// Get the base URL (`scheme://{project}.{base}`) of the project a request was made to, `None` if
+/// the host isn't a project host on one of the base domains (see [`super::hosts::base_domains`])
+pub fn base_url(req: &HttpRequest) -> Option<String> {
+    let host = super::hosts::normalize(req.headers().get("host")?.to_str().ok()?)?;
+    super::hosts::project_label(&host, &super::hosts::base_domains())?;
+
+    // the normalized host is exactly "{label}.{base}", so only known hosts end up in the cache
+    let scheme = if req.connection_info().scheme() == "http" {
+        "http"
+    } else {
+        "https"
+    };
+
+    Option::Some(format!("{scheme}://{host}"))
+}
+
+/// Get the sitemap URL path of a file, `None` if the file isn't a page
+///
+/// Error pages, the maintenance page and files in hidden directories (`/.well-known/`) are skipped.
+///
+/// # Arguments:
+/// * `metadata` - the project's [`ProjectMetadata`]
+/// * `path` - file path
+pub fn page_path(metadata: &ProjectMetadata, path: &str) -> Option<String> {
+    if !path.ends_with(".html") | path.split("/").any(|s| s.starts_with(".")) {
+        return Option::None;
+    }
+
+    let is_error_page = super::errors::CUSTOM_PAGES
+        .iter()
+        .any(|s| path == format!("/{}.html", s.as_u16()));
+
+    if is_error_page | (path == "/503.html") | (path == metadata.maintenance.page) {
+        return Option::None;
+    }
+
+    Option::Some(
+        super::paths::canonical(path, metadata.clean_paths, &metadata.trailing_slash)
+            .unwrap_or(path.to_string()),
+    )
+}
+
+/// Build the sitemap of a [`Project`]
+///
+/// # Arguments:
+/// * `project` - the [`Project`]
+/// * `base_url` - `scheme://host` of the project (see [`base_url`])
+/// * `files` - paths of every file in the project
+pub fn sitemap(project: &Project, base_url: &str, files: &[String]) -> String {
+    let mut pages: Vec<String> = files
+        .iter()
+        .filter_map(|f| page_path(&project.metadata, f))
+        .collect();
+
+    pages.sort();
+    pages.dedup();
+    pages.truncate(MAX_URLS);
+
+    let lastmod = super::caching::w3c_date(project.private_metadata.last_modified());
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+
+    for page in pages {
+        out.push_str(&format!(
+            "  <url>\n    <loc>{}</loc>\n    <lastmod>{lastmod}</lastmod>\n  </url>\n",
+            escape_xml(&format!("{base_url}{}", encode_path(&page)))
+        ));
+    }
+
+    out.push_str("</urlset>\n");
+    out
+}
+
+/// Build the default `robots.txt` of a [`Project`]
+///
+/// Only public projects are open to crawlers.
+///
+/// # Arguments:
+/// * `project` - the [`Project`]
+/// * `base_url` - `scheme://host` of the project (see [`base_url`])
+pub fn robots(project: &Project, base_url: &str) -> String {
+    if project.metadata.file_privacy != ProjectFilePrivacy::Public {
+        return String::from("User-agent: *\nDisallow: /\n");
+    }
+
+    format!("User-agent: *\nAllow: /\n\nSitemap: {base_url}{SITEMAP_PATH}\n")
+}
+
+/// Build the response for a generated file
+///
+/// # Arguments:
+/// * `req` - the request being answered
+/// * `project` - the [`Project`] the file belongs to
+/// * `path` - [`SITEMAP_PATH`] or [`ROBOTS_PATH`]
+/// * `content` - generated content
+/// * `set_cookie` - value of the `Set-Cookie` header
+pub fn response(
+    req: &HttpRequest,
+    project: &Project,
+    path: &str,
+    content: String,
+    set_cookie: String,
+) -> HttpResponse {
+    let content_type = if path == SITEMAP_PATH {
+        "application/xml; charset=utf-8"
+    } else {
+        "text/plain; charset=utf-8"
+    };
+
+    // generated files are small, so they're hashed every time
+    let content = Bytes::from(content);
+    let etag = super::caching::etag(&content);
+
+    let mut res = super::file_response(
+        req,
+        project,
+        path,
+        content,
+        etag,
+        content_type.to_string(),
+        Option::None,
+        set_cookie,
+    );
+
+    super::security::apply_defaults(res.headers_mut());
+    res
+}
+
+/// Percent-encode the characters of a path that can't appear in a URL
+fn encode_path(path: &str) -> String {
+    let mut out = String::new();
+
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() | b"/-._~!$&'()*+,;=:@".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    out
+}
+
+/// Escape the characters XML doesn't allow in text
+fn escape_xml(input: &str) -> String {
+    input
+        .replace("&", "&amp;")
+        .replace("<", "&lt;")
+        .replace(">", "&gt;")
+        .replace("\"", "&quot;")
+        .replace("'", "&apos;")
+}
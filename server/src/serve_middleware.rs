@@ -1,15 +1,21 @@
 //! Middleware for serving project assets
-use actix_files::file_extension_to_mime;
 use awc::body::EitherBody;
 use futures_util::future::LocalBoxFuture;
 use std::future::{ready, Ready};
 
 use actix_web::{
-    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-    Error, HttpResponse,
+    body::{BodySize, MessageBody},
+    cookie::Cookie,
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{header, Method, StatusCode},
+    web::{Data, Form},
+    Error, FromRequest, HttpRequest, HttpResponse,
 };
+use std::collections::HashMap;
 
+use crate::db::{AppData, Project, ProjectFilePrivacy};
 use crate::pages::base;
+use crate::serve;
 
 pub struct ServeAssets;
 
@@ -47,155 +53,572 @@ where
     forward_ready!(service);
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
+        // plain http is moved to https when the server terminates tls (see crate::tls)
+        let https = crate::tls::https_redirect(req.request());
+
+        if https.is_some() {
+            let res = serve::redirect(StatusCode::PERMANENT_REDIRECT, https.unwrap());
+            return Box::pin(async move { Ok(req.into_response(res).map_into_right_body()) });
+        }
+
         let site_host = std::env::var("HOST");
+        let bases = serve::hosts::base_domains();
+
+        // only project hosts are handled here, everything else goes to the app
+        let project = if site_host.is_ok() {
+            project_from_host(req.request(), &bases)
+        } else {
+            Option::None
+        };
+
+        // projects on subdomains of the app are moved to the user-content domain
+        if project.is_none() && serve::security::has_content_host() {
+            let host = req
+                .headers()
+                .get("host")
+                .and_then(|h| h.to_str().ok())
+                .and_then(serve::hosts::normalize)
+                .unwrap_or_default();
+
+            let legacy = project_from_host(
+                req.request(),
+                &serve::hosts::parse_base_domains(site_host.as_ref().unwrap()),
+            );
+
+            // (the user-content domain can be a subdomain of the app)
+            let location = if legacy.is_some() && !bases.contains(&host) {
+                serve::security::content_url(&legacy.unwrap(), req.path(), req.query_string())
+            } else {
+                Option::None
+            };
+
+            if location.is_some() {
+                let res = serve::redirect(StatusCode::MOVED_PERMANENTLY, location.unwrap());
+                return Box::pin(async move { Ok(req.into_response(res).map_into_right_body()) });
+            }
+        }
+
+        if project.is_none() {
+            // process response as normal
+            let fut = self.service.call(req);
+            return Box::pin(async move { Ok(fut.await?.map_into_left_body()) });
+        }
 
-        // process response as normal
+        // serve project asset (the app never sees this request)
         let cookie = req.request().cookie("__Secure-Token");
-        let fut = self.service.call(req);
+        let data = req.app_data::<Data<AppData>>().unwrap().clone();
+        let (req, payload) = req.into_parts();
 
         Box::pin(async move {
-            let res = fut.await?;
-
-            // get host
-            let host = res.request().headers().get("host");
-
-            // custom domain
-            // if host.is_some()
-            //     && site_host.is_ok()
-            //     && std::str::from_utf8(host.as_ref().unwrap().as_bytes()).unwrap()
-            //         != site_host.as_ref().unwrap()
-            // {
-            //     dbg!(&site_host);
-            //     // implement get_project_by_custom_domain
-            //     // return error if not found
-            // }
-            // // subdomain
-            // else
-            if host.is_some() && site_host.is_ok()
-            // && std::str::from_utf8(host.as_ref().unwrap().as_bytes())
-            //     .unwrap()
-            //     .contains(".get.")
-            {
-                let site_host = site_host.unwrap();
-
-                // serve project asset
-                let data = res
-                    .request()
-                    .app_data::<actix_web::web::Data<crate::db::AppData>>()
-                    .unwrap();
-
-                // ...
-                let host = std::str::from_utf8(host.as_ref().unwrap().as_bytes()).unwrap();
-                // let host_split = host.split(".get.").collect::<Vec<&str>>();
-                let host_split = host.split(&format!(".{site_host}")).collect::<Vec<&str>>();
-
-                let project = host_split.get(0);
-                if project.is_some() {
-                    let project = project
-                        .unwrap()
-                        .replace("https://", "")
-                        .replace("http://", "");
-
-                    let project = project.as_str();
-
-                    // make sure project is not the host and is not "www"
-                    if [host, "www", ""].contains(&project) {
-                        return Ok(res.map_into_left_body());
-                    }
-
-                    // ...
-                    let mut path = res.request().path().to_string();
-
-                    // check path
-                    if path == "/" {
-                        path = String::from("/index.html");
-                    } else if !path.starts_with("/") {
-                        path = format!("/{}", path);
-                    }
-
-                    // verify auth status
-                    let (set_cookie, _, token_user) =
-                        base::check_auth_status_with_cookie(cookie, data.clone()).await;
-
-                    // fetch asset
-                    let file = data
-                        .db
-                        .get_file_in_project(
-                            project.to_string(),
-                            path.clone(),
-                            if token_user.is_some() {
-                                let user = token_user.unwrap().payload.unwrap();
-                                Option::Some(user.user.username)
-                            } else {
-                                Option::None
-                            },
-                            false,
-                            false,
-                        )
-                        .await;
-
-                    if file.success == false {
-                        let new_res = ServiceResponse::new(
-                            res.request().clone(),
-                            HttpResponse::NotAcceptable()
-                                .append_header(("Content-Type", "text/html"))
-                                .body(format!("<!DOCTYPE html>
-
-                                <html lang=\"en\">
-                                    <head>
-                                        <meta charset=\"UTF-8\" />
-                                        <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\" />
-                                        <title>Error! (Vibrant)</title>
-
-                                        <link rel=\"stylesheet\" href=\"//{site_host}/static/style.css\" />
-                                    </head>
-                                
-                                    <body>
-                                        <main class=\"small flex flex-column g-4\">
-                                            <div class=\"card secondary border round full flex justify-center align-center\">
-                                                <h3 class=\"no-margin text-center\">{}</h3>
-                                            </div>
-
-                                            <div class=\"flex justify-center footernav\">
-                                                <span class=\"item\"><a href=\"/\">Root</a></span>
-                                                <span class=\"item\"><a href=\"//{site_host}\">🌸 Homepage</a></span>
-                                                <span class=\"item\"><a href=\"https://code.stellular.org/stellular/vibrant\">Source Code</a></span>
-                                            </div>
-                                        </main>
-                                    </body>
-                                </html>", file.message)),
-                        )
-                        .map_into_right_body();
-
-                        return Ok(new_res);
-                    }
-
-                    data.db.incr_project_requests(project.to_string()).await;
-
-                    // get file extension from path
-                    let ext = file
-                        .message
-                        .split(".")
-                        .collect::<Vec<&str>>()
-                        .pop()
-                        .unwrap_or("txt");
-
-                    // return
-                    let new_res = ServiceResponse::new(
-                        res.request().clone(),
-                        HttpResponse::Ok()
-                            .append_header(("Set-Cookie", set_cookie))
-                            .append_header(("Content-Type", file_extension_to_mime(ext)))
-                            .body(file.payload.unwrap()),
-                    )
-                    .map_into_right_body();
-
-                    return Ok(new_res);
-                }
+            let response = serve_project(
+                &req,
+                payload,
+                data,
+                project.unwrap(),
+                cookie,
+                site_host.unwrap(),
+            )
+            .await;
+
+            Ok(ServiceResponse::new(req, response).map_into_right_body())
+        })
+    }
+}
+
+/// Get the name of the project a request is for from its `Host` header (`{project}.{CONTENT_HOST}`)
+///
+/// # Arguments:
+/// * `req` - the request
+/// * `bases` - domains projects are served under (see [`serve::hosts::base_domains`])
+pub fn project_from_host(req: &HttpRequest, bases: &[String]) -> Option<String> {
+    let host = req.headers().get("host")?.to_str().ok()?;
+
+    // custom domain
+    // TODO: implement get_project_by_custom_domain
+
+    // subdomain
+    serve::hosts::resolve(host, bases)
+}
+
+/// Serve a request for a file in a project
+///
+/// # Arguments:
+/// * `req` - the request
+/// * `payload` - the request body (only read to unlock protected projects)
+/// * `data` - [`AppData`]
+/// * `name` - project name (see [`project_from_host`])
+/// * `cookie` - the request's `__Secure-Token` cookie
+/// * `site_host` - `HOST` of the server
+pub async fn serve_project(
+    req: &HttpRequest,
+    payload: Payload,
+    data: Data<AppData>,
+    name: String,
+    cookie: Option<Cookie<'static>>,
+    site_host: String,
+) -> HttpResponse {
+    // get project
+    let existing = data.db.get_project_by_host_name(name.clone()).await;
+
+    if existing.success == false {
+        return serve::errors::error_response(
+            &data.db,
+            Option::None,
+            StatusCode::NOT_FOUND,
+            "Project does not exist!",
+            &site_host,
+        )
+        .await;
+    }
+
+    let project = existing.payload.unwrap();
+
+    // cors preflight (preflight requests never carry credentials, so they're answered before any checks)
+    if serve::cors::is_preflight(req) {
+        return serve::cors::preflight(&project.metadata.cors, req);
+    }
+
+    let cors = project.metadata.cors.clone();
+    let access_log = project.metadata.access_log.clone();
+    let project_name = project.name.clone();
+    let timestamp = dorsal::utility::unix_epoch_timestamp();
+
+    let mut response =
+        serve_loaded_project(req, payload, data.clone(), project, cookie, site_host).await;
+
+    // custom headers (matched against the file that was served, or the request path for redirects
+    // and error pages)
+    let rules = data.db.get_project_header_rules(project_name.clone()).await;
+    let served = response
+        .extensions()
+        .get::<serve::headers::ServedPath>()
+        .map(|p| p.0.clone())
+        .unwrap_or(req.path().to_string());
+
+    serve::headers::apply(&rules, &served, response.headers_mut());
+
+    // cors
+    serve::cors::apply(&cors, req, response.headers_mut());
+
+    // access log (written in the background so the response isn't held up)
+    if access_log.enabled {
+        let record = serve::access_log::record(req, &response, timestamp);
+
+        actix_web::rt::spawn(async move {
+            data.db.log_access(project_name, &access_log, record).await;
+        });
+    }
+
+    response
+}
+
+/// Serve a request for a file in a [`Project`] that has already been loaded (see [`serve_project`])
+///
+/// # Arguments:
+/// * `req` - the request
+/// * `payload` - the request body (only read to unlock protected projects)
+/// * `data` - [`AppData`]
+/// * `project` - the [`Project`]
+/// * `cookie` - the request's `__Secure-Token` cookie
+/// * `site_host` - `HOST` of the server
+pub async fn serve_loaded_project(
+    req: &HttpRequest,
+    payload: Payload,
+    data: Data<AppData>,
+    project: Project,
+    cookie: Option<Cookie<'static>>,
+    site_host: String,
+) -> HttpResponse {
+    let name = project.name.clone();
+
+    // verify auth status (only private, protected and offline projects care who is asking)
+    let privacy = &project.metadata.file_privacy;
+    let maintenance = &project.metadata.maintenance;
+    let (set_cookie, as_user) = if (privacy == &ProjectFilePrivacy::Private)
+        | (privacy == &ProjectFilePrivacy::Protected)
+        | maintenance.enabled
+    {
+        let (set_cookie, _, token_user) =
+            base::check_auth_status_with_cookie(cookie, data.clone()).await;
+
+        if token_user.is_some() {
+            let user = token_user.unwrap().payload.unwrap();
+            (set_cookie, Option::Some(user.user.username))
+        } else {
+            (set_cookie, Option::None)
+        }
+    } else {
+        (String::new(), Option::None)
+    };
+
+    // maintenance mode (the owner and allowed users still see the project)
+    if maintenance.enabled {
+        let can_bypass = as_user
+            .as_ref()
+            .is_some_and(|u| maintenance.allowed_users.contains(u))
+            || data.db.can_manage_project(&project, as_user.clone()).await;
+
+        if serve::maintenance::is_down(maintenance, req.path(), can_bypass) {
+            return serve::maintenance::response(&data.db, &project, &site_host).await;
+        }
+    }
+
+    // share links
+    let share = serve::share::token_from_request(req);
+    let share_link = if share.is_some() {
+        data.db.check_share_token(&project, share.as_ref().unwrap())
+    } else {
+        Option::None
+    };
+
+    // password protection (the owner can always view the project, share links skip the password)
+    let mut unlocked = false;
+
+    if privacy == &ProjectFilePrivacy::Protected {
+        unlocked = as_user.as_ref() == Option::Some(&project.owner);
+
+        if !unlocked {
+            let token = req.cookie(serve::protection::ACCESS_COOKIE);
+
+            unlocked = token.is_some()
+                && serve::protection::check_access_token(
+                    &data.db.signing_key,
+                    &project.name,
+                    &project.private_metadata.password,
+                    token.unwrap().value(),
+                );
+        }
+
+        if !unlocked && share_link.is_none() {
+            return unlock_project(
+                req,
+                payload,
+                &data,
+                &project.name,
+                &project.private_metadata.password,
+                &site_host,
+            )
+            .await;
+        }
+    }
+
+    let mut path = req.path().to_string();
+
+    // redirects and rewrites (files shadow rules that aren't forced)
+    let rules = data.db.get_project_redirect_rules(name.clone()).await;
+    let mut rewritten = false;
+
+    let file_exists =
+        rules.iter().any(|r| !r.force) && data.db.project_file_exists(&project, &path).await;
+
+    match serve::redirects::resolve(&rules, &path, req.query_string(), file_exists) {
+        Some(serve::redirects::Resolution::Redirect(status, location)) => {
+            data.db.incr_project_requests(name).await;
+
+            return serve::redirect(
+                StatusCode::from_u16(status).unwrap_or(StatusCode::MOVED_PERMANENTLY),
+                location,
+            );
+        }
+        Some(serve::redirects::Resolution::Rewrite(target)) => {
+            path = target;
+            rewritten = true;
+        }
+        None => (),
+    }
+
+    // canonical path (clean paths, trailing slash)
+    if !rewritten {
+        if let Some(canonical) = serve::paths::canonical(
+            &path,
+            project.metadata.clean_paths,
+            &project.metadata.trailing_slash,
+        ) {
+            // files without an extension (`/LICENSE`) are served as is instead of getting a slash
+            let is_file = (canonical == format!("{path}/"))
+                && data
+                    .db
+                    .get_project_file_content(name.clone(), path.clone())
+                    .await
+                    .success;
+
+            if !is_file {
+                let query = req.query_string();
+                let location = if query.is_empty() {
+                    canonical
+                } else {
+                    format!("{canonical}?{query}")
+                };
+
+                data.db.incr_project_requests(name).await;
+                return serve::redirect(StatusCode::MOVED_PERMANENTLY, location);
+            }
+        }
+    }
+
+    // error pages are only taken from the project when the visitor can read them
+    let page_access = || serve::errors::PageAccess {
+        project: &project,
+        as_user: as_user.clone(),
+        share: share.clone(),
+        unlocked,
+    };
+
+    // fetch asset
+    let mut file = data
+        .db
+        .get_file_in_loaded_project(
+            &project,
+            path.clone(),
+            as_user.clone(),
+            share.clone(),
+            unlocked,
+            false,
+        )
+        .await;
+
+    if file.is_err() {
+        let status = serve::errors::status_for(file.as_ref().err().unwrap());
+
+        // generated sitemap and robots file (files in the project take precedence)
+        if (status == StatusCode::NOT_FOUND)
+            && serve::sitemap::is_generated(&project.metadata, &path)
+        {
+            if let Some(base_url) = serve::sitemap::base_url(req) {
+                let content = if path == serve::sitemap::SITEMAP_PATH {
+                    data.db.get_project_sitemap(&project, &base_url).await
+                } else {
+                    serve::sitemap::robots(&project, &base_url)
+                };
+
+                data.db.incr_project_requests(name).await;
+                return serve::sitemap::response(req, &project, &path, content, set_cookie);
             }
+        }
+
+        // single-page apps handle unknown paths themselves
+        if (status == StatusCode::NOT_FOUND) && project.metadata.spa_fallback {
+            file = data
+                .db
+                .get_file_in_loaded_project(
+                    &project,
+                    String::from("/index.html"),
+                    as_user.clone(),
+                    share.clone(),
+                    unlocked,
+                    false,
+                )
+                .await;
+        }
+
+        if file.is_err() {
+            let message = serve::errors::message_for(file.as_ref().err().unwrap(), &name, &path);
+
+            return serve::errors::error_response(
+                &data.db,
+                Option::Some(page_access()),
+                status,
+                &message,
+                &site_host,
+            )
+            .await;
+        }
+    }
 
-            // normal res
-            Ok(res.map_into_left_body())
-        })
+    let (file_path, content) = file.unwrap();
+
+    // hotlink protection
+    let referrer = req
+        .headers()
+        .get(header::REFERER)
+        .and_then(|r| r.to_str().ok());
+
+    let own_host = req
+        .headers()
+        .get("host")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("");
+
+    if !serve::hotlink::is_allowed(&project.metadata.hotlink, &file_path, referrer, own_host) {
+        let substitute = &project.metadata.hotlink.substitute;
+
+        if !substitute.is_empty() {
+            let content = data
+                .db
+                .get_project_file_content(name.clone(), substitute.to_owned())
+                .await;
+
+            if content.success {
+                let mut response = serve::serve_file(
+                    req,
+                    &data.db,
+                    &project,
+                    substitute.to_owned(),
+                    content.payload.unwrap(),
+                    set_cookie,
+                )
+                .await;
+
+                // the substitute depends on who is embedding the file
+                response
+                    .headers_mut()
+                    .append(header::VARY, header::HeaderValue::from_static("Referer"));
+
+                response
+                    .extensions_mut()
+                    .insert(serve::headers::ServedPath(substitute.to_owned()));
+
+                return response;
+            }
+        }
+
+        return serve::errors::error_response(
+            &data.db,
+            Option::Some(page_access()),
+            StatusCode::FORBIDDEN,
+            "This file can't be embedded on other sites.",
+            &site_host,
+        )
+        .await;
+    }
+
+    // image transformations
+    let transform = serve::images::from_query(&file_path, req.query_string());
+
+    if transform.is_err() {
+        return serve::errors::error_response(
+            &data.db,
+            Option::Some(page_access()),
+            StatusCode::BAD_REQUEST,
+            &transform.err().unwrap(),
+            &site_host,
+        )
+        .await;
+    }
+
+    // build response
+    let served = serve::headers::ServedPath(file_path.clone());
+    let mut response = if let Ok(Some(transform)) = transform {
+        serve::serve_image(
+            req, &data.db, &project, file_path, content, transform, set_cookie,
+        )
+        .await
+    } else {
+        serve::serve_file(req, &data.db, &project, file_path, content, set_cookie).await
+    };
+
+    response.extensions_mut().insert(served);
+
+    // remember share link so the page can load its own files
+    let remembered = req
+        .cookie(serve::share::SHARE_COOKIE)
+        .is_some_and(|c| Option::Some(c.value()) == share.as_deref());
+
+    if share_link.is_some() && !remembered {
+        let cookie = serve::share::share_cookie(
+            share.as_ref().unwrap(),
+            share_link.unwrap().expires,
+            dorsal::utility::unix_epoch_timestamp(),
+        );
+
+        if let Ok(value) = header::HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(header::SET_COOKIE, value);
+        }
+    }
+
+    // protected files depend on who is embedding them
+    if project.metadata.hotlink.enabled {
+        response
+            .headers_mut()
+            .append(header::VARY, header::HeaderValue::from_static("Referer"));
     }
+
+    // incr project requests and bandwidth (revalidated files aren't billed)
+    // requests go first, they reset the bandwidth when a new billing month starts
+    if response.status() != StatusCode::NOT_MODIFIED {
+        data.db.incr_project_requests(name.clone()).await;
+
+        if let BodySize::Sized(bytes) = response.body().size() {
+            data.db.incr_project_bandwidth(&name, bytes).await;
+        }
+    }
+
+    // return
+    response
+}
+
+/// Answer a request for a locked [`ProjectFilePrivacy::Protected`] project
+///
+/// `POST` requests with the password form are unlock attempts, everything else gets the password page.
+///
+/// # Arguments:
+/// * `req` - the request
+/// * `payload` - the request body
+/// * `data` - [`AppData`]
+/// * `name` - project name
+/// * `password` - stored password hash of the project
+/// * `site_host` - `HOST` of the server
+pub async fn unlock_project(
+    req: &HttpRequest,
+    mut payload: Payload,
+    data: &Data<AppData>,
+    name: &String,
+    password: &String,
+    site_host: &str,
+) -> HttpResponse {
+    let page = |status: StatusCode, message: &str| {
+        HttpResponse::build(status)
+            .append_header(("Content-Type", "text/html"))
+            .append_header((header::CACHE_CONTROL, "no-store"))
+            .body(serve::protection::password_page(name, message, site_host))
+    };
+
+    if req.method() != Method::POST {
+        return page(StatusCode::UNAUTHORIZED, "");
+    }
+
+    // check lockout
+    let client = serve::security::client_addr(req);
+
+    if data.db.is_unlock_locked(name, &client).await {
+        return page(
+            StatusCode::TOO_MANY_REQUESTS,
+            "Too many attempts, please try again later.",
+        );
+    }
+
+    // check password
+    let form = Form::<HashMap<String, String>>::from_request(req, &mut payload).await;
+    let attempt = match form {
+        Ok(ref form) => form.get(serve::protection::PASSWORD_FIELD),
+        Err(_) => Option::None,
+    };
+
+    // verify (off the async runtime, argon2 is slow on purpose)
+    let verified = match attempt {
+        Option::Some(attempt) => {
+            let (stored, attempt) = (password.to_owned(), attempt.to_owned());
+            actix_web::web::block(move || serve::protection::verify_password(&stored, &attempt))
+                .await
+                .unwrap_or(false)
+        }
+        Option::None => false,
+    };
+
+    if !verified {
+        data.db.record_failed_unlock(name, &client).await;
+        return page(StatusCode::UNAUTHORIZED, "Incorrect password.");
+    }
+
+    // remember access
+    data.db.clear_failed_unlocks(name, &client).await;
+
+    let expires = dorsal::utility::unix_epoch_timestamp() + serve::protection::ACCESS_TIME;
+    let token = serve::protection::access_token(&data.db.signing_key, name, password, expires);
+
+    // send the visitor back to the page they were trying to open
+    HttpResponse::SeeOther()
+        .append_header(("Set-Cookie", serve::protection::access_cookie(&token)))
+        .append_header((header::LOCATION, req.uri().to_string()))
+        .finish()
 }
@@ -0,0 +1,289 @@
+//! Built-in TLS (rustls) with per-domain certificates
+//!
+//! Certificates are read from `TLS_CERT_DIR`, every domain has a certificate chain and a private
+//! key named after it:
+//!
+//! * `example.com.pem` + `example.com.key` - served for `example.com`
+//! * `_.example.com.pem` + `_.example.com.key` - served for any subdomain of `example.com` (`*.example.com`)
+//! * `default.pem` + `default.key` - served when nothing else matches (and to clients without SNI)
+//!
+//! The directory is checked for changes every [`RELOAD_INTERVAL`], so renewed certificates are
+//! picked up without a restart.
+use actix_web::HttpRequest;
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::{self, CertifiedKey};
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+/// How often the certificate directory is checked for changes
+pub const RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Port TLS is served on when `TLS_PORT` is not set
+pub const DEFAULT_TLS_PORT: u16 = 8443;
+
+/// Extension of certificate chains
+const CERT_EXTENSION: &str = "pem";
+
+/// Extension of private keys
+const KEY_EXTENSION: &str = "key";
+
+/// Name of the certificate used when no other certificate matches
+const DEFAULT_NAME: &str = "default";
+
+/// Prefix of wildcard certificate names (`_.example.com` is `*.example.com`)
+const WILDCARD_PREFIX: &str = "_.";
+
+/// Get the certificate directory (`TLS_CERT_DIR`), `None` when TLS is disabled
+pub fn cert_dir() -> Option<PathBuf> {
+    std::env::var("TLS_CERT_DIR")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Get the port TLS is served on (`TLS_PORT`)
+pub fn tls_port() -> u16 {
+    std::env::var("TLS_PORT")
+        .ok()
+        .and_then(|p| p.parse::<u16>().ok())
+        .unwrap_or(DEFAULT_TLS_PORT)
+}
+
+/// Check if plain HTTP requests are redirected to HTTPS (`TLS_REDIRECT`, only when TLS is enabled)
+pub fn redirect_enabled() -> bool {
+    cert_dir().is_some() && std::env::var("TLS_REDIRECT").is_ok_and(|v| (v == "true") | (v == "1"))
+}
+
+/// Get the HTTPS URL a plain HTTP request should be redirected to (see [`redirect_enabled`])
+pub fn https_redirect(req: &HttpRequest) -> Option<String> {
+    if !redirect_enabled() {
+        return Option::None;
+    }
+
+    // tls listener, or a proxy that already terminated tls
+    if req.app_config().secure() || (req.connection_info().scheme() == "https") {
+        return Option::None;
+    }
+
+    let info = req.connection_info();
+    let host = crate::serve::hosts::normalize(info.host())?;
+    let port = tls_port();
+
+    let path = req
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or("/");
+
+    Option::Some(if port == 443 {
+        format!("https://{host}{path}")
+    } else {
+        format!("https://{host}:{port}{path}")
+    })
+}
+
+/// Certificates loaded from the certificate directory
+#[derive(Default)]
+struct Certificates {
+    /// certificates by domain
+    exact: HashMap<String, Arc<CertifiedKey>>,
+    /// wildcard certificates by parent domain (`example.com` for `*.example.com`)
+    wildcard: HashMap<String, Arc<CertifiedKey>>,
+    /// certificate used when nothing else matches
+    default: Option<Arc<CertifiedKey>>,
+}
+
+impl Certificates {
+    fn get(&self, server_name: Option<&str>) -> Option<Arc<CertifiedKey>> {
+        if let Some(name) = server_name {
+            let name = name.trim_end_matches(".").to_lowercase();
+
+            if let Some(key) = self.exact.get(&name) {
+                return Option::Some(key.clone());
+            }
+
+            // wildcards only cover a single label
+            if let Some((_, parent)) = name.split_once(".") {
+                if let Some(key) = self.wildcard.get(parent) {
+                    return Option::Some(key.clone());
+                }
+            }
+        }
+
+        self.default.clone()
+    }
+}
+
+/// Picks a certificate by the server name (SNI) of each connection
+pub struct CertResolver {
+    /// certificate directory
+    dir: PathBuf,
+    /// loaded certificates, replaced when the directory changes
+    certs: RwLock<Arc<Certificates>>,
+    /// files the certificates were loaded from, with their last modification time
+    loaded: RwLock<Vec<(PathBuf, Option<SystemTime>)>>,
+}
+
+impl CertResolver {
+    /// Load every certificate in `dir`
+    pub fn new(dir: PathBuf) -> std::io::Result<Self> {
+        let resolver = CertResolver {
+            dir,
+            certs: RwLock::new(Arc::new(Certificates::default())),
+            loaded: RwLock::new(Vec::new()),
+        };
+
+        let count = resolver.reload()?;
+
+        if count == 0 {
+            println!(
+                "[tls] no certificates found in {} yet",
+                resolver.dir.display()
+            );
+        }
+
+        Ok(resolver)
+    }
+
+    /// Get the files in the certificate directory with their last modification time
+    fn files(&self) -> std::io::Result<Vec<(PathBuf, Option<SystemTime>)>> {
+        let mut files: Vec<(PathBuf, Option<SystemTime>)> = Vec::new();
+
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+
+            if (ext == CERT_EXTENSION) | (ext == KEY_EXTENSION) {
+                let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+                files.push((path, modified));
+            }
+        }
+
+        files.sort();
+        Ok(files)
+    }
+
+    /// Load every certificate in the directory, returning how many were loaded
+    ///
+    /// Certificates that fail to load are skipped (and logged), the previous certificates are kept
+    /// if the directory can't be read.
+    pub fn reload(&self) -> std::io::Result<usize> {
+        let files = self.files()?;
+        let mut certs = Certificates::default();
+        let mut count: usize = 0;
+
+        for (path, _) in &files {
+            if path.extension().and_then(|e| e.to_str()) != Option::Some(CERT_EXTENSION) {
+                continue;
+            }
+
+            let Some(name) = path.file_stem().and_then(|n| n.to_str()) else {
+                continue;
+            };
+
+            let name = name.to_lowercase();
+            let key = match load_certified_key(path, &path.with_extension(KEY_EXTENSION)) {
+                Ok(key) => Arc::new(key),
+                Err(e) => {
+                    println!("[tls] failed to load {}: {e}", path.display());
+                    continue;
+                }
+            };
+
+            if name == DEFAULT_NAME {
+                certs.default = Option::Some(key);
+            } else if let Some(parent) = name.strip_prefix(WILDCARD_PREFIX) {
+                certs.wildcard.insert(parent.to_string(), key);
+            } else {
+                certs.exact.insert(name, key);
+            }
+
+            count += 1;
+        }
+
+        *self.certs.write().unwrap() = Arc::new(certs);
+        *self.loaded.write().unwrap() = files;
+
+        Ok(count)
+    }
+
+    /// Reload the certificates if any file in the directory changed
+    pub fn reload_if_changed(&self) {
+        let Ok(files) = self.files() else {
+            return;
+        };
+
+        if *self.loaded.read().unwrap() == files {
+            return;
+        }
+
+        match self.reload() {
+            Ok(count) => println!("[tls] reloaded {count} certificates"),
+            Err(e) => println!("[tls] failed to reload certificates: {e}"),
+        }
+    }
+
+    /// Check the directory for changes every [`RELOAD_INTERVAL`] (on a background thread)
+    pub fn watch(self: &Arc<Self>) {
+        let resolver = self.clone();
+
+        std::thread::spawn(move || loop {
+            std::thread::sleep(RELOAD_INTERVAL);
+            resolver.reload_if_changed();
+        });
+    }
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self, client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        let certs = self.certs.read().unwrap().clone();
+        certs.get(client_hello.server_name())
+    }
+}
+
+/// Load a certificate chain and its private key
+///
+/// # Arguments:
+/// * `cert_path` - path of the certificate chain (PEM)
+/// * `key_path` - path of the private key (PEM, PKCS#8, PKCS#1 or SEC1)
+fn load_certified_key(cert_path: &Path, key_path: &Path) -> Result<CertifiedKey, String> {
+    // certificate chain
+    let mut reader = std::io::BufReader::new(fs::File::open(cert_path).map_err(|e| e.to_string())?);
+    let chain: Vec<Certificate> = rustls_pemfile::certs(&mut reader)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+
+    if chain.is_empty() {
+        return Err(String::from("no certificates in file"));
+    }
+
+    // private key
+    let mut reader = std::io::BufReader::new(fs::File::open(key_path).map_err(|e| e.to_string())?);
+    let key = rustls_pemfile::read_all(&mut reader)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => Option::Some(PrivateKey(key)),
+            _ => Option::None,
+        })
+        .ok_or(String::from("no private key in file"))?;
+
+    let key = sign::any_supported_type(&key).map_err(|e| e.to_string())?;
+    Ok(CertifiedKey::new(chain, key))
+}
+
+/// Build the rustls configuration of the server
+pub fn server_config(resolver: Arc<CertResolver>) -> ServerConfig {
+    ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_cert_resolver(resolver)
+}
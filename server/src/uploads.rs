@@ -0,0 +1,274 @@
+//! Resumable chunked uploads (for files too large for a single request)
+//!
+//! 1. `POST /api/v1/project/{name}/uploads` starts an upload session for a path and a size
+//! 2. `PUT /api/v1/project/{name}/uploads/{id}/chunks/{number}` stores a chunk (numbered from `0`),
+//!    chunks can be sent in any order and sent again
+//! 3. `POST /api/v1/project/{name}/uploads/{id}:finish` checks the SHA-256 of the file and stores it
+//!
+//! Every chunk is written to the database as soon as it's received. Clients that lose their
+//! connection can get the session (`GET /api/v1/project/{name}/uploads/{id}`) to see which chunks
+//! are still missing. Finishing an upload appends the chunks (in order) to an [`ASSEMBLED`] row
+//! in pieces of at most [`ASSEMBLE_BUFFER_SIZE`] (see [`Assembler`]), so the whole file is never
+//! held in memory, then moves that row to the project.
+use base64::Engine;
+use sha2::{Digest, Sha256};
+
+use crate::serve::{headers, redirects};
+
+/// Size of every chunk but the last when the client doesn't pick one (1 MiB)
+pub const DEFAULT_CHUNK_SIZE: usize = 1_048_576;
+
+/// Smallest chunk size a client can pick (64 KiB)
+pub const MIN_CHUNK_SIZE: usize = 65_536;
+
+/// Largest chunk size a client can pick (4 MiB)
+pub const MAX_CHUNK_SIZE: usize = 4_194_304;
+
+/// How long (in ms) an upload session can be used for (24 hours)
+pub const UPLOAD_EXPIRY: u128 = 86_400_000;
+
+/// Most unfinished upload sessions a project can have at once
+pub const MAX_UPLOADS: usize = 20;
+
+/// Most chunks an upload can be split into
+pub const MAX_CHUNKS: usize = 128;
+
+/// Chunk "number" of the row a finished upload is assembled in (next to the upload's chunks)
+pub const ASSEMBLED: &str = "file";
+
+/// How much base64 (16 MiB) is collected before it's appended to the [`ASSEMBLED`] row
+///
+/// Every append rewrites the whole row, so appending in large pieces keeps a 100 MiB file to a
+/// handful of rewrites instead of one per chunk.
+pub const ASSEMBLE_BUFFER_SIZE: usize = 16_777_216;
+
+/// Get the number of chunks a file of `size` bytes is split into
+pub fn chunk_count(size: usize, chunk_size: usize) -> usize {
+    if size == 0 {
+        return 1;
+    }
+
+    size.div_ceil(chunk_size)
+}
+
+/// Get the expected length of chunk `number`, `None` if the file doesn't have this chunk
+///
+/// # Arguments:
+/// * `size` - file size
+/// * `chunk_size` - size of every chunk but the last
+/// * `number` - chunk number (starting at `0`)
+pub fn chunk_len(size: usize, chunk_size: usize, number: usize) -> Option<usize> {
+    if number >= chunk_count(size, chunk_size) {
+        return Option::None;
+    }
+
+    Option::Some((size - number * chunk_size).min(chunk_size))
+}
+
+/// Check a chunk size picked by a client, returning why it can't be used
+pub fn check_chunk_size(chunk_size: usize) -> Result<(), String> {
+    if !(MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE).contains(&chunk_size) {
+        return Err(format!(
+            "Chunk size must be between {MIN_CHUNK_SIZE} and {MAX_CHUNK_SIZE} bytes"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check the number of chunks a file is split into, returning why it can't be used
+pub fn check_chunk_count(size: usize, chunk_size: usize) -> Result<(), String> {
+    if chunk_count(size, chunk_size) > MAX_CHUNKS {
+        return Err(format!(
+            "Uploads can have at most {MAX_CHUNKS} chunks, use a larger chunk size"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check a file path sent by a client (including its leading slash), returning why it can't be used
+pub fn check_file_path(path: &str) -> Result<(), String> {
+    if (path == "/") | path.ends_with("/") {
+        return Err(format!("\"{path}\" is not a file path"));
+    }
+
+    if path.contains("//") | path.split("/").any(|s| (s == ".") | (s == "..")) {
+        return Err(String::from(
+            "File paths can't have empty, \".\" or \"..\" segments",
+        ));
+    }
+
+    if path.chars().any(|c| c.is_control()) {
+        return Err(String::from("File paths can't have control characters"));
+    }
+
+    Ok(())
+}
+
+/// Check the path an upload is stored at, returning why it can't be used (see [`check_file_path`])
+///
+/// Rules files (`_headers`, `_redirects`) are checked when they're stored, so they can't be
+/// uploaded in chunks.
+pub fn check_path(path: &str) -> Result<(), String> {
+    check_file_path(path)?;
+
+    if headers::is_headers_file(path) | (path == redirects::REDIRECTS_FILE) {
+        return Err(String::from("Rules files can't be uploaded in chunks"));
+    }
+
+    Ok(())
+}
+
+/// Check if `checksum` (hex, any case) is the SHA-256 `digest` (see [`Assembler::finish`])
+pub fn checksum_matches(digest: &str, checksum: &str) -> bool {
+    digest == checksum.trim().to_lowercase()
+}
+
+/// Hashes and base64 encodes a file one chunk at a time
+///
+/// Bytes that don't fill a base64 quantum (3 bytes) are carried over to the next chunk, so the
+/// pieces joined together are the same as the whole file encoded at once.
+#[derive(Default)]
+pub struct Assembler {
+    hasher: Sha256,
+    carry: Vec<u8>,
+    /// bytes pushed so far
+    pub size: usize,
+}
+
+impl Assembler {
+    /// Add the next chunk, returning the base64 that can already be written
+    pub fn push(&mut self, bytes: &[u8]) -> String {
+        self.hasher.update(bytes);
+        self.size += bytes.len();
+        self.carry.extend_from_slice(bytes);
+
+        let whole = self.carry.len() - (self.carry.len() % 3);
+        let out = base64::engine::general_purpose::STANDARD.encode(&self.carry[..whole]);
+
+        self.carry.drain(..whole);
+        out
+    }
+
+    /// Get the rest of the base64 (with padding) and the SHA-256 (hex) of the whole file
+    pub fn finish(self) -> (String, String) {
+        (
+            base64::engine::general_purpose::STANDARD.encode(&self.carry),
+            format!("{:x}", self.hasher.finalize()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic bytes that aren't a repeating pattern
+    fn bytes(len: usize) -> Vec<u8> {
+        let mut state: u32 = 0x1234_5678;
+
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    fn sha256(bytes: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(bytes))
+    }
+
+    #[test]
+    fn counts_chunks() {
+        assert_eq!(chunk_count(0, 4), 1);
+        assert_eq!(chunk_count(4, 4), 1);
+        assert_eq!(chunk_count(5, 4), 2);
+        assert_eq!(chunk_len(10, 4, 0), Some(4));
+        assert_eq!(chunk_len(10, 4, 2), Some(2));
+        assert_eq!(chunk_len(10, 4, 3), None);
+        assert_eq!(chunk_len(0, 4, 0), Some(0));
+
+        assert!(check_chunk_count(MAX_CHUNKS * MIN_CHUNK_SIZE, MIN_CHUNK_SIZE).is_ok());
+        assert!(check_chunk_count(MAX_CHUNKS * MIN_CHUNK_SIZE + 1, MIN_CHUNK_SIZE).is_err());
+    }
+
+    #[test]
+    fn checks_paths() {
+        for path in ["/a.txt", "/assets/app.js", "/.well-known/x", "/a..b"] {
+            assert!(check_path(path).is_ok(), "{path} should be allowed");
+        }
+
+        for path in [
+            "/", "/assets/", "//a.txt", "/a//b", "/./a", "/a/../b", "/..", "/a\nb", "/a\u{0}",
+        ] {
+            assert!(
+                check_file_path(path).is_err(),
+                "{path:?} shouldn't be allowed"
+            );
+            assert!(check_path(path).is_err(), "{path:?} shouldn't be allowed");
+        }
+
+        // rules files are only checked when they're stored in a single request
+        for path in ["/_redirects", "/_headers"] {
+            assert!(check_file_path(path).is_ok(), "{path} should be allowed");
+            assert!(check_path(path).is_err(), "{path} can't be uploaded");
+        }
+    }
+
+    #[test]
+    fn matches_checksums() {
+        let digest = sha256(b"hello");
+
+        assert!(checksum_matches(&digest, &digest));
+        assert!(checksum_matches(
+            &digest,
+            &format!(" {} ", digest.to_uppercase())
+        ));
+        assert!(!checksum_matches(&digest, &sha256(b"hello!")));
+        assert!(!checksum_matches(&digest, ""));
+    }
+
+    #[test]
+    fn assembles_like_a_single_encode() {
+        // chunk sizes that are (and aren't) multiples of a base64 quantum
+        for file_len in [0, 1, 2, 3, 4, 5, 64, 65, 100, 1000] {
+            for chunk_size in [1, 2, 3, 4, 5, 7, 8, 64, 1001] {
+                let file = bytes(file_len);
+                let mut assembler = Assembler::default();
+                let mut out = String::new();
+
+                for chunk in file.chunks(chunk_size) {
+                    out.push_str(&assembler.push(chunk));
+                }
+
+                assert_eq!(assembler.size, file_len);
+
+                let (rest, digest) = assembler.finish();
+                out.push_str(&rest);
+
+                assert_eq!(
+                    out,
+                    base64::engine::general_purpose::STANDARD.encode(&file),
+                    "file of {file_len} bytes in chunks of {chunk_size}"
+                );
+                assert_eq!(digest, sha256(&file));
+            }
+        }
+    }
+
+    #[test]
+    fn pushes_whole_quanta() {
+        let mut assembler = Assembler::default();
+
+        // nothing can be written until 3 bytes are in
+        assert_eq!(assembler.push(&[1]), "");
+        assert_eq!(assembler.push(&[2]), "");
+        assert_eq!(assembler.push(&[3, 4]), "AQID");
+        assert_eq!(assembler.push(&[]), "");
+
+        let (rest, _) = assembler.finish();
+        assert_eq!(rest, "BA==");
+    }
+}
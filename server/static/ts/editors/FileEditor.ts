@@ -36,7 +36,12 @@ import { javascript } from "@codemirror/lang-javascript";
 import { css, cssCompletionSource } from "@codemirror/lang-css";
 import { tags } from "@lezer/highlight";
 
-import { linter, Diagnostic, lintGutter } from "@codemirror/lint";
+import {
+    linter,
+    Diagnostic,
+    lintGutter,
+    setDiagnostics,
+} from "@codemirror/lint";
 
 // prettier
 // @ts-ignore
@@ -167,6 +172,46 @@ export const EmptyLint = linter((view) => {
     return diagnostics;
 });
 
+/**
+ * @function RulesDiagnostics
+ *
+ * Turn the errors of a rules file (`_headers`, `_redirects`) the server refused into diagnostics
+ * on their lines ("Failed to parse /_redirects: line 3: ...; line 5: ...")
+ *
+ * @param {EditorView} view
+ * @param {string} message
+ * @return {Diagnostic[]}
+ */
+export function RulesDiagnostics(
+    view: EditorView,
+    message: string
+): Diagnostic[] {
+    let diagnostics: Diagnostic[] = [];
+
+    if (!message.startsWith("Failed to parse ")) return diagnostics;
+    const errors = message.slice(message.indexOf(": ") + 2).split("; ");
+
+    for (const error of errors) {
+        const matched = error.match(/^line (\d+): (.*)$/);
+        if (!matched) continue;
+
+        const number = parseInt(matched[1]);
+        if (number < 1 || number > view.state.doc.lines) continue;
+
+        const line = view.state.doc.line(number);
+
+        diagnostics.push({
+            from: line.from,
+            to: line.to,
+            severity: "error",
+            message: `${matched[2]} (${number}:1)`,
+        });
+    }
+
+    // return
+    return diagnostics;
+}
+
 // create completion context
 
 /**
@@ -423,6 +468,8 @@ export function create_editor(
         });
     }
 
+    let has_rules_errors: boolean = false;
+
     (globalThis as any).save_editor_state = async () => {
         const res = await fetch(
             `/api/v1/project/${project_name}/files/${path}`,
@@ -441,8 +488,16 @@ export function create_editor(
 
         const json = await res.json();
 
+        // show rules file errors on their lines (cleared once the file saves)
+        const diagnostics = RulesDiagnostics(view, json.message || "");
+
+        if (diagnostics.length > 0 || has_rules_errors) {
+            view.dispatch(setDiagnostics(view.state, diagnostics));
+            has_rules_errors = diagnostics.length > 0;
+        }
+
         if (json.success === false) {
-            return alert(json.message);
+            return alert(json.message.replaceAll("; line ", "\nline "));
         } else {
             set_has_unused_changes(false);
             // return alert("File saved");
@@ -49,12 +49,47 @@ export function project_settings(
                     <option value="Private" ${
                         meta_value === "Private" ? "selected" : ""
                     }>Private</option>
+
+                    <option value="Protected" ${
+                        meta_value === "Protected" ? "selected" : ""
+                    }>Protected</option>
+                </select>`;
+
+                options = build_options(metadata, current_property);
+                render_project_settings_fields(field, options, option_render); // rerender
+                return;
+            } else if (current_property === "trailing_slash") {
+                let meta_value = metadata[current_property];
+
+                (globalThis as any).set_trailing_slash = (e: any) => {
+                    const selected = (
+                        e.target.options[
+                            e.target.selectedIndex
+                        ] as HTMLOptionElement
+                    ).value;
+
+                    metadata[current_property] = selected;
+                };
+
+                // add button
+                option_render = `<select class="round mobile:max" onchange="window.set_trailing_slash(event);" style="width: 60%;">
+                    <option value="Ignore" ${
+                        meta_value === "Ignore" ? "selected" : ""
+                    }>Ignore</option>
+
+                    <option value="Add" ${
+                        meta_value === "Add" ? "selected" : ""
+                    }>Add</option>
+
+                    <option value="Strip" ${
+                        meta_value === "Strip" ? "selected" : ""
+                    }>Strip</option>
                 </select>`;
 
                 options = build_options(metadata, current_property);
                 render_project_settings_fields(field, options, option_render); // rerender
                 return;
-            } else if (current_property === "clean_paths") {
+            } else if (typeof metadata[current_property] === "boolean") {
                 let meta_value = metadata[current_property];
 
                 (globalThis as any).set_yes_no_option = (e: any) => {
@@ -105,6 +140,25 @@ export function project_settings(
                 (window as any).project_settings_field_input = (e: any) => {
                     metadata[current_property] = e.target.value;
                 };
+            } else if (typeof meta_value === "object") {
+                // lists and objects (rules) are edited as JSON
+                option_render = `<textarea 
+                    name="${current_property}" 
+                    placeholder="${current_property}" 
+                    oninput="window.project_settings_json_input(event);" 
+                    class="round mobile:max"
+                    style="width: 60%; min-height: 10rem; font-family: monospace;"
+                >${JSON.stringify(meta_value, null, 4).replace(/</g, "&lt;")}</textarea>`;
+
+                (window as any).project_settings_json_input = (e: any) => {
+                    try {
+                        metadata[current_property] = JSON.parse(e.target.value);
+                        e.target.style.outline = "";
+                    } catch {
+                        // keep the last valid value until the JSON parses again
+                        e.target.style.outline = "solid 1px red";
+                    }
+                };
             }
         }
 
@@ -219,22 +219,6 @@ const create_button: HTMLButtonElement | null = document.getElementById(
     "create_file"
 ) as HTMLButtonElement | null;
 
-function base64_file(input: File): Promise<String> {
-    return new Promise((resolve, reject) => {
-        const reader = new FileReader();
-
-        reader.addEventListener("loadend", () => {
-            resolve(
-                (reader.result as String)
-                    .replace("data:", "")
-                    .replace(/^.+,/, "")
-            );
-        });
-
-        reader.readAsDataURL(input);
-    });
-}
-
 if (upload_button) {
     // upload file
     upload_button.addEventListener("click", async (e) => {
@@ -246,10 +230,10 @@ if (upload_button) {
         file_input.click();
 
         file_input.addEventListener("change", async () => {
-            const file_base64 = await base64_file(file_input.files![0]);
+            const file = file_input.files![0];
 
             // get path
-            const file_path = prompt("File path:");
+            const file_path = prompt("File path:", `/${file.name}`);
             if (!file_path) return;
 
             file_input.remove();
@@ -264,16 +248,19 @@ if (upload_button) {
                 {
                     method: "POST",
                     headers: {
-                        "Content-Type": "application/json",
+                        "Content-Type": "application/octet-stream",
                     },
-                    body: JSON.stringify({
-                        content: file_base64,
-                    }),
+                    body: file,
                 }
             );
 
             loading_modal.close();
 
+            if (!res.ok) {
+                alert(await res.text());
+                return;
+            }
+
             const json = await res.json();
 
             if (json.success === false) {
@@ -285,6 +272,72 @@ if (upload_button) {
     });
 }
 
+// drag and drop upload
+const drop_zone: HTMLDivElement | null = document.getElementById(
+    "drop_zone"
+) as HTMLDivElement | null;
+
+if (drop_zone) {
+    drop_zone.addEventListener("dragover", (e) => {
+        e.preventDefault();
+        drop_zone.classList.add("dragging");
+    });
+
+    drop_zone.addEventListener("dragleave", () => {
+        drop_zone.classList.remove("dragging");
+    });
+
+    drop_zone.addEventListener("drop", async (e) => {
+        e.preventDefault();
+        drop_zone.classList.remove("dragging");
+
+        const files = e.dataTransfer?.files;
+        if (!files || files.length === 0) return;
+
+        // get directory
+        const directory = prompt("Upload to directory:", "/");
+        if (!directory) return;
+
+        // every file is stored at the directory joined with its field name
+        const body = new FormData();
+
+        for (const file of Array.from(files)) {
+            body.append(file.name, file, file.name);
+        }
+
+        // ...
+        loading_modal_inner.innerHTML = `<b>Uploading ${files.length} file(s)!</b> Please wait.`;
+        loading_modal.showModal();
+
+        const res = await fetch(
+            `${drop_zone.getAttribute("data-endpoint")!}/${directory.replace(/^\/+/, "")}`,
+            {
+                method: "POST",
+                body,
+            }
+        );
+
+        loading_modal.close();
+
+        if (!res.ok) {
+            alert(await res.text());
+            return;
+        }
+
+        const json = await res.json();
+
+        if (json.success === false) {
+            const failed = (json.payload || [])
+                .filter((r: any) => r.success === false)
+                .map((r: any) => r.message);
+
+            alert(`${json.message}\n\n${failed.join("\n")}`);
+        }
+
+        window.location.reload();
+    });
+}
+
 if (create_button && upload_button) {
     // create file
     create_button.addEventListener("click", async (e) => {
@@ -330,6 +383,291 @@ if (create_button && upload_button) {
     });
 }
 
+// share links
+const share_button: HTMLButtonElement | null = document.getElementById(
+    "create_share_link"
+) as HTMLButtonElement | null;
+
+const rotate_button: HTMLButtonElement | null = document.getElementById(
+    "rotate_share_key"
+) as HTMLButtonElement | null;
+
+const share_links_table: HTMLTableElement | null = document.getElementById(
+    "share_links"
+) as HTMLTableElement | null;
+
+if (share_button && rotate_button && share_links_table) {
+    const endpoint = share_button.getAttribute("data-endpoint")!;
+    const project = share_button.getAttribute("data-project")!;
+    const host = share_button.getAttribute("data-host") || window.location.host;
+
+    const share_url = (link: { path: string; token: string }): string => {
+        return `${window.location.protocol}//${project}.${host}${link.path}?share=${link.token}`;
+    };
+
+    // list links
+    const res = await fetch(endpoint);
+    const json = await res.json();
+
+    if (json.success === true) {
+        const tbody = share_links_table.querySelector("tbody")!;
+
+        for (const link of json.payload) {
+            const row = document.createElement("tr");
+
+            const path = document.createElement("td");
+            const path_link = document.createElement("a");
+            path_link.href = share_url(link);
+            path_link.target = "_blank";
+            path_link.innerText = link.path;
+            path.appendChild(path_link);
+
+            const expires = document.createElement("td");
+            expires.innerText = new Date(link.expires).toLocaleString();
+
+            const actions = document.createElement("td");
+            actions.className = "flex g-4 flex-wrap";
+
+            const copy = document.createElement("button");
+            copy.className = "secondary round";
+            copy.innerText = "Copy";
+            copy.addEventListener("click", () => {
+                navigator.clipboard.writeText(share_url(link));
+            });
+
+            const revoke = document.createElement("button");
+            revoke.className = "round red";
+            revoke.innerText = "Revoke";
+            revoke.addEventListener("click", async () => {
+                if (!confirm("Are you sure you want to revoke this link?")) {
+                    return;
+                }
+
+                const res = await fetch(`${endpoint}/${link.id}`, {
+                    method: "DELETE",
+                });
+
+                const json = await res.json();
+
+                if (json.success === false) {
+                    alert(json.message);
+                } else {
+                    row.remove();
+                }
+            });
+
+            actions.append(copy, revoke);
+            row.append(path, expires, actions);
+            tbody.appendChild(row);
+        }
+    }
+
+    // create link
+    share_button.addEventListener("click", async (e) => {
+        e.preventDefault();
+
+        const path = prompt(
+            "Path to share (end with \"/\" to share a directory):",
+            "/"
+        );
+
+        if (!path) return;
+
+        const days = prompt("Days until the link expires (at most 90):", "7");
+        if (!days) return;
+
+        const res = await fetch(endpoint, {
+            method: "POST",
+            headers: {
+                "Content-Type": "application/json",
+            },
+            body: JSON.stringify({
+                path,
+                duration: Math.round(parseFloat(days) * 86_400_000),
+            }),
+        });
+
+        const json = await res.json();
+
+        if (json.success === false) {
+            alert(json.message);
+        } else {
+            prompt("Share link:", share_url(json.payload));
+            window.location.reload();
+        }
+    });
+
+    // revoke all links
+    rotate_button.addEventListener("click", async (e) => {
+        e.preventDefault();
+
+        if (
+            !confirm(
+                "Are you sure you want to revoke every share link? It cannot be undone."
+            )
+        ) {
+            return;
+        }
+
+        const res = await fetch(rotate_button.getAttribute("data-endpoint")!, {
+            method: "POST",
+        });
+
+        const json = await res.json();
+
+        if (json.success === false) {
+            alert(json.message);
+        } else {
+            window.location.reload();
+        }
+    });
+}
+
+// previews
+const preview_button: HTMLButtonElement | null = document.getElementById(
+    "create_preview"
+) as HTMLButtonElement | null;
+
+const previews_table: HTMLTableElement | null = document.getElementById(
+    "previews"
+) as HTMLTableElement | null;
+
+if (preview_button && previews_table) {
+    const endpoint = preview_button.getAttribute("data-endpoint")!;
+    const host =
+        preview_button.getAttribute("data-host") || window.location.host;
+
+    const preview_url = (preview: { project: string }): string => {
+        return `${window.location.protocol}//${preview.project}.${host}`;
+    };
+
+    // list previews
+    const res = await fetch(endpoint);
+    const json = await res.json();
+
+    if (json.success === true) {
+        const tbody = previews_table.querySelector("tbody")!;
+
+        for (const preview of json.payload) {
+            const row = document.createElement("tr");
+
+            const name = document.createElement("td");
+            const name_link = document.createElement("a");
+            name_link.href = preview_url(preview);
+            name_link.target = "_blank";
+            name_link.innerText = preview.name;
+            name.appendChild(name_link);
+
+            const privacy = document.createElement("td");
+            privacy.innerText = preview.file_privacy;
+
+            const actions = document.createElement("td");
+            actions.className = "flex g-4 flex-wrap";
+
+            const manage = document.createElement("a");
+            manage.className = "button secondary round";
+            manage.href = `/dashboard/project/${preview.project}`;
+            manage.innerText = "Files";
+
+            const promote = document.createElement("button");
+            promote.className = "theme:primary round";
+            promote.innerText = "Promote";
+            promote.addEventListener("click", async () => {
+                if (
+                    !confirm(
+                        "Are you sure you want to replace the project's files with this preview? It cannot be undone."
+                    )
+                ) {
+                    return;
+                }
+
+                loading_modal_inner.innerHTML =
+                    "<b>Promoting preview!</b> Please wait.";
+                loading_modal.showModal();
+
+                const res = await fetch(`${endpoint}/${preview.name}:promote`, {
+                    method: "POST",
+                });
+
+                loading_modal.close();
+
+                const json = await res.json();
+
+                if (json.success === false) {
+                    alert(json.message);
+                } else {
+                    window.location.reload();
+                }
+            });
+
+            const remove = document.createElement("button");
+            remove.className = "round red";
+            remove.innerText = "Delete";
+            remove.addEventListener("click", async () => {
+                if (
+                    !confirm(
+                        "Are you sure you want to delete this preview? It cannot be undone."
+                    )
+                ) {
+                    return;
+                }
+
+                const res = await fetch(`${endpoint}/${preview.name}`, {
+                    method: "DELETE",
+                });
+
+                const json = await res.json();
+
+                if (json.success === false) {
+                    alert(json.message);
+                } else {
+                    row.remove();
+                }
+            });
+
+            actions.append(manage, promote, remove);
+            row.append(name, privacy, actions);
+            tbody.appendChild(row);
+        }
+    }
+
+    // create preview
+    preview_button.addEventListener("click", async (e) => {
+        e.preventDefault();
+
+        const name = prompt("Preview name:");
+        if (!name) return;
+
+        const is_private = confirm(
+            "Make the preview's files private? (only you can view them)"
+        );
+
+        loading_modal_inner.innerHTML = "<b>Creating preview!</b> Please wait.";
+        loading_modal.showModal();
+
+        const res = await fetch(endpoint, {
+            method: "POST",
+            headers: {
+                "Content-Type": "application/json",
+            },
+            body: JSON.stringify({
+                name,
+                private: is_private,
+            }),
+        });
+
+        loading_modal.close();
+
+        const json = await res.json();
+
+        if (json.success === false) {
+            alert(json.message);
+        } else {
+            window.location.href = `/dashboard/project/${json.payload.project}`;
+        }
+    });
+}
+
 // live url
 const live_url = document.getElementById(
     "live_url"
@@ -346,7 +684,9 @@ if (live_url && live_url_1) {
     live_url.href = `/${live_url.getAttribute("data-project")!}`;
     live_url.innerText = live_url.href;
 
-    live_url_1.href = `${window.location.protocol}//${live_url.getAttribute("data-project")!}.${window.location.host}`;
+    live_url_1.href = `${window.location.protocol}//${live_url.getAttribute("data-project")!}.${
+        live_url_1.getAttribute("data-host") || window.location.host
+    }`;
     live_url_1.innerText = live_url_1.href;
 }
 